@@ -1,14 +1,26 @@
+use std::future;
+use std::time::Duration;
+
+use base64::engine::{general_purpose, Engine};
 use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::{stream, StreamExt, TryStreamExt};
 
 use crate::{
+    audio_format,
     Client,
     config::Config,
-    error::OpenAIError,
+    error::{map_deserialization_error, OpenAIError},
+    speech_batch::{LongSpeechResponse, SpeechBatchOptions, SpeechSegment},
+    subtitle::Subtitle,
+    transcription_batch::{self, TranscriptionBatchOptions},
     types::{
-        CreateSpeechRequest, CreateSpeechResponse, CreateTranscriptionRequest,
-        CreateTranscriptionResponseJson, CreateTranscriptionResponseVerboseJson,
-        CreateTranslationRequest, CreateTranslationResponseJson,
-        CreateTranslationResponseVerboseJson,
+        AudioInput, AudioResponseFormat, CreateSpeechRequest, CreateSpeechResponse,
+        CreateTranscriptionRequest, CreateTranscriptionResponseJson,
+        CreateTranscriptionResponseVerboseJson, CreateTranslationRequest,
+        CreateTranslationResponseJson, CreateTranslationResponseVerboseJson, InputSource,
+        SpeechResponseFormat, SpeechResponseStream, SpeechStreamEvent, SpeechStreamFormat,
+        TranscriptionResponseStream, TranscriptionStreamEvent,
     },
 };
 
@@ -28,6 +40,7 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateTranscriptionRequest,
     ) -> Result<CreateTranscriptionResponseJson, OpenAIError> {
+        self.client.validate_request(&request)?;
         self.client
             .post_form("/audio/transcriptions", request)
             .await
@@ -38,6 +51,7 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateTranscriptionRequest,
     ) -> Result<CreateTranscriptionResponseVerboseJson, OpenAIError> {
+        self.client.validate_request(&request)?;
         self.client
             .post_form("/audio/transcriptions", request)
             .await
@@ -48,11 +62,127 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateTranscriptionRequest,
     ) -> Result<Bytes, OpenAIError> {
+        self.client.validate_request(&request)?;
         self.client
             .post_form_raw("/audio/transcriptions", request)
             .await
     }
 
+    /// Transcribes audio into the input language, streaming the text as it is transcribed. Not supported by
+    /// `whisper-1`.
+    pub async fn transcribe_stream(
+        &self,
+        mut request: CreateTranscriptionRequest,
+    ) -> Result<TranscriptionResponseStream, OpenAIError> {
+        request.stream = Some(true);
+        self.client.validate_request(&request)?;
+        let events = self
+            .client
+            .post_form_response("/audio/transcriptions", request)
+            .await?
+            .bytes_stream()
+            .eventsource()
+            .map(|event| match event {
+                Err(e) => Err(OpenAIError::StreamError(e.to_string())),
+                Ok(event) => serde_json::from_str::<TranscriptionStreamEvent>(&event.data)
+                    .map_err(|e| map_deserialization_error(e, event.data.as_bytes())),
+            });
+        Ok(Box::pin(events))
+    }
+
+    /// Transcribes audio into SubRip subtitles, overriding the `response_format` of the request.
+    pub async fn transcribe_srt(
+        &self,
+        mut request: CreateTranscriptionRequest,
+    ) -> Result<Subtitle, OpenAIError> {
+        request.response_format = Some(AudioResponseFormat::Srt);
+        let bytes = self.transcribe_raw(request).await?;
+        Subtitle::parse_srt(&String::from_utf8_lossy(&bytes))
+    }
+
+    /// Transcribes audio into WebVTT subtitles, overriding the `response_format` of the request.
+    pub async fn transcribe_vtt(
+        &self,
+        mut request: CreateTranscriptionRequest,
+    ) -> Result<Subtitle, OpenAIError> {
+        request.response_format = Some(AudioResponseFormat::Vtt);
+        let bytes = self.transcribe_raw(request).await?;
+        Subtitle::parse_vtt(&String::from_utf8_lossy(&bytes))
+    }
+
+    /// Transcribes WAV or raw PCM audio of any length, see [crate::transcription_batch].
+    ///
+    /// The chunks are transcribed with the parameters of the request as `verbose_json`, and the `prompt` of the
+    /// request is the prompt of the first chunk. The merged transcription has the timestamps of the whole audio.
+    pub async fn transcribe_long(
+        &self,
+        mut request: CreateTranscriptionRequest,
+        options: &TranscriptionBatchOptions,
+    ) -> Result<CreateTranscriptionResponseVerboseJson, OpenAIError> {
+        let (filename, audio) = match std::mem::take(&mut request.file).source {
+            InputSource::Bytes { filename, bytes } => (filename, bytes),
+            InputSource::VecU8 { filename, vec } => (filename, vec.into()),
+        };
+        let chunks = options.split(&audio)?;
+        if chunks.is_empty() {
+            return Err(OpenAIError::InvalidArgument(
+                "audio must not be empty".into(),
+            ));
+        }
+        let duration = chunks.last().map(|chunk| chunk.end).unwrap_or_default();
+        let stem = filename
+            .rsplit_once('.')
+            .map_or(filename.as_str(), |(stem, _)| stem);
+        request.response_format = Some(AudioResponseFormat::VerboseJson);
+        let requests: Vec<CreateTranscriptionRequest> = chunks
+            .iter()
+            .map(|chunk| CreateTranscriptionRequest {
+                file: AudioInput::from_bytes(
+                    format!("{stem}-{}.wav", chunk.index),
+                    chunk.wav.clone(),
+                ),
+                stream: None,
+                ..request.clone()
+            })
+            .collect();
+        for request in &requests {
+            self.client.validate_request(request)?;
+        }
+
+        let transcriptions = if options.previous_text_prompt {
+            let mut transcriptions: Vec<CreateTranscriptionResponseVerboseJson> = vec![];
+            for mut request in requests {
+                if let Some(previous) = transcriptions.last() {
+                    request.prompt = Some(transcription_batch::chunk_prompt(
+                        request.prompt.as_deref(),
+                        &previous.text,
+                    ));
+                }
+                let transcription = options
+                    .requests
+                    .retry(|| self.transcribe_verbose_json(request.clone()))
+                    .await?;
+                transcriptions.push(transcription);
+            }
+            transcriptions
+        } else {
+            stream::iter(&requests)
+                .map(|request| {
+                    options
+                        .requests
+                        .retry(|| self.transcribe_verbose_json(request.clone()))
+                })
+                .buffered(options.requests.concurrency.max(1))
+                .try_collect()
+                .await?
+        };
+        Ok(transcription_batch::merge(
+            &chunks,
+            transcriptions,
+            duration,
+        ))
+    }
+
     /// Translates audio into English.
     pub async fn translate(
         &self,
@@ -84,8 +214,119 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateSpeechRequest,
     ) -> Result<CreateSpeechResponse, OpenAIError> {
+        self.client.validate_request(&request)?;
         let bytes = self.client.post_raw("/audio/speech", request).await?;
 
         Ok(CreateSpeechResponse { bytes })
     }
+
+    /// Generates audio from an input of any length, see [crate::speech_batch].
+    ///
+    /// The segments are synthesized concurrently with the voice, model and other parameters of the request, and
+    /// joined in the requested `response_format`, which must be `mp3`, `opus`, `wav` or `pcm`.
+    /// The `stream_format` of the request is ignored.
+    pub async fn speech_long(
+        &self,
+        request: CreateSpeechRequest,
+        options: &SpeechBatchOptions,
+    ) -> Result<LongSpeechResponse, OpenAIError> {
+        let response_format = request.response_format.unwrap_or_default();
+        if matches!(
+            response_format,
+            SpeechResponseFormat::Aac | SpeechResponseFormat::Flac
+        ) {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{response_format:?} audio cannot be joined, use mp3, opus, wav or pcm"
+            )));
+        }
+        let ranges = options.split(&request.input);
+        if ranges.is_empty() {
+            return Err(OpenAIError::InvalidArgument(
+                "input must not be empty".into(),
+            ));
+        }
+
+        let requests: Vec<CreateSpeechRequest> = ranges
+            .iter()
+            .map(|range| CreateSpeechRequest {
+                input: request.input[range.clone()].to_string(),
+                stream_format: None,
+                ..request.clone()
+            })
+            .collect();
+        for request in &requests {
+            self.client.validate_request(request)?;
+        }
+
+        let parts: Vec<Bytes> = stream::iter(&requests)
+            .map(|request| {
+                options
+                    .requests
+                    .retry(|| async { Ok(self.speech(request.clone()).await?.bytes) })
+            })
+            .buffered(options.requests.concurrency.max(1))
+            .try_collect()
+            .await?;
+
+        let durations = audio_format::durations(response_format, &parts)?;
+        let mut offset = Duration::ZERO;
+        let mut segments = Vec::with_capacity(parts.len());
+        let segment_durations = ranges.into_iter().zip(requests).zip(durations);
+        for (index, ((range, request), duration)) in segment_durations.enumerate() {
+            segments.push(SpeechSegment {
+                index,
+                text: request.input,
+                range,
+                offset,
+                duration,
+            });
+            offset += duration;
+        }
+
+        Ok(LongSpeechResponse {
+            bytes: audio_format::concat(response_format, &parts)?,
+            response_format,
+            segments,
+        })
+    }
+
+    /// Generates audio from the input text, yielding the bytes as they arrive to start playback early.
+    ///
+    /// With `stream_format` set to [SpeechStreamFormat::Sse], the audio chunks of the events are decoded, so the
+    /// stream yields the same bytes in both formats. Chunks of `pcm` audio may end within a sample, see
+    /// [crate::stream::OpenAIStreamExt::pcm_frames].
+    pub async fn speech_stream(
+        &self,
+        request: CreateSpeechRequest,
+    ) -> Result<SpeechResponseStream, OpenAIError> {
+        self.client.validate_request(&request)?;
+        let sse = request.stream_format == Some(SpeechStreamFormat::Sse);
+        let bytes = self
+            .client
+            .post_response("/audio/speech", request)
+            .await?
+            .bytes_stream();
+        if !sse {
+            return Ok(Box::pin(bytes.map_err(OpenAIError::Reqwest)));
+        }
+
+        let audio = bytes.eventsource().filter_map(|event| {
+            future::ready(match event {
+                Err(e) => Some(Err(OpenAIError::StreamError(e.to_string()))),
+                Ok(event) => match serde_json::from_str::<SpeechStreamEvent>(&event.data) {
+                    Ok(SpeechStreamEvent::AudioDelta { audio }) => Some(
+                        general_purpose::STANDARD
+                            .decode(audio)
+                            .map(Bytes::from)
+                            .map_err(|e| {
+                                OpenAIError::StreamError(format!("invalid base64 audio: {e}"))
+                            }),
+                    ),
+                    Ok(SpeechStreamEvent::AudioDone { .. }) => None,
+                    Err(e) => Some(Err(map_deserialization_error(e, event.data.as_bytes()))),
+                },
+            })
+        });
+        Ok(Box::pin(audio))
+    }
 }
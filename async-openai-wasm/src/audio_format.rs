@@ -0,0 +1,518 @@
+//! Durations and lossless concatenation of the audio returned by [crate::Audio::speech], see [concat].
+//!
+//! WAV files are joined by rewriting the header around the joined samples, `pcm` is joined as is, MP3 by joining
+//! the frames without tags and Xing headers, and Opus by renumbering the Ogg pages into one logical stream.
+//! AAC and FLAC are not supported.
+//!
+//! ```
+//! use async_openai_wasm::audio_format::{concat, duration, wav_header};
+//! use async_openai_wasm::types::SpeechResponseFormat;
+//! use std::time::Duration;
+//!
+//! // 0.5 seconds of silence in 16-bit mono at 24 kHz
+//! let mut wav = wav_header(1, 24_000, 16, 24_000);
+//! wav.extend([0; 24_000]);
+//!
+//! let joined = concat(SpeechResponseFormat::Wav, &[&wav, &wav]).unwrap();
+//! assert_eq!(duration(SpeechResponseFormat::Wav, &joined).unwrap(), Duration::from_secs(1));
+//! ```
+use std::ops::Range;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::error::OpenAIError;
+use crate::stream::pcm_duration;
+use crate::types::SpeechResponseFormat;
+
+/// The duration of an audio file in one of the formats supported by [concat].
+pub fn duration(format: SpeechResponseFormat, bytes: &[u8]) -> Result<Duration, OpenAIError> {
+    match format {
+        SpeechResponseFormat::Pcm => Ok(pcm_duration(bytes.len())),
+        SpeechResponseFormat::Wav => Ok(Wav::parse(bytes)?.duration()),
+        SpeechResponseFormat::Mp3 => {
+            let frames = mp3_frames(bytes)?;
+            Ok(frames.iter().map(|frame| frame.duration()).sum())
+        }
+        SpeechResponseFormat::Opus => {
+            let stream = OggOpus::parse(bytes)?;
+            Ok(stream.duration())
+        }
+        format => Err(unsupported(format)),
+    }
+}
+
+/// The durations of the parts of [concat] in the joined audio.
+///
+/// These are the durations of the parts, except for Opus where the pre-skip is only dropped at the start of the
+/// joined stream, so that the following parts last slightly longer.
+pub fn durations<B: AsRef<[u8]>>(
+    format: SpeechResponseFormat,
+    parts: &[B],
+) -> Result<Vec<Duration>, OpenAIError> {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| match format {
+            SpeechResponseFormat::Opus if index > 0 => {
+                let samples = OggOpus::parse(part.as_ref())?.end_granule();
+                Ok(Duration::from_secs_f64(samples as f64 / 48_000.0))
+            }
+            format => duration(format, part.as_ref()),
+        })
+        .collect()
+}
+
+/// Joins audio files of the same format into one, keeping the header of the first.
+pub fn concat<B: AsRef<[u8]>>(
+    format: SpeechResponseFormat,
+    parts: &[B],
+) -> Result<Bytes, OpenAIError> {
+    match format {
+        SpeechResponseFormat::Pcm => Ok(parts
+            .iter()
+            .flat_map(|part| part.as_ref().iter().copied())
+            .collect::<Vec<u8>>()
+            .into()),
+        SpeechResponseFormat::Wav => concat_wav(parts),
+        SpeechResponseFormat::Mp3 => concat_mp3(parts),
+        SpeechResponseFormat::Opus => concat_opus(parts),
+        format => Err(unsupported(format)),
+    }
+}
+
+fn unsupported(format: SpeechResponseFormat) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!(
+        "{format:?} audio cannot be joined, use mp3, opus, wav or pcm"
+    ))
+}
+
+fn invalid(format: &str, message: &str) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!("invalid {format} audio: {message}"))
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// The `fmt ` tag of integer PCM.
+const WAVE_FORMAT_PCM: u16 = 1;
+/// The `fmt ` tag of a format given by the subformat GUID of the extended `fmt ` chunk.
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// The format and samples of a WAV file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wav {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// The byte range of the samples in the file.
+    pub data: Range<usize>,
+}
+
+impl Wav {
+    /// Reads the `fmt ` and `data` chunks of a WAV file.
+    ///
+    /// Only 8, 16, 24 and 32-bit integer PCM is supported, other formats like floating point samples are rejected.
+    ///
+    /// Streamed WAV files have a placeholder size in their header, so a `data` chunk claiming more bytes than
+    /// the file has extends to the end of the file.
+    pub fn parse(bytes: &[u8]) -> Result<Self, OpenAIError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(invalid("wav", "no RIFF/WAVE header"));
+        }
+        let mut format = None;
+        let mut position = 12;
+        while position + 8 <= bytes.len() {
+            let id = &bytes[position..position + 4];
+            let size = u32_at(bytes, position + 4) as usize;
+            let body = position + 8;
+            if id == b"fmt " {
+                if size < 16 || body + 16 > bytes.len() {
+                    return Err(invalid("wav", "truncated fmt chunk"));
+                }
+                let mut tag = u16_at(bytes, body);
+                // the first two bytes of the subformat GUID are the tag of the actual format
+                if tag == WAVE_FORMAT_EXTENSIBLE {
+                    if size < 40 || body + 26 > bytes.len() {
+                        return Err(invalid("wav", "truncated fmt chunk"));
+                    }
+                    tag = u16_at(bytes, body + 24);
+                }
+                format = Some((
+                    tag,
+                    u16_at(bytes, body + 2),
+                    u32_at(bytes, body + 4),
+                    u16_at(bytes, body + 14),
+                ));
+            } else if id == b"data" {
+                let (tag, channels, sample_rate, bits_per_sample) =
+                    format.ok_or_else(|| invalid("wav", "data chunk before fmt chunk"))?;
+                if channels == 0 || sample_rate == 0 || bits_per_sample == 0 {
+                    return Err(invalid("wav", "empty format"));
+                }
+                if tag != WAVE_FORMAT_PCM || !matches!(bits_per_sample, 8 | 16 | 24 | 32) {
+                    return Err(invalid(
+                        "wav",
+                        &format!(
+                            "format {tag} with {bits_per_sample} bits per sample is not integer PCM"
+                        ),
+                    ));
+                }
+                let end = body.saturating_add(size).min(bytes.len());
+                return Ok(Self {
+                    channels,
+                    sample_rate,
+                    bits_per_sample,
+                    data: body..end,
+                });
+            }
+            position = body.saturating_add(size).saturating_add(size & 1);
+        }
+        Err(invalid("wav", "no data chunk"))
+    }
+
+    /// The number of bytes per second of audio.
+    pub fn byte_rate(&self) -> u32 {
+        self.sample_rate * self.block_align() as u32
+    }
+
+    /// The number of bytes of a sample of every channel.
+    pub fn block_align(&self) -> u16 {
+        self.channels * ((self.bits_per_sample + 7) / 8)
+    }
+
+    /// The duration of the samples.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.data.len() as f64 / self.byte_rate() as f64)
+    }
+}
+
+/// The 44 byte header of a WAV file of `data_len` bytes of integer samples.
+pub fn wav_header(channels: u16, sample_rate: u32, bits_per_sample: u16, data_len: u32) -> Vec<u8> {
+    let block_align = channels * ((bits_per_sample + 7) / 8);
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&data_len.saturating_add(36).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&channels.to_le_bytes());
+    header.extend_from_slice(&sample_rate.to_le_bytes());
+    header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&bits_per_sample.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_len.to_le_bytes());
+    header
+}
+
+fn concat_wav<B: AsRef<[u8]>>(parts: &[B]) -> Result<Bytes, OpenAIError> {
+    let wavs = parts
+        .iter()
+        .map(|part| Wav::parse(part.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let Some(first) = wavs.first() else {
+        return Ok(Bytes::new());
+    };
+    let format = |wav: &Wav| (wav.channels, wav.sample_rate, wav.bits_per_sample);
+    if wavs.iter().any(|wav| format(wav) != format(first)) {
+        return Err(invalid("wav", "parts have different formats"));
+    }
+
+    let data_len: usize = wavs.iter().map(|wav| wav.data.len()).sum();
+    let data_len =
+        u32::try_from(data_len).map_err(|_| invalid("wav", "joined audio exceeds 4 GiB"))?;
+    let mut joined = wav_header(
+        first.channels,
+        first.sample_rate,
+        first.bits_per_sample,
+        data_len,
+    );
+    for (part, wav) in parts.iter().zip(&wavs) {
+        joined.extend_from_slice(&part.as_ref()[wav.data.clone()]);
+    }
+    Ok(joined.into())
+}
+
+/// An MPEG audio frame.
+#[derive(Debug, Clone)]
+struct Mp3Frame {
+    range: Range<usize>,
+    samples: u32,
+    sample_rate: u32,
+    /// A Xing or Info frame, which holds no audio but the frame count of the file.
+    info: bool,
+}
+
+impl Mp3Frame {
+    fn duration(&self) -> Duration {
+        if self.info {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(self.samples as f64 / self.sample_rate as f64)
+        }
+    }
+}
+
+/// The length, samples, sample rate and side information size of the Layer III frame starting with `header`.
+fn mp3_frame_header(header: &[u8]) -> Option<(usize, u32, u32, usize)> {
+    const MPEG1_BITRATES: [u32; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+    const MPEG2_BITRATES: [u32; 15] =
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    const SAMPLE_RATES: [u32; 3] = [44_100, 48_000, 32_000];
+
+    if header.len() < 4 || header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    let version = (header[1] >> 3) & 3;
+    let layer = (header[1] >> 1) & 3;
+    let bitrate = (header[2] >> 4) as usize;
+    let sample_rate = ((header[2] >> 2) & 3) as usize;
+    if version == 1 || layer != 1 || bitrate == 0 || bitrate == 15 || sample_rate == 3 {
+        return None;
+    }
+    let mpeg1 = version == 3;
+    let (bitrate, samples) = if mpeg1 {
+        (MPEG1_BITRATES[bitrate] * 1000, 1152)
+    } else {
+        (MPEG2_BITRATES[bitrate] * 1000, 576)
+    };
+    let sample_rate = SAMPLE_RATES[sample_rate] >> (3 - version).min(2);
+    let padding = ((header[2] >> 1) & 1) as usize;
+    let len = (samples / 8 * bitrate / sample_rate) as usize + padding;
+    let mono = header[3] >> 6 == 3;
+    let side_info = match (mpeg1, mono) {
+        (true, true) | (false, false) => 17,
+        (true, false) => 32,
+        (false, true) => 9,
+    };
+    Some((len, samples, sample_rate, side_info))
+}
+
+/// The frames of an MP3 file, skipping ID3 tags and any bytes between frames.
+fn mp3_frames(bytes: &[u8]) -> Result<Vec<Mp3Frame>, OpenAIError> {
+    let mut position = 0;
+    let mut frames = vec![];
+    while position + 4 <= bytes.len() {
+        if &bytes[position..position + 3] == b"ID3" && position + 10 <= bytes.len() {
+            let size = bytes[position + 6..position + 10]
+                .iter()
+                .fold(0usize, |size, byte| (size << 7) | (byte & 0x7F) as usize);
+            let footer = if bytes[position + 5] & 0x10 != 0 {
+                10
+            } else {
+                0
+            };
+            position += 10 + size + footer;
+            continue;
+        }
+        match mp3_frame_header(&bytes[position..]) {
+            Some((len, samples, sample_rate, side_info)) if position + len <= bytes.len() => {
+                let tag = position + 4 + side_info;
+                let info = frames.is_empty()
+                    && bytes
+                        .get(tag..tag + 4)
+                        .map_or(false, |tag| tag == b"Xing" || tag == b"Info");
+                frames.push(Mp3Frame {
+                    range: position..position + len,
+                    samples,
+                    sample_rate,
+                    info,
+                });
+                position += len;
+            }
+            Some(_) => break,
+            None => position += 1,
+        }
+    }
+    if frames.iter().all(|frame| frame.info) {
+        return Err(invalid("mp3", "no audio frames"));
+    }
+    Ok(frames)
+}
+
+fn concat_mp3<B: AsRef<[u8]>>(parts: &[B]) -> Result<Bytes, OpenAIError> {
+    let mut joined = Vec::with_capacity(parts.iter().map(|part| part.as_ref().len()).sum());
+    for part in parts {
+        let part = part.as_ref();
+        for frame in mp3_frames(part)?.into_iter().filter(|frame| !frame.info) {
+            joined.extend_from_slice(&part[frame.range]);
+        }
+    }
+    Ok(joined.into())
+}
+
+/// The granule position of pages on which no packet ends.
+const NO_GRANULE: u64 = u64::MAX;
+
+/// A page of an Ogg stream.
+struct OggPage<'a> {
+    header_type: u8,
+    granule: u64,
+    segments: &'a [u8],
+    body: &'a [u8],
+}
+
+/// The pages of an Ogg Opus stream, and its pre-skip in samples at 48 kHz.
+struct OggOpus<'a> {
+    pages: Vec<OggPage<'a>>,
+    pre_skip: u64,
+    /// The number of pages of the `OpusHead` and `OpusTags` headers.
+    header_pages: usize,
+}
+
+impl<'a> OggOpus<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, OpenAIError> {
+        let mut pages = vec![];
+        let mut position = 0;
+        while position < bytes.len() {
+            let page = &bytes[position..];
+            if page.len() < 27 || &page[0..4] != b"OggS" {
+                return Err(invalid("opus", "truncated Ogg page"));
+            }
+            let segment_count = page[26] as usize;
+            let body = 27 + segment_count;
+            let len = body
+                + page[27..body.min(page.len())]
+                    .iter()
+                    .map(|&segment| segment as usize)
+                    .sum::<usize>();
+            if page.len() < len {
+                return Err(invalid("opus", "truncated Ogg page"));
+            }
+            pages.push(OggPage {
+                header_type: page[5],
+                granule: u64::from_le_bytes(page[6..14].try_into().unwrap()),
+                segments: &page[27..body],
+                body: &page[body..len],
+            });
+            position += len;
+        }
+
+        let head = pages
+            .first()
+            .map(|page| page.body)
+            .filter(|head| head.len() >= 12 && head.starts_with(b"OpusHead"))
+            .ok_or_else(|| invalid("opus", "no OpusHead"))?;
+        let pre_skip = u16_at(head, 10) as u64;
+        // the headers end on pages of their own, before the first page with audio
+        let header_pages = pages
+            .iter()
+            .position(|page| page.granule != 0)
+            .unwrap_or(pages.len())
+            .max(1);
+        Ok(Self {
+            pages,
+            pre_skip,
+            header_pages,
+        })
+    }
+
+    /// The granule position of the last page, the number of samples at 48 kHz including the pre-skip.
+    fn end_granule(&self) -> u64 {
+        self.pages
+            .iter()
+            .rev()
+            .map(|page| page.granule)
+            .find(|&granule| granule != NO_GRANULE)
+            .unwrap_or(0)
+    }
+
+    fn duration(&self) -> Duration {
+        let samples = self.end_granule().saturating_sub(self.pre_skip);
+        Duration::from_secs_f64(samples as f64 / 48_000.0)
+    }
+}
+
+/// The CRC of Ogg pages, polynomial 0x04C11DB7 without reflection.
+const OGG_CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn ogg_crc(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |crc, &byte| {
+        (crc << 8) ^ OGG_CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+/// Joins Ogg Opus streams into one logical stream: the headers of the first stream are kept, the pages of the
+/// following streams get the serial number of the first, continued sequence numbers and granule positions.
+fn concat_opus<B: AsRef<[u8]>>(parts: &[B]) -> Result<Bytes, OpenAIError> {
+    let streams = parts
+        .iter()
+        .map(|part| OggOpus::parse(part.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let serial = parts
+        .first()
+        .map(|part| u32_at(part.as_ref(), 14))
+        .unwrap_or_default();
+
+    let pages: Vec<(&OggPage, u64)> = streams
+        .iter()
+        .enumerate()
+        .scan(0u64, |offset, (index, stream)| {
+            let skip = if index == 0 { 0 } else { stream.header_pages };
+            let pages: Vec<_> = stream.pages[skip..]
+                .iter()
+                .map(|page| (page, *offset))
+                .collect();
+            *offset += stream.end_granule();
+            Some(pages)
+        })
+        .flatten()
+        .collect();
+
+    let mut joined = Vec::with_capacity(parts.iter().map(|part| part.as_ref().len()).sum());
+    let last = pages.len().saturating_sub(1);
+    for (sequence, (page, offset)) in pages.into_iter().enumerate() {
+        let start = joined.len();
+        let mut header_type = page.header_type & 0x01;
+        if sequence == 0 {
+            header_type |= 0x02;
+        }
+        if sequence == last {
+            header_type |= 0x04;
+        }
+        let granule = if page.granule == NO_GRANULE {
+            NO_GRANULE
+        } else {
+            page.granule + offset
+        };
+        joined.extend_from_slice(b"OggS");
+        joined.push(0);
+        joined.push(header_type);
+        joined.extend_from_slice(&granule.to_le_bytes());
+        joined.extend_from_slice(&serial.to_le_bytes());
+        joined.extend_from_slice(&(sequence as u32).to_le_bytes());
+        joined.extend_from_slice(&[0; 4]);
+        joined.push(page.segments.len() as u8);
+        joined.extend_from_slice(page.segments);
+        joined.extend_from_slice(page.body);
+        let crc = ogg_crc(&joined[start..]);
+        joined[start + 22..start + 26].copy_from_slice(&crc.to_le_bytes());
+    }
+    Ok(joined.into())
+}
@@ -0,0 +1,616 @@
+//! What each model supports, so requests can be checked or adapted before they are sent.
+//!
+//! [ModelRegistry] maps model id prefixes to [ModelCapabilities]: context window, maximum output tokens,
+//! supported request parameters, input and output modalities and pricing. The built-in entries cover the
+//! current OpenAI models and can be overridden or extended, e.g. for fine-tuned or self-hosted models.
+//!
+//! With [crate::Client::with_model_registry], [crate::Chat] applies an [UnsupportedParameters] policy to every request:
+//! reject requests using parameters the model does not support, or rewrite them where possible
+//! (`max_tokens` to `max_completion_tokens`, `system` to `developer` messages, dropping unsupported sampling parameters).
+//!
+//! ```
+//! use async_openai_wasm::capabilities::{ModelParameter, ModelRegistry};
+//!
+//! let registry = ModelRegistry::default();
+//! let o3 = registry.get("o3-mini-2025-01-31").unwrap();
+//! assert!(!o3.supports(ModelParameter::Temperature));
+//! assert!(o3.supports(ModelParameter::ReasoningEffort));
+//! ```
+use std::collections::HashSet;
+
+use crate::error::{InvalidArgumentDetail, OpenAIError};
+use crate::types::{
+    ChatCompletionModalities, ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
+    CompletionUsage, CreateChatCompletionRequest, CreateEmbeddingRequest,
+};
+
+/// A request parameter which is only supported by some models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelParameter {
+    Temperature,
+    TopP,
+    PresencePenalty,
+    FrequencyPenalty,
+    LogitBias,
+    Logprobs,
+    /// The deprecated `max_tokens`, replaced by `max_completion_tokens`.
+    MaxTokens,
+    MaxCompletionTokens,
+    N,
+    Stop,
+    Tools,
+    ParallelToolCalls,
+    ResponseFormat,
+    ReasoningEffort,
+    /// Messages with the `system` role.
+    SystemMessages,
+    /// Messages with the `developer` role.
+    DeveloperMessages,
+    Stream,
+    /// `dimensions` of embedding models.
+    Dimensions,
+}
+
+impl ModelParameter {
+    /// The name of the parameter in requests.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelParameter::Temperature => "temperature",
+            ModelParameter::TopP => "top_p",
+            ModelParameter::PresencePenalty => "presence_penalty",
+            ModelParameter::FrequencyPenalty => "frequency_penalty",
+            ModelParameter::LogitBias => "logit_bias",
+            ModelParameter::Logprobs => "logprobs",
+            ModelParameter::MaxTokens => "max_tokens",
+            ModelParameter::MaxCompletionTokens => "max_completion_tokens",
+            ModelParameter::N => "n",
+            ModelParameter::Stop => "stop",
+            ModelParameter::Tools => "tools",
+            ModelParameter::ParallelToolCalls => "parallel_tool_calls",
+            ModelParameter::ResponseFormat => "response_format",
+            ModelParameter::ReasoningEffort => "reasoning_effort",
+            ModelParameter::SystemMessages => "messages",
+            ModelParameter::DeveloperMessages => "messages",
+            ModelParameter::Stream => "stream",
+            ModelParameter::Dimensions => "dimensions",
+        }
+    }
+}
+
+/// Kinds of model input and output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modality {
+    Text,
+    Image,
+    Audio,
+}
+
+/// Price in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    /// Price of cached input tokens, if the model supports prompt caching.
+    pub cached_input: Option<f64>,
+    pub output: f64,
+}
+
+impl ModelPricing {
+    /// Cost in USD of the tokens of a response, ignoring discounts for cached input tokens.
+    pub fn cost(&self, usage: &CompletionUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input + usage.completion_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// What a model supports, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelCapabilities {
+    /// Maximum number of input and output tokens.
+    pub context_window: u32,
+    /// Maximum number of output tokens, including reasoning tokens.
+    pub max_output_tokens: Option<u32>,
+    pub parameters: HashSet<ModelParameter>,
+    pub input_modalities: HashSet<Modality>,
+    pub output_modalities: HashSet<Modality>,
+    pub pricing: Option<ModelPricing>,
+}
+
+impl ModelCapabilities {
+    pub fn supports(&self, parameter: ModelParameter) -> bool {
+        self.parameters.contains(&parameter)
+    }
+
+    /// Chat models accepting text and images, such as `gpt-4o`.
+    pub fn chat(context_window: u32, max_output_tokens: u32) -> Self {
+        use ModelParameter::*;
+        Self {
+            context_window,
+            max_output_tokens: Some(max_output_tokens),
+            parameters: HashSet::from([
+                Temperature,
+                TopP,
+                PresencePenalty,
+                FrequencyPenalty,
+                LogitBias,
+                Logprobs,
+                MaxTokens,
+                MaxCompletionTokens,
+                N,
+                Stop,
+                Tools,
+                ParallelToolCalls,
+                ResponseFormat,
+                SystemMessages,
+                DeveloperMessages,
+                Stream,
+            ]),
+            input_modalities: HashSet::from([Modality::Text, Modality::Image]),
+            output_modalities: HashSet::from([Modality::Text]),
+            pricing: None,
+        }
+    }
+
+    /// Reasoning models, such as `o3`, which reject sampling parameters, `max_tokens` and system messages.
+    pub fn reasoning(context_window: u32, max_output_tokens: u32) -> Self {
+        use ModelParameter::*;
+        Self {
+            context_window,
+            max_output_tokens: Some(max_output_tokens),
+            parameters: HashSet::from([
+                MaxCompletionTokens,
+                Tools,
+                ResponseFormat,
+                ReasoningEffort,
+                DeveloperMessages,
+                Stream,
+            ]),
+            input_modalities: HashSet::from([Modality::Text, Modality::Image]),
+            output_modalities: HashSet::from([Modality::Text]),
+            pricing: None,
+        }
+    }
+
+    /// Embedding models.
+    pub fn embedding(context_window: u32, supports_dimensions: bool) -> Self {
+        Self {
+            context_window,
+            max_output_tokens: None,
+            parameters: if supports_dimensions {
+                HashSet::from([ModelParameter::Dimensions])
+            } else {
+                HashSet::new()
+            },
+            input_modalities: HashSet::from([Modality::Text]),
+            output_modalities: HashSet::new(),
+            pricing: None,
+        }
+    }
+
+    pub fn with_pricing(mut self, input: f64, cached_input: Option<f64>, output: f64) -> Self {
+        self.pricing = Some(ModelPricing {
+            input,
+            cached_input,
+            output,
+        });
+        self
+    }
+
+    pub fn with_parameters<I: IntoIterator<Item = ModelParameter>>(
+        mut self,
+        parameters: I,
+    ) -> Self {
+        self.parameters.extend(parameters);
+        self
+    }
+
+    pub fn without_parameters<I: IntoIterator<Item = ModelParameter>>(
+        mut self,
+        parameters: I,
+    ) -> Self {
+        for parameter in parameters {
+            self.parameters.remove(&parameter);
+        }
+        self
+    }
+
+    pub fn with_modalities<I, O>(mut self, input: I, output: O) -> Self
+    where
+        I: IntoIterator<Item = Modality>,
+        O: IntoIterator<Item = Modality>,
+    {
+        self.input_modalities = input.into_iter().collect();
+        self.output_modalities = output.into_iter().collect();
+        self
+    }
+}
+
+/// How [crate::Chat] handles requests with parameters the model does not support.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnsupportedParameters {
+    /// Send requests as they are.
+    #[default]
+    Ignore,
+    /// Fail with [OpenAIError::InvalidArguments] listing the unsupported parameters.
+    Reject,
+    /// Rewrite requests to fit the model, see [ModelRegistry::adapt_chat_request].
+    /// Parameters which cannot be rewritten are rejected.
+    Rewrite,
+}
+
+/// Capabilities of models keyed by model id prefix, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelRegistry {
+    entries: Vec<(String, ModelCapabilities)>,
+}
+
+impl Default for ModelRegistry {
+    /// The built-in capabilities of OpenAI models.
+    fn default() -> Self {
+        use Modality::*;
+        let mut registry = Self::empty();
+        let entries = [
+            (
+                "gpt-3.5-turbo",
+                ModelCapabilities::chat(16385, 4096)
+                    .with_modalities([Text], [Text])
+                    .with_pricing(0.5, None, 1.5),
+            ),
+            (
+                "gpt-4",
+                ModelCapabilities::chat(8192, 8192)
+                    .with_modalities([Text], [Text])
+                    .with_pricing(30.0, None, 60.0),
+            ),
+            (
+                "gpt-4-turbo",
+                ModelCapabilities::chat(128000, 4096).with_pricing(10.0, None, 30.0),
+            ),
+            (
+                "gpt-4o",
+                ModelCapabilities::chat(128000, 16384).with_pricing(2.5, Some(1.25), 10.0),
+            ),
+            (
+                "gpt-4o-mini",
+                ModelCapabilities::chat(128000, 16384).with_pricing(0.15, Some(0.075), 0.6),
+            ),
+            (
+                "gpt-4o-audio-preview",
+                ModelCapabilities::chat(128000, 16384)
+                    .with_modalities([Text, Audio], [Text, Audio])
+                    .with_pricing(2.5, None, 10.0),
+            ),
+            (
+                "gpt-4o-mini-audio-preview",
+                ModelCapabilities::chat(128000, 16384)
+                    .with_modalities([Text, Audio], [Text, Audio])
+                    .with_pricing(0.15, None, 0.6),
+            ),
+            (
+                "gpt-4.5-preview",
+                ModelCapabilities::chat(128000, 16384).with_pricing(75.0, Some(37.5), 150.0),
+            ),
+            (
+                "gpt-4.1",
+                ModelCapabilities::chat(1047576, 32768).with_pricing(2.0, Some(0.5), 8.0),
+            ),
+            (
+                "gpt-4.1-mini",
+                ModelCapabilities::chat(1047576, 32768).with_pricing(0.4, Some(0.1), 1.6),
+            ),
+            (
+                "gpt-4.1-nano",
+                ModelCapabilities::chat(1047576, 32768).with_pricing(0.1, Some(0.025), 0.4),
+            ),
+            (
+                "o1",
+                ModelCapabilities::reasoning(200000, 100000).with_pricing(15.0, Some(7.5), 60.0),
+            ),
+            (
+                "o1-mini",
+                ModelCapabilities::reasoning(128000, 65536)
+                    .without_parameters([
+                        ModelParameter::Tools,
+                        ModelParameter::ResponseFormat,
+                        ModelParameter::ReasoningEffort,
+                        ModelParameter::DeveloperMessages,
+                    ])
+                    .with_modalities([Text], [Text])
+                    .with_pricing(1.1, Some(0.55), 4.4),
+            ),
+            (
+                "o1-preview",
+                ModelCapabilities::reasoning(128000, 32768)
+                    .without_parameters([
+                        ModelParameter::Tools,
+                        ModelParameter::ResponseFormat,
+                        ModelParameter::ReasoningEffort,
+                        ModelParameter::DeveloperMessages,
+                    ])
+                    .with_modalities([Text], [Text])
+                    .with_pricing(15.0, Some(7.5), 60.0),
+            ),
+            (
+                "o3",
+                ModelCapabilities::reasoning(200000, 100000).with_pricing(2.0, Some(0.5), 8.0),
+            ),
+            (
+                "o3-mini",
+                ModelCapabilities::reasoning(200000, 100000)
+                    .with_modalities([Text], [Text])
+                    .with_pricing(1.1, Some(0.55), 4.4),
+            ),
+            (
+                "o4-mini",
+                ModelCapabilities::reasoning(200000, 100000).with_pricing(1.1, Some(0.275), 4.4),
+            ),
+            (
+                "gpt-5",
+                ModelCapabilities::reasoning(400000, 128000).with_pricing(1.25, Some(0.125), 10.0),
+            ),
+            (
+                "gpt-5-mini",
+                ModelCapabilities::reasoning(400000, 128000).with_pricing(0.25, Some(0.025), 2.0),
+            ),
+            (
+                "gpt-5-nano",
+                ModelCapabilities::reasoning(400000, 128000).with_pricing(0.05, Some(0.005), 0.4),
+            ),
+            // the non-reasoning model behind ChatGPT, e.g. `gpt-5-chat-latest`
+            (
+                "gpt-5-chat",
+                ModelCapabilities::chat(128000, 16384).with_pricing(1.25, Some(0.125), 10.0),
+            ),
+            (
+                "text-embedding-3-small",
+                ModelCapabilities::embedding(8192, true).with_pricing(0.02, None, 0.0),
+            ),
+            (
+                "text-embedding-3-large",
+                ModelCapabilities::embedding(8192, true).with_pricing(0.13, None, 0.0),
+            ),
+            (
+                "text-embedding-ada-002",
+                ModelCapabilities::embedding(8192, false).with_pricing(0.1, None, 0.0),
+            ),
+        ];
+        for (prefix, capabilities) in entries {
+            registry.insert(prefix, capabilities);
+        }
+        registry
+    }
+}
+
+impl ModelRegistry {
+    /// A registry without any entries.
+    pub fn empty() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds or replaces the capabilities of the models whose id starts with `prefix`.
+    pub fn insert<S: Into<String>>(&mut self, prefix: S, capabilities: ModelCapabilities) {
+        let prefix = prefix.into();
+        match self.entries.iter_mut().find(|(p, _)| *p == prefix) {
+            Some(entry) => entry.1 = capabilities,
+            None => self.entries.push((prefix, capabilities)),
+        }
+    }
+
+    /// Capabilities of the entry with the longest prefix of the model id.
+    /// Fine-tuned models (`ft:{base model}:...`) have the capabilities of their base model.
+    pub fn get(&self, model: &str) -> Option<&ModelCapabilities> {
+        let model = model.strip_prefix("ft:").unwrap_or(model);
+        self.entries
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, capabilities)| capabilities)
+    }
+
+    /// Parameters of the request which are not supported by its model. Empty for unknown models.
+    #[allow(deprecated)]
+    pub fn check_chat_request(
+        &self,
+        request: &CreateChatCompletionRequest,
+    ) -> Vec<InvalidArgumentDetail> {
+        let Some(capabilities) = self.get(&request.model) else {
+            return Vec::new();
+        };
+        let model = &request.model;
+        let mut details = Vec::new();
+        let mut unsupported = |parameter: ModelParameter, used: bool| {
+            if used && !capabilities.supports(parameter) {
+                details.push(InvalidArgumentDetail {
+                    param: parameter.name().into(),
+                    message: format!("is not supported by {model}"),
+                });
+            }
+        };
+        use ModelParameter::*;
+        unsupported(Temperature, request.temperature.is_some());
+        unsupported(TopP, request.top_p.is_some());
+        unsupported(PresencePenalty, request.presence_penalty.is_some());
+        unsupported(FrequencyPenalty, request.frequency_penalty.is_some());
+        unsupported(LogitBias, request.logit_bias.is_some());
+        unsupported(
+            Logprobs,
+            request.logprobs.is_some() || request.top_logprobs.is_some(),
+        );
+        unsupported(MaxTokens, request.max_tokens.is_some());
+        unsupported(MaxCompletionTokens, request.max_completion_tokens.is_some());
+        unsupported(N, request.n.is_some());
+        unsupported(Stop, request.stop.is_some());
+        unsupported(
+            Tools,
+            request.tools.is_some() || request.functions.is_some(),
+        );
+        unsupported(ParallelToolCalls, request.parallel_tool_calls.is_some());
+        unsupported(ResponseFormat, request.response_format.is_some());
+        unsupported(ReasoningEffort, request.reasoning_effort.is_some());
+        unsupported(Stream, request.stream == Some(true));
+        let has_role = |developer: bool| {
+            request.messages.iter().any(|message| match message {
+                ChatCompletionRequestMessage::System(_) => !developer,
+                ChatCompletionRequestMessage::Developer(_) => developer,
+                _ => false,
+            })
+        };
+        if has_role(false) && !capabilities.supports(SystemMessages) {
+            details.push(InvalidArgumentDetail {
+                param: "messages".into(),
+                message: format!("system messages are not supported by {model}"),
+            });
+        }
+        if has_role(true) && !capabilities.supports(DeveloperMessages) {
+            details.push(InvalidArgumentDetail {
+                param: "messages".into(),
+                message: format!("developer messages are not supported by {model}"),
+            });
+        }
+
+        let has_images = request.messages.iter().any(|message| {
+            matches!(message, ChatCompletionRequestMessage::User(user)
+                if matches!(&user.content, ChatCompletionRequestUserMessageContent::Array(parts)
+                    if parts.iter().any(|part| matches!(part, ChatCompletionRequestUserMessageContentPart::ImageUrl(_)))))
+        });
+        if has_images && !capabilities.input_modalities.contains(&Modality::Image) {
+            details.push(InvalidArgumentDetail {
+                param: "messages".into(),
+                message: format!("image inputs are not supported by {model}"),
+            });
+        }
+        let audio_output = request
+            .modalities
+            .iter()
+            .flatten()
+            .any(|modality| *modality == ChatCompletionModalities::Audio);
+        let supports_audio_output = capabilities.output_modalities.contains(&Modality::Audio);
+        if audio_output && !supports_audio_output {
+            details.push(InvalidArgumentDetail {
+                param: "modalities".into(),
+                message: format!("audio output is not supported by {model}"),
+            });
+        }
+        if supports_audio_output && request.modalities.is_none() {
+            details.push(InvalidArgumentDetail {
+                param: "modalities".into(),
+                message: format!("is required by {model}"),
+            });
+        }
+        if let (Some(max), Some(requested)) = (
+            capabilities.max_output_tokens,
+            request.max_completion_tokens.or(request.max_tokens),
+        ) {
+            if requested > max {
+                details.push(InvalidArgumentDetail {
+                    param: if request.max_completion_tokens.is_some() {
+                        "max_completion_tokens".into()
+                    } else {
+                        "max_tokens".into()
+                    },
+                    message: format!("{model} generates at most {max} tokens, got {requested}"),
+                });
+            }
+        }
+        details
+    }
+
+    /// Rewrites a request to fit its model, then fails with [OpenAIError::InvalidArguments] if parameters
+    /// which cannot be rewritten are still unsupported. Requests for unknown models are left as they are.
+    ///
+    /// - `max_tokens` is moved to `max_completion_tokens`
+    /// - `system` messages become `developer` messages, and the other way around
+    /// - unsupported sampling parameters (`temperature`, `top_p`, penalties, `logit_bias` and logprobs) are removed
+    pub fn adapt_chat_request(
+        &self,
+        request: &mut CreateChatCompletionRequest,
+    ) -> Result<(), OpenAIError> {
+        let Some(capabilities) = self.get(&request.model) else {
+            return Ok(());
+        };
+        use ModelParameter::*;
+        if request.max_tokens.is_some()
+            && !capabilities.supports(MaxTokens)
+            && capabilities.supports(MaxCompletionTokens)
+        {
+            let max_tokens = request.max_tokens.take();
+            request.max_completion_tokens = request.max_completion_tokens.or(max_tokens);
+        }
+        if !capabilities.supports(SystemMessages) && capabilities.supports(DeveloperMessages) {
+            for message in request.messages.iter_mut() {
+                if let ChatCompletionRequestMessage::System(system) = message {
+                    *message = ChatCompletionRequestMessage::Developer(system.clone().into());
+                }
+            }
+        }
+        if !capabilities.supports(DeveloperMessages) && capabilities.supports(SystemMessages) {
+            for message in request.messages.iter_mut() {
+                if let ChatCompletionRequestMessage::Developer(developer) = message {
+                    *message = ChatCompletionRequestMessage::System(developer.clone().into());
+                }
+            }
+        }
+        if !capabilities.supports(Temperature) {
+            request.temperature = None;
+        }
+        if !capabilities.supports(TopP) {
+            request.top_p = None;
+        }
+        if !capabilities.supports(PresencePenalty) {
+            request.presence_penalty = None;
+        }
+        if !capabilities.supports(FrequencyPenalty) {
+            request.frequency_penalty = None;
+        }
+        if !capabilities.supports(LogitBias) {
+            request.logit_bias = None;
+        }
+        if !capabilities.supports(Logprobs) {
+            request.logprobs = None;
+            request.top_logprobs = None;
+        }
+        reject_unsupported(self.check_chat_request(request))
+    }
+
+    /// Parameters of the request which are not supported by its model. Empty for unknown models.
+    pub fn check_embedding_request(
+        &self,
+        request: &CreateEmbeddingRequest,
+    ) -> Vec<InvalidArgumentDetail> {
+        match self.get(&request.model) {
+            Some(capabilities)
+                if request.dimensions.is_some()
+                    && !capabilities.supports(ModelParameter::Dimensions) =>
+            {
+                vec![InvalidArgumentDetail {
+                    param: "dimensions".into(),
+                    message: format!("is not supported by {}", request.model),
+                }]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Applies the policy to a chat request, see [UnsupportedParameters].
+    pub fn apply_chat_policy(
+        &self,
+        policy: UnsupportedParameters,
+        request: &mut CreateChatCompletionRequest,
+    ) -> Result<(), OpenAIError> {
+        match policy {
+            UnsupportedParameters::Ignore => Ok(()),
+            UnsupportedParameters::Reject => reject_unsupported(self.check_chat_request(request)),
+            UnsupportedParameters::Rewrite => self.adapt_chat_request(request),
+        }
+    }
+}
+
+fn reject_unsupported(details: Vec<InvalidArgumentDetail>) -> Result<(), OpenAIError> {
+    if details.is_empty() {
+        Ok(())
+    } else {
+        Err(OpenAIError::InvalidArguments(details))
+    }
+}
@@ -1,9 +1,15 @@
+use serde::{Serialize, de::DeserializeOwned};
+
 use crate::{
     Client,
     config::Config,
     error::OpenAIError,
+    function_calling::{self, FunctionCallingStyle},
     types::{
-        ChatCompletionResponseStream, CreateChatCompletionRequest, CreateChatCompletionResponse
+        ChatCompletionResponseStream, CreateChatCompletionRequest, CreateChatCompletionResponse,
+        DeleteChatCompletionResponse, ListChatCompletionMessagesResponse,
+        ListChatCompletionsResponse, ParseOptions, ParsedChatCompletion,
+        UpdateChatCompletionRequest,
     },
 };
 
@@ -20,16 +26,55 @@ impl<'c, C: Config> Chat<'c, C> {
     }
 
     /// Creates a model response for the given chat conversation.
+    ///
+    /// Requests are checked or rewritten for their model if enabled by [Client::with_model_registry],
+    /// and rewritten to the [FunctionCallingStyle] of the [Config].
     pub async fn create(
         &self,
-        request: CreateChatCompletionRequest,
+        mut request: CreateChatCompletionRequest,
     ) -> Result<CreateChatCompletionResponse, OpenAIError> {
         if request.stream.is_some() && request.stream.unwrap() {
             return Err(OpenAIError::InvalidArgument(
                 "When stream is true, use Chat::create_stream".into(),
             ));
         }
-        self.client.post("/chat/completions", request).await
+        let style = self.client.config().function_calling_style();
+        let converted = style.convert_request(&mut request)?;
+        self.client.adapt_chat_request(&mut request)?;
+        self.client.validate_request(&request)?;
+        let mut response: CreateChatCompletionResponse =
+            self.client.post("/chat/completions", request).await?;
+        if converted {
+            style.restore_response(&mut response);
+        }
+        Ok(response)
+    }
+
+    /// Creates a model response and parses the message contents into `T`.
+    ///
+    /// Set `response_format` to [crate::types::ResponseFormat::JsonSchema] with a schema matching `T` for the content to be parsed.
+    /// Tool call arguments are parsed into [serde_json::Value], use [Chat::create_parsed_with_tools] to parse them into Rust types.
+    ///
+    /// Returns [OpenAIError::IncompleteResponse] when any choice was cut off, i.e. its `finish_reason` is `length` or `content_filter`.
+    pub async fn create_parsed<T: DeserializeOwned>(
+        &self,
+        request: CreateChatCompletionRequest,
+    ) -> Result<ParsedChatCompletion<T>, OpenAIError> {
+        let options = ParseOptions::from_request(&request);
+        let response = self.create(request).await?;
+        ParsedChatCompletion::parse(response, &options)
+    }
+
+    /// Same as [Chat::create_parsed], additionally parsing tool call arguments into the registered tools `F`.
+    ///
+    /// See [ParsedChatCompletion::parse_with_tools] for how `F` is deserialized.
+    pub async fn create_parsed_with_tools<T: DeserializeOwned, F: DeserializeOwned>(
+        &self,
+        request: CreateChatCompletionRequest,
+    ) -> Result<ParsedChatCompletion<T, F>, OpenAIError> {
+        let options = ParseOptions::from_request(&request);
+        let response = self.create(request).await?;
+        ParsedChatCompletion::parse_with_tools(response, &options)
     }
 
 
@@ -49,7 +94,75 @@ impl<'c, C: Config> Chat<'c, C> {
         }
 
         request.stream = Some(true);
+        let style = self.client.config().function_calling_style();
+        let converted = style.convert_request(&mut request)?;
+        self.client.adapt_chat_request(&mut request)?;
+        self.client.validate_request(&request)?;
 
-        Ok(self.client.post_stream("/chat/completions", request).await)
+        let stream = self.client.post_stream("/chat/completions", request).await;
+        Ok(match style {
+            FunctionCallingStyle::Tools if converted => {
+                stream.with_transform(function_calling::chunk_tools_to_functions)
+            }
+            FunctionCallingStyle::Functions if converted => {
+                stream.with_transform(function_calling::chunk_functions_to_tools)
+            }
+            _ => stream,
+        })
+    }
+
+    /// Lists stored chat completions. Only chat completions that have been created with `store` set to `true` are returned.
+    ///
+    /// See [crate::types::ListChatCompletionsQuery] for the model and metadata filters.
+    pub async fn list<Q>(&self, query: &Q) -> Result<ListChatCompletionsResponse, OpenAIError>
+    where
+        Q: Serialize + ?Sized,
+    {
+        self.client.get_with_query("/chat/completions", query).await
+    }
+
+    /// Retrieves a stored chat completion.
+    pub async fn retrieve(
+        &self,
+        completion_id: &str,
+    ) -> Result<CreateChatCompletionResponse, OpenAIError> {
+        self.client
+            .get(&format!("/chat/completions/{completion_id}"))
+            .await
+    }
+
+    /// Modifies a stored chat completion. Currently, the only supported modification is to update the `metadata` field.
+    pub async fn update(
+        &self,
+        completion_id: &str,
+        request: UpdateChatCompletionRequest,
+    ) -> Result<CreateChatCompletionResponse, OpenAIError> {
+        self.client
+            .post(&format!("/chat/completions/{completion_id}"), request)
+            .await
+    }
+
+    /// Deletes a stored chat completion.
+    pub async fn delete(
+        &self,
+        completion_id: &str,
+    ) -> Result<DeleteChatCompletionResponse, OpenAIError> {
+        self.client
+            .delete(&format!("/chat/completions/{completion_id}"))
+            .await
+    }
+
+    /// Lists the messages of a stored chat completion, see [crate::types::ListChatCompletionMessagesQuery].
+    pub async fn list_messages<Q>(
+        &self,
+        completion_id: &str,
+        query: &Q,
+    ) -> Result<ListChatCompletionMessagesResponse, OpenAIError>
+    where
+        Q: Serialize + ?Sized,
+    {
+        self.client
+            .get_with_query(&format!("/chat/completions/{completion_id}/messages"), query)
+            .await
     }
 }
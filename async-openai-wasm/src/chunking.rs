@@ -0,0 +1,456 @@
+//! Splitting of documents into chunks of a bounded number of tokens, before embedding them or uploading them to
+//! vector stores, see [TextChunker].
+//!
+//! The defaults equal those of the `auto` chunking strategy of vector stores, 800 tokens per chunk of which 400
+//! overlap with the previous chunk, so that chunks embedded locally are comparable to those of a vector store.
+//! Every [Chunk] keeps its byte offsets in the source text and the metadata of its document.
+//!
+//! ```
+//! use async_openai_wasm::chunking::{ChunkingMode, TextChunker};
+//! use serde_json::json;
+//!
+//! let chunker = TextChunker::default()
+//!     .with_mode(ChunkingMode::Markdown)
+//!     .with_max_chunk_size_tokens(100)
+//!     .with_chunk_overlap_tokens(20);
+//!
+//! let text = "# Guide\n\n## Install\n\nRun `cargo add async-openai-wasm`.\n";
+//! let chunks = chunker.split_with_metadata(text, json!({"file": "guide.md"}));
+//! assert_eq!(chunks.len(), 1);
+//! assert_eq!(&text[chunks[0].start..chunks[0].end], chunks[0].text);
+//! assert_eq!(chunks[0].headings, ["Guide"]);
+//! assert_eq!(chunks[0].metadata["file"], "guide.md");
+//! ```
+use std::ops::Range;
+
+use serde_json::Value;
+
+use crate::types::StaticChunkingStrategy;
+
+/// The maximum number of tokens of a chunk in the `auto` chunking strategy of vector stores.
+pub const DEFAULT_MAX_CHUNK_SIZE_TOKENS: usize = 800;
+/// The number of tokens shared by consecutive chunks in the `auto` chunking strategy of vector stores.
+pub const DEFAULT_CHUNK_OVERLAP_TOKENS: usize = 400;
+/// The separators of [ChunkingMode::recursive]: paragraphs, lines, sentences and words.
+pub const DEFAULT_SEPARATORS: &[&str] = &["\n\n", "\n", ". ", " "];
+
+/// How a [TextChunker] chooses chunk boundaries.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ChunkingMode {
+    /// Windows of [TextChunker::max_chunk_size_tokens] tokens, each starting
+    /// [TextChunker::chunk_overlap_tokens] tokens before the end of the previous one.
+    ///
+    /// This is how vector stores chunk files, boundaries may fall within words.
+    #[default]
+    Fixed,
+    /// Splits the text after the first separator, then pieces that are still too long after the next separator,
+    /// down to single tokens. Consecutive pieces are merged into chunks, which overlap by whole pieces.
+    Recursive(Vec<String>),
+    /// Splits Markdown into sections at headings, then into blocks, keeping fenced code blocks whole where they
+    /// fit, then into lines and words. Chunks carry the headings they are in, see [Chunk::headings].
+    Markdown,
+    /// Splits source code into top-level blocks, which start at an unindented line after a blank line, then at
+    /// blank lines, then into lines and words.
+    Code,
+}
+
+impl ChunkingMode {
+    /// [ChunkingMode::Recursive] with the [DEFAULT_SEPARATORS].
+    pub fn recursive() -> Self {
+        ChunkingMode::Recursive(DEFAULT_SEPARATORS.iter().map(|s| s.to_string()).collect())
+    }
+}
+
+/// A part of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// The position of the chunk in the document, starting at 0.
+    pub index: usize,
+    /// The text of the chunk, equal to `&source[start..end]`.
+    pub text: String,
+    /// The byte offset of the start of the chunk in the document.
+    pub start: usize,
+    /// The byte offset of the end of the chunk in the document, exclusive.
+    pub end: usize,
+    /// The number of tokens of the text.
+    pub tokens: usize,
+    /// The titles of the Markdown headings enclosing the start of the chunk, outermost first.
+    /// Empty in other modes than [ChunkingMode::Markdown].
+    pub headings: Vec<String>,
+    /// The metadata of the document, see [TextChunker::split_with_metadata].
+    pub metadata: Value,
+}
+
+/// Splits text into [Chunk]s of at most [Self::max_chunk_size_tokens] tokens.
+///
+/// Tokens are counted with the encoding of [Self::model] if the `tokenizer` feature is enabled, otherwise they are
+/// estimated as one token per three bytes of text.
+/// In the modes other than [ChunkingMode::Fixed], a chunk may exceed the limit by a few tokens, where merging
+/// pieces changes how the text around their boundaries is tokenized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunker {
+    /// The maximum number of tokens of a chunk, 800 by default.
+    pub max_chunk_size_tokens: usize,
+    /// The number of tokens a chunk repeats from the end of the previous one, 400 by default.
+    /// At most `max_chunk_size_tokens - 1` are used.
+    pub chunk_overlap_tokens: usize,
+    /// How chunk boundaries are chosen, [ChunkingMode::Fixed] by default.
+    pub mode: ChunkingMode,
+    /// The model whose tokenizer counts tokens, `text-embedding-3-small` by default.
+    pub model: String,
+}
+
+impl Default for TextChunker {
+    fn default() -> Self {
+        Self {
+            max_chunk_size_tokens: DEFAULT_MAX_CHUNK_SIZE_TOKENS,
+            chunk_overlap_tokens: DEFAULT_CHUNK_OVERLAP_TOKENS,
+            mode: ChunkingMode::default(),
+            model: "text-embedding-3-small".into(),
+        }
+    }
+}
+
+impl From<&StaticChunkingStrategy> for TextChunker {
+    fn from(strategy: &StaticChunkingStrategy) -> Self {
+        Self::default()
+            .with_max_chunk_size_tokens(strategy.max_chunk_size_tokens as usize)
+            .with_chunk_overlap_tokens(strategy.chunk_overlap_tokens as usize)
+    }
+}
+
+impl From<&TextChunker> for StaticChunkingStrategy {
+    /// The sizes of the chunker, saturated to the range of the API.
+    fn from(chunker: &TextChunker) -> Self {
+        StaticChunkingStrategy {
+            max_chunk_size_tokens: chunker.max_chunk_size_tokens.min(u16::MAX as usize) as u16,
+            chunk_overlap_tokens: chunker.chunk_overlap_tokens.min(u16::MAX as usize) as u16,
+        }
+    }
+}
+
+/// Where a piece of text is split, from the coarsest level down.
+#[derive(Debug, Clone, Copy)]
+enum Separator<'a> {
+    /// After every occurrence of the string.
+    After(&'a str),
+    /// Before every Markdown heading outside of fenced code blocks.
+    Headings,
+    /// Between Markdown paragraphs, around fenced code blocks.
+    MarkdownBlocks,
+    /// Before every unindented line following a blank line.
+    CodeBlocks,
+}
+
+impl TextChunker {
+    pub fn with_max_chunk_size_tokens(mut self, max_chunk_size_tokens: usize) -> Self {
+        self.max_chunk_size_tokens = max_chunk_size_tokens;
+        self
+    }
+
+    pub fn with_chunk_overlap_tokens(mut self, chunk_overlap_tokens: usize) -> Self {
+        self.chunk_overlap_tokens = chunk_overlap_tokens;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: ChunkingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// The number of tokens of the text, as counted for chunking.
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.token_ranges(text).len()
+    }
+
+    /// Splits the text into chunks with [Value::Null] metadata.
+    pub fn split(&self, text: &str) -> Vec<Chunk> {
+        self.split_with_metadata(text, Value::Null)
+    }
+
+    /// Splits the text into chunks, each with a copy of `metadata`. Chunks of only whitespace are left out.
+    pub fn split_with_metadata(&self, text: &str, metadata: Value) -> Vec<Chunk> {
+        let ranges = match &self.mode {
+            ChunkingMode::Fixed => self.token_windows(text, 0..text.len(), self.overlap()),
+            ChunkingMode::Recursive(separators) => {
+                let separators: Vec<Separator> = separators
+                    .iter()
+                    .filter(|separator| !separator.is_empty())
+                    .map(|separator| Separator::After(separator))
+                    .collect();
+                self.merge(self.pieces(text, &separators))
+            }
+            ChunkingMode::Markdown => {
+                let separators = [
+                    Separator::Headings,
+                    Separator::MarkdownBlocks,
+                    Separator::After("\n"),
+                    Separator::After(". "),
+                    Separator::After(" "),
+                ];
+                self.merge(self.pieces(text, &separators))
+            }
+            ChunkingMode::Code => {
+                let separators = [
+                    Separator::CodeBlocks,
+                    Separator::After("\n\n"),
+                    Separator::After("\n"),
+                    Separator::After(" "),
+                ];
+                self.merge(self.pieces(text, &separators))
+            }
+        };
+
+        let headings = match self.mode {
+            ChunkingMode::Markdown => markdown_headings(text),
+            _ => vec![],
+        };
+        ranges
+            .into_iter()
+            .filter(|range| !text[range.clone()].trim().is_empty())
+            .enumerate()
+            .map(|(index, range)| Chunk {
+                index,
+                text: text[range.clone()].to_string(),
+                start: range.start,
+                end: range.end,
+                tokens: self.count_tokens(&text[range.clone()]),
+                headings: heading_path(&headings, range.start),
+                metadata: metadata.clone(),
+            })
+            .collect()
+    }
+
+    fn max(&self) -> usize {
+        self.max_chunk_size_tokens.max(1)
+    }
+
+    fn overlap(&self) -> usize {
+        self.chunk_overlap_tokens.min(self.max() - 1)
+    }
+
+    /// Byte ranges of the tokens of the text.
+    fn token_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        #[cfg(feature = "tokenizer")]
+        if let Ok(tokenizer) = crate::tokenizer::Tokenizer::for_model(&self.model) {
+            return tokenizer.token_ranges(text);
+        }
+
+        let mut ranges = vec![];
+        let mut start = 0;
+        while start < text.len() {
+            let mut end = (start + 3).min(text.len());
+            while !text.is_char_boundary(end) {
+                end += 1;
+            }
+            ranges.push(start..end);
+            start = end;
+        }
+        ranges
+    }
+
+    /// Windows of at most [Self::max] tokens over `range`, consecutive windows sharing `overlap` tokens.
+    fn token_windows(&self, text: &str, range: Range<usize>, overlap: usize) -> Vec<Range<usize>> {
+        let tokens = self.token_ranges(&text[range.clone()]);
+        let mut windows = vec![];
+        let mut first = 0;
+        while first < tokens.len() {
+            let last = (first + self.max()).min(tokens.len());
+            windows.push(range.start + tokens[first].start..range.start + tokens[last - 1].end);
+            if last == tokens.len() {
+                break;
+            }
+            first = last - overlap;
+        }
+        windows
+    }
+
+    /// Splits the text into consecutive pieces of at most [Self::max] tokens, with their number of tokens.
+    fn pieces(&self, text: &str, separators: &[Separator]) -> Vec<(Range<usize>, usize)> {
+        let mut pieces = vec![];
+        self.split_range(text, 0..text.len(), separators, &mut pieces);
+        pieces
+    }
+
+    fn split_range(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        separators: &[Separator],
+        pieces: &mut Vec<(Range<usize>, usize)>,
+    ) {
+        let tokens = self.count_tokens(&text[range.clone()]);
+        if tokens <= self.max() {
+            if tokens > 0 {
+                pieces.push((range, tokens));
+            }
+            return;
+        }
+
+        let Some((separator, separators)) = separators.split_first() else {
+            for window in self.token_windows(text, range, 0) {
+                let tokens = self.count_tokens(&text[window.clone()]);
+                pieces.push((window, tokens));
+            }
+            return;
+        };
+        let parts = split_at(text, range.clone(), *separator);
+        if parts.len() == 1 {
+            self.split_range(text, range, separators, pieces);
+        } else {
+            for part in parts {
+                self.split_range(text, part, separators, pieces);
+            }
+        }
+    }
+
+    /// Merges consecutive pieces into chunks of at most [Self::max] tokens. A chunk starts with the last pieces of
+    /// the previous chunk that add up to at most [Self::overlap] tokens.
+    fn merge(&self, pieces: Vec<(Range<usize>, usize)>) -> Vec<Range<usize>> {
+        let (max, overlap) = (self.max(), self.overlap());
+        let mut chunks = vec![];
+        let mut first = 0;
+        let mut tokens = 0;
+        // whether the current chunk has pieces that are not in the previous chunk
+        let mut new = false;
+        for (index, (_, piece_tokens)) in pieces.iter().enumerate() {
+            if new && tokens + piece_tokens > max {
+                chunks.push(pieces[first].0.start..pieces[index - 1].0.end);
+                while first < index && (tokens > overlap || tokens + piece_tokens > max) {
+                    tokens -= pieces[first].1;
+                    first += 1;
+                }
+            }
+            tokens += piece_tokens;
+            new = true;
+        }
+        if new {
+            chunks.push(pieces[first].0.start..pieces[pieces.len() - 1].0.end);
+        }
+        chunks
+    }
+}
+
+/// The lines of `range`, each with its line break.
+fn lines(text: &str, range: Range<usize>) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = range.start;
+    text[range].split_inclusive('\n').map(move |line| {
+        let line_range = start..start + line.len();
+        start = line_range.end;
+        line_range
+    })
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// The level and title of a Markdown ATX heading.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|&byte| byte == b'#').count();
+    let title = &line[level..];
+    if (1..=6).contains(&level) && (title.starts_with(' ') || title.trim().is_empty()) {
+        Some((level, title.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+/// Splits `range` into consecutive parts at `separator`.
+fn split_at(text: &str, range: Range<usize>, separator: Separator) -> Vec<Range<usize>> {
+    let mut starts = vec![];
+    match separator {
+        Separator::After(separator) => {
+            starts.extend(
+                text[range.clone()]
+                    .match_indices(separator)
+                    .map(|(offset, _)| range.start + offset + separator.len()),
+            );
+        }
+        Separator::Headings => {
+            let mut fenced = false;
+            for line in lines(text, range.clone()) {
+                let line_text = &text[line.clone()];
+                if is_fence(line_text) {
+                    fenced = !fenced;
+                } else if !fenced && heading(line_text).is_some() {
+                    starts.push(line.start);
+                }
+            }
+        }
+        Separator::MarkdownBlocks => {
+            let (mut fenced, mut blank) = (false, false);
+            for line in lines(text, range.clone()) {
+                let line_text = &text[line.clone()];
+                if is_fence(line_text) {
+                    if !fenced {
+                        starts.push(line.start);
+                    } else {
+                        starts.push(line.end);
+                    }
+                    fenced = !fenced;
+                } else if !fenced && blank && !line_text.trim().is_empty() {
+                    starts.push(line.start);
+                }
+                blank = line_text.trim().is_empty();
+            }
+        }
+        Separator::CodeBlocks => {
+            let mut blank = false;
+            for line in lines(text, range.clone()) {
+                let line_text = &text[line.clone()];
+                if blank && !line_text.starts_with(char::is_whitespace) && !line_text.is_empty() {
+                    starts.push(line.start);
+                }
+                blank = line_text.trim().is_empty();
+            }
+        }
+    }
+
+    let mut parts = vec![];
+    let mut start = range.start;
+    for end in starts {
+        if end > start && end < range.end {
+            parts.push(start..end);
+            start = end;
+        }
+    }
+    parts.push(start..range.end);
+    parts
+}
+
+/// The offset, level and title of every Markdown heading outside of fenced code blocks.
+fn markdown_headings(text: &str) -> Vec<(usize, usize, String)> {
+    let mut headings = vec![];
+    let mut fenced = false;
+    for line in lines(text, 0..text.len()) {
+        let line_text = &text[line.clone()];
+        if is_fence(line_text) {
+            fenced = !fenced;
+        } else if let Some((level, title)) = heading(line_text).filter(|_| !fenced) {
+            headings.push((line.start, level, title.to_string()));
+        }
+    }
+    headings
+}
+
+/// The titles of the headings enclosing `offset`, outermost first.
+fn heading_path(headings: &[(usize, usize, String)], offset: usize) -> Vec<String> {
+    let mut path: Vec<(usize, &str)> = vec![];
+    for (start, level, title) in headings {
+        if *start > offset {
+            break;
+        }
+        path.retain(|(enclosing, _)| enclosing < level);
+        path.push((*level, title));
+    }
+    path.into_iter()
+        .map(|(_, title)| title.to_string())
+        .collect()
+}
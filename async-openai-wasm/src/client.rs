@@ -1,6 +1,7 @@
 use std::future;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
@@ -12,11 +13,14 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     Assistants, Audio, Batches, Chat, Completions,
+    capabilities::{ModelRegistry, UnsupportedParameters},
     config::{Config, OpenAIConfig}, Embeddings,
+    embedding_cache::{EmbeddingCache, SharedEmbeddingCache},
     error::{map_deserialization_error, OpenAIError, WrappedError},
     file::Files, FineTuning,
     image::Images, Models,
     moderation::Moderations, Threads,
+    types::{CreateChatCompletionRequest, CreateEmbeddingRequest, Validate},
     VectorStores,
 };
 
@@ -26,6 +30,10 @@ use crate::{
 pub struct Client<C: Config> {
     http_client: reqwest::Client,
     config: C,
+    validate_requests: bool,
+    model_registry: Option<Arc<ModelRegistry>>,
+    unsupported_parameters: UnsupportedParameters,
+    embedding_cache: Option<SharedEmbeddingCache>,
 }
 
 impl Client<OpenAIConfig> {
@@ -34,6 +42,10 @@ impl Client<OpenAIConfig> {
         Self {
             http_client: reqwest::Client::new(),
             config: OpenAIConfig::default(),
+            validate_requests: false,
+            model_registry: None,
+            unsupported_parameters: UnsupportedParameters::Ignore,
+            embedding_cache: None,
         }
     }
 }
@@ -47,6 +59,10 @@ impl<C: Config> Client<C> {
         Self {
             http_client,
             config,
+            validate_requests: false,
+            model_registry: None,
+            unsupported_parameters: UnsupportedParameters::Ignore,
+            embedding_cache: None,
         }
     }
 
@@ -54,7 +70,11 @@ impl<C: Config> Client<C> {
     pub fn with_config(config: C) -> Self {
         Self {
             http_client: reqwest::Client::new(),
-            config
+            config,
+            validate_requests: false,
+            model_registry: None,
+            unsupported_parameters: UnsupportedParameters::Ignore,
+            embedding_cache: None,
         }
     }
 
@@ -66,6 +86,81 @@ impl<C: Config> Client<C> {
         self
     }
 
+    /// Validate requests on the client before sending them, see [Validate].
+    /// Invalid requests fail with [OpenAIError::InvalidArguments] without making an API call.
+    pub fn with_request_validation(mut self, validate_requests: bool) -> Self {
+        self.validate_requests = validate_requests;
+        self
+    }
+
+    /// Runs [Validate::validate] on the request if enabled by [Client::with_request_validation].
+    pub(crate) fn validate_request<R: Validate>(&self, request: &R) -> Result<(), OpenAIError> {
+        if self.validate_requests {
+            request.validate()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check chat and embedding requests against the capabilities of their model before sending them,
+    /// handling unsupported parameters according to `policy`. See [crate::capabilities].
+    pub fn with_model_registry(
+        mut self,
+        registry: ModelRegistry,
+        policy: UnsupportedParameters,
+    ) -> Self {
+        self.model_registry = Some(Arc::new(registry));
+        self.unsupported_parameters = policy;
+        self
+    }
+
+    /// The registry set by [Client::with_model_registry].
+    pub fn model_registry(&self) -> Option<&ModelRegistry> {
+        self.model_registry.as_deref()
+    }
+
+    /// Cache embeddings per input, so that [Embeddings::create] and [Embeddings::create_many] only send the inputs
+    /// missing from the cache. See [crate::embedding_cache].
+    pub fn with_embedding_cache(mut self, cache: impl EmbeddingCache + 'static) -> Self {
+        self.embedding_cache = Some(SharedEmbeddingCache(Arc::new(cache)));
+        self
+    }
+
+    /// The cache set by [Client::with_embedding_cache].
+    pub(crate) fn embedding_cache(&self) -> Option<&dyn EmbeddingCache> {
+        self.embedding_cache.as_ref().map(|cache| cache.0.as_ref())
+    }
+
+    /// Applies the [UnsupportedParameters] policy set by [Client::with_model_registry] to a chat request.
+    pub(crate) fn adapt_chat_request(
+        &self,
+        request: &mut CreateChatCompletionRequest,
+    ) -> Result<(), OpenAIError> {
+        match &self.model_registry {
+            Some(registry) => registry.apply_chat_policy(self.unsupported_parameters, request),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects embedding requests with parameters unsupported by the model,
+    /// unless the policy set by [Client::with_model_registry] is [UnsupportedParameters::Ignore].
+    pub(crate) fn check_embedding_request(
+        &self,
+        request: &CreateEmbeddingRequest,
+    ) -> Result<(), OpenAIError> {
+        match &self.model_registry {
+            Some(registry) if self.unsupported_parameters != UnsupportedParameters::Ignore => {
+                let details = registry.check_embedding_request(request);
+                if details.is_empty() {
+                    Ok(())
+                } else {
+                    Err(OpenAIError::InvalidArguments(details))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
     // API groups
 
     /// To call [Models] group related APIs using this client.
@@ -222,6 +317,62 @@ impl<C: Config> Client<C> {
         self.execute_raw(request_maker).await
     }
 
+    /// Make a POST request to {path} and return the response once its headers arrive, to stream the body
+    pub(crate) async fn post_response<I>(
+        &self,
+        path: &str,
+        request: I,
+    ) -> Result<reqwest::Response, OpenAIError>
+        where
+            I: Serialize,
+    {
+        let response = self
+            .http_client
+            .post(self.config.url(path))
+            .query(&self.config.query())
+            .headers(self.config.headers())
+            .json(&request)
+            .send()
+            .await
+            .map_err(OpenAIError::Reqwest)?;
+
+        Self::error_for_status(response).await
+    }
+
+    /// POST a form at {path} and return the response once its headers arrive, to stream the body
+    pub(crate) async fn post_form_response<F>(
+        &self,
+        path: &str,
+        form: F,
+    ) -> Result<reqwest::Response, OpenAIError>
+        where
+            reqwest::multipart::Form: async_convert::TryFrom<F, Error=OpenAIError>,
+    {
+        let response = self
+            .http_client
+            .post(self.config.url(path))
+            .query(&self.config.query())
+            .headers(self.config.headers())
+            .multipart(async_convert::TryFrom::try_from(form).await?)
+            .send()
+            .await
+            .map_err(OpenAIError::Reqwest)?;
+
+        Self::error_for_status(response).await
+    }
+
+    /// The response if it is successful, otherwise the API error in its body
+    async fn error_for_status(response: reqwest::Response) -> Result<reqwest::Response, OpenAIError> {
+        if !response.status().is_success() {
+            let bytes = response.bytes().await.map_err(OpenAIError::Reqwest)?;
+            let wrapped_error: WrappedError = serde_json::from_slice(bytes.as_ref())
+                .map_err(|e| map_deserialization_error(e, bytes.as_ref()))?;
+            return Err(OpenAIError::ApiError(wrapped_error.error));
+        }
+
+        Ok(response)
+    }
+
     /// Make a POST request to {path} and deserialize the response body
     pub(crate) async fn post<I, O>(&self, path: &str, request: I) -> Result<O, OpenAIError>
         where
@@ -419,6 +570,7 @@ pub struct OpenAIEventStream<O: DeserializeOwned + Send + 'static> {
     #[pin]
     stream: Filter<EventSource, future::Ready<bool>, fn(&Result<Event, reqwest_eventsource::Error>) -> future::Ready<bool>>,
     done: bool,
+    transform: Option<fn(&mut O)>,
     _phantom_data: PhantomData<O>,
 }
 
@@ -430,9 +582,16 @@ impl<O: DeserializeOwned + Send + 'static> OpenAIEventStream<O> {
                 future::ready(!(result.is_ok() && result.as_ref().unwrap().eq(&Event::Open)))
             ),
             done: false,
+            transform: None,
             _phantom_data: PhantomData,
         }
     }
+
+    /// Applies `transform` to every deserialized event.
+    pub(crate) fn with_transform(mut self, transform: fn(&mut O)) -> Self {
+        self.transform = Some(transform);
+        self
+    }
 }
 
 impl<O: DeserializeOwned + Send + 'static> Stream for OpenAIEventStream<O> {
@@ -462,7 +621,12 @@ impl<O: DeserializeOwned + Send + 'static> Stream for OpenAIEventStream<O> {
                                             *this.done = true;
                                             Poll::Ready(Some(Err(map_deserialization_error(e, &message.data.as_bytes()))))
                                         }
-                                        Ok(output) => Poll::Ready(Some(Ok(output))),
+                                        Ok(mut output) => {
+                                            if let Some(transform) = this.transform {
+                                                transform(&mut output);
+                                            }
+                                            Poll::Ready(Some(Ok(output)))
+                                        }
                                     }
                                 }
                             }
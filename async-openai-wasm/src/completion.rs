@@ -30,6 +30,7 @@ impl<'c, C: Config> Completions<'c, C> {
                 "When stream is true, use Completion::create_stream".into(),
             ));
         }
+        self.client.validate_request(&request)?;
         self.client.post("/completions", request).await
     }
 
@@ -52,6 +53,7 @@ impl<'c, C: Config> Completions<'c, C> {
         }
 
         request.stream = Some(true);
+        self.client.validate_request(&request)?;
 
         Ok(self.client.post_stream("/completions", request).await)
     }
@@ -3,6 +3,8 @@ use reqwest::header::{AUTHORIZATION, HeaderMap};
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 
+use crate::function_calling::FunctionCallingStyle;
+
 /// Default v1 API base url
 pub const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
 /// Organization header
@@ -23,6 +25,11 @@ pub trait Config: Clone {
     fn api_base(&self) -> &str;
 
     fn api_key(&self) -> &SecretString;
+
+    /// The function calling style supported by the server, requests in the other style are rewritten to it.
+    fn function_calling_style(&self) -> FunctionCallingStyle {
+        FunctionCallingStyle::Unchanged
+    }
 }
 
 /// Configuration for OpenAI API
@@ -33,6 +40,7 @@ pub struct OpenAIConfig {
     api_key: SecretString,
     org_id: String,
     project_id: String,
+    function_calling_style: FunctionCallingStyle,
 }
 
 impl Default for OpenAIConfig {
@@ -44,6 +52,7 @@ impl Default for OpenAIConfig {
                 .into(),
             org_id: Default::default(),
             project_id: Default::default(),
+            function_calling_style: Default::default(),
         }
     }
 }
@@ -78,6 +87,12 @@ impl OpenAIConfig {
         self
     }
 
+    /// To use a server that supports only one of legacy functions and tools, see [crate::function_calling]
+    pub fn with_function_calling_style(mut self, style: FunctionCallingStyle) -> Self {
+        self.function_calling_style = style;
+        self
+    }
+
     pub fn org_id(&self) -> &str {
         &self.org_id
     }
@@ -130,6 +145,10 @@ impl Config for OpenAIConfig {
     fn query(&self) -> Vec<(&str, &str)> {
         vec![]
     }
+
+    fn function_calling_style(&self) -> FunctionCallingStyle {
+        self.function_calling_style
+    }
 }
 
 /// Configuration for Azure OpenAI Service
@@ -140,6 +159,7 @@ pub struct AzureConfig {
     deployment_id: String,
     api_base: String,
     api_key: SecretString,
+    function_calling_style: FunctionCallingStyle,
 }
 
 impl Default for AzureConfig {
@@ -151,6 +171,7 @@ impl Default for AzureConfig {
                 .into(),
             deployment_id: Default::default(),
             api_version: Default::default(),
+            function_calling_style: Default::default(),
         }
     }
 }
@@ -181,6 +202,12 @@ impl AzureConfig {
         self.api_base = api_base.into();
         self
     }
+
+    /// To use a deployment that supports only one of legacy functions and tools, see [crate::function_calling]
+    pub fn with_function_calling_style(mut self, style: FunctionCallingStyle) -> Self {
+        self.function_calling_style = style;
+        self
+    }
 }
 
 impl Config for AzureConfig {
@@ -213,4 +240,8 @@ impl Config for AzureConfig {
     fn query(&self) -> Vec<(&str, &str)> {
         vec![("api-version", &self.api_version)]
     }
+
+    fn function_calling_style(&self) -> FunctionCallingStyle {
+        self.function_calling_style
+    }
 }
@@ -0,0 +1,386 @@
+//! Chat history which stays valid as it grows.
+//!
+//! [Conversation] owns the messages of a chat, appends model responses and tool results in the order
+//! the API expects, and keeps the history under a token budget by dropping or summarizing older turns.
+//! Messages are never reordered, system and developer messages are never dropped, and tool results are always
+//! dropped together with the assistant message which requested them.
+//!
+//! ```
+//! use async_openai_wasm::conversation::Conversation;
+//!
+//! let mut conversation = Conversation::new();
+//! conversation.push_system("You are a helpful assistant.");
+//! conversation.push_user("Hello!");
+//!
+//! // persist and restore the history
+//! let json = serde_json::to_string(&conversation).unwrap();
+//! let restored: Conversation = serde_json::from_str(&json).unwrap();
+//! assert_eq!(restored, conversation);
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::OpenAIError;
+use crate::types::{
+    ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageContent,
+    ChatCompletionRequestAssistantMessageContentPart, ChatCompletionRequestDeveloperMessageContent,
+    ChatCompletionRequestDeveloperMessageContentPart, ChatCompletionRequestMessage,
+    ChatCompletionRequestSystemMessage, ChatCompletionRequestSystemMessageContent,
+    ChatCompletionRequestSystemMessageContentPart, ChatCompletionRequestToolMessage,
+    ChatCompletionRequestToolMessageContent, ChatCompletionRequestToolMessageContentPart,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
+    ChatCompletionResponseMessage, CreateChatCompletionRequestArgs, CreateChatCompletionResponse,
+};
+use crate::Client;
+
+/// The `name` of the system message holding the summary of older turns.
+pub const SUMMARY_MESSAGE_NAME: &str = "conversation_summary";
+
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the following conversation between a user and an assistant. \
+Keep every fact, decision, open question and tool result the assistant needs to continue the conversation. \
+Reply with the summary only.";
+
+/// The messages of a chat, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    messages: Vec<ChatCompletionRequestMessage>,
+}
+
+impl Conversation {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The messages, in order, e.g. for [CreateChatCompletionRequestArgs::messages].
+    pub fn messages(&self) -> &[ChatCompletionRequestMessage] {
+        &self.messages
+    }
+
+    pub fn into_messages(self) -> Vec<ChatCompletionRequestMessage> {
+        self.messages
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Appends a message as is.
+    pub fn push<M: Into<ChatCompletionRequestMessage>>(&mut self, message: M) {
+        self.messages.push(message.into());
+    }
+
+    pub fn push_system<S: Into<ChatCompletionRequestSystemMessageContent>>(&mut self, content: S) {
+        self.push(ChatCompletionRequestSystemMessage {
+            content: content.into(),
+            name: None,
+        });
+    }
+
+    pub fn push_user<S: Into<ChatCompletionRequestUserMessageContent>>(&mut self, content: S) {
+        self.push(crate::types::ChatCompletionRequestUserMessage {
+            content: content.into(),
+            name: None,
+        });
+    }
+
+    /// Appends a message generated by the model, including its tool calls.
+    pub fn push_response_message(&mut self, message: ChatCompletionResponseMessage) {
+        self.push(message);
+    }
+
+    /// Appends the message of the first choice of a response.
+    pub fn push_response(
+        &mut self,
+        response: &CreateChatCompletionResponse,
+    ) -> Result<(), OpenAIError> {
+        let choice = response.choices.first().ok_or_else(|| {
+            OpenAIError::InvalidArgument("response does not contain any choices".into())
+        })?;
+        self.push_response_message(choice.message.clone());
+        Ok(())
+    }
+
+    /// Tool calls of the last assistant message which do not have a result yet.
+    pub fn pending_tool_calls(&self) -> Vec<&ChatCompletionMessageToolCall> {
+        let mut answered = Vec::new();
+        for message in self.messages.iter().rev() {
+            match message {
+                ChatCompletionRequestMessage::Tool(tool) => answered.push(&tool.tool_call_id),
+                ChatCompletionRequestMessage::Assistant(assistant) => {
+                    return assistant
+                        .tool_calls
+                        .iter()
+                        .flatten()
+                        .filter(|tool_call| !answered.contains(&&tool_call.id))
+                        .collect();
+                }
+                _ => break,
+            }
+        }
+        Vec::new()
+    }
+
+    /// Appends the result of a tool call.
+    ///
+    /// Fails if `tool_call_id` is not one of the [Conversation::pending_tool_calls], as the API rejects
+    /// tool results which do not directly follow the assistant message requesting them.
+    pub fn push_tool_result<S: Into<ChatCompletionRequestToolMessageContent>>(
+        &mut self,
+        tool_call_id: &str,
+        content: S,
+    ) -> Result<(), OpenAIError> {
+        if !self
+            .pending_tool_calls()
+            .iter()
+            .any(|tool_call| tool_call.id == tool_call_id)
+        {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "no pending tool call with id {tool_call_id}"
+            )));
+        }
+        self.push(ChatCompletionRequestToolMessage {
+            content: content.into(),
+            tool_call_id: tool_call_id.into(),
+        });
+        Ok(())
+    }
+
+    /// Drops the oldest turns until `count_tokens` of the remaining messages is at most `max_tokens`,
+    /// returning the number of dropped messages.
+    ///
+    /// A turn starts with a user message and contains the replies and tool results following it.
+    /// System and developer messages are pinned and the latest turn is always kept. Fails without changing the
+    /// conversation if the budget cannot be met.
+    pub fn truncate_with<F>(
+        &mut self,
+        max_tokens: usize,
+        count_tokens: F,
+    ) -> Result<usize, OpenAIError>
+    where
+        F: Fn(&[ChatCompletionRequestMessage]) -> usize,
+    {
+        let turns = self.turns();
+        let mut dropped_turns = 0;
+        loop {
+            let remaining = self.without_turns(&turns[..dropped_turns]);
+            if count_tokens(&remaining) <= max_tokens {
+                let dropped = self.messages.len() - remaining.len();
+                self.messages = remaining;
+                return Ok(dropped);
+            }
+            if dropped_turns + 1 >= turns.len() {
+                return Err(OpenAIError::InvalidArgument(format!(
+                    "conversation does not fit into {max_tokens} tokens without dropping its latest turn"
+                )));
+            }
+            dropped_turns += 1;
+        }
+    }
+
+    /// [Conversation::truncate_with] counting prompt tokens with the given tokenizer.
+    #[cfg(feature = "tokenizer")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokenizer")))]
+    pub fn truncate(
+        &mut self,
+        max_tokens: usize,
+        tokenizer: &crate::tokenizer::Tokenizer,
+    ) -> Result<usize, OpenAIError> {
+        self.truncate_with(max_tokens, |messages| {
+            tokenizer.count_message_tokens(messages)
+        })
+    }
+
+    /// Replaces all but the latest `keep_turns` turns with a summary written by `model`.
+    ///
+    /// The summary is stored as a system message named [SUMMARY_MESSAGE_NAME] after the leading system messages,
+    /// and is itself summarized again by the next call. Returns `false` if there was nothing to summarize.
+    pub async fn summarize_older_turns<C: Config>(
+        &mut self,
+        client: &Client<C>,
+        model: &str,
+        keep_turns: usize,
+    ) -> Result<bool, OpenAIError> {
+        let turns = self.turns();
+        if turns.len() <= keep_turns {
+            return Ok(false);
+        }
+        let older = &turns[..turns.len() - keep_turns];
+        let previous_summary = self.messages.iter().position(is_summary);
+
+        let mut transcript = String::new();
+        for index in previous_summary.iter().chain(older.iter().flatten()) {
+            let message = &self.messages[*index];
+            transcript.push_str(&format!("{}: {}\n\n", message.role(), text(message)));
+        }
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(model)
+            .messages([
+                ChatCompletionRequestSystemMessage::from(SUMMARY_INSTRUCTIONS).into(),
+                crate::types::ChatCompletionRequestUserMessage::from(transcript).into(),
+            ])
+            .build()?;
+        let response = client.chat().create(request).await?;
+        let summary = response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| {
+                OpenAIError::InvalidArgument("summary response has no content".into())
+            })?;
+
+        let mut messages = self.without_turns(older);
+        messages.retain(|message| !is_summary(message));
+        let position = messages
+            .iter()
+            .position(|message| {
+                !matches!(
+                    message,
+                    ChatCompletionRequestMessage::System(_)
+                        | ChatCompletionRequestMessage::Developer(_)
+                )
+            })
+            .unwrap_or(messages.len());
+        messages.insert(
+            position,
+            ChatCompletionRequestSystemMessage {
+                content: format!("Summary of the earlier conversation:\n{summary}").into(),
+                name: Some(SUMMARY_MESSAGE_NAME.into()),
+            }
+            .into(),
+        );
+        self.messages = messages;
+        Ok(true)
+    }
+
+    /// Indices of the unpinned messages, grouped into turns.
+    fn turns(&self) -> Vec<Vec<usize>> {
+        let mut turns: Vec<Vec<usize>> = Vec::new();
+        for (index, message) in self.messages.iter().enumerate() {
+            match message {
+                ChatCompletionRequestMessage::System(_)
+                | ChatCompletionRequestMessage::Developer(_) => {}
+                ChatCompletionRequestMessage::User(_) => turns.push(vec![index]),
+                _ => match turns.last_mut() {
+                    Some(turn) => turn.push(index),
+                    None => turns.push(vec![index]),
+                },
+            }
+        }
+        turns
+    }
+
+    fn without_turns(&self, turns: &[Vec<usize>]) -> Vec<ChatCompletionRequestMessage> {
+        let dropped: Vec<usize> = turns.iter().flatten().copied().collect();
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !dropped.contains(index))
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+}
+
+impl From<Vec<ChatCompletionRequestMessage>> for Conversation {
+    fn from(messages: Vec<ChatCompletionRequestMessage>) -> Self {
+        Self { messages }
+    }
+}
+
+impl From<Conversation> for Vec<ChatCompletionRequestMessage> {
+    fn from(conversation: Conversation) -> Self {
+        conversation.messages
+    }
+}
+
+fn is_summary(message: &ChatCompletionRequestMessage) -> bool {
+    matches!(message, ChatCompletionRequestMessage::System(system)
+        if system.name.as_deref() == Some(SUMMARY_MESSAGE_NAME))
+}
+
+/// The text of a message for the summary transcript, with tool calls rendered as calls.
+#[allow(deprecated)]
+fn text(message: &ChatCompletionRequestMessage) -> String {
+    match message {
+        ChatCompletionRequestMessage::System(message) => match &message.content {
+            ChatCompletionRequestSystemMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestSystemMessageContent::Array(parts) => parts
+                .iter()
+                .map(|ChatCompletionRequestSystemMessageContentPart::Text(part)| part.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::Developer(message) => match &message.content {
+            ChatCompletionRequestDeveloperMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestDeveloperMessageContent::Array(parts) => parts
+                .iter()
+                .map(
+                    |ChatCompletionRequestDeveloperMessageContentPart::Text(part)| {
+                        part.text.as_str()
+                    },
+                )
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::User(message) => match &message.content {
+            ChatCompletionRequestUserMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ChatCompletionRequestUserMessageContentPart::Text(part) => part.text.as_str(),
+                    ChatCompletionRequestUserMessageContentPart::ImageUrl(_) => "[image]",
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::Assistant(message) => {
+            let mut lines = Vec::new();
+            match &message.content {
+                Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => {
+                    lines.push(text.clone())
+                }
+                Some(ChatCompletionRequestAssistantMessageContent::Array(parts)) => {
+                    lines.extend(parts.iter().map(|part| match part {
+                        ChatCompletionRequestAssistantMessageContentPart::Text(part) => {
+                            part.text.clone()
+                        }
+                        ChatCompletionRequestAssistantMessageContentPart::Refusal(part) => {
+                            part.refusal.clone()
+                        }
+                    }))
+                }
+                None => {}
+            }
+            lines.extend(message.refusal.clone());
+            for tool_call in message.tool_calls.iter().flatten() {
+                lines.push(format!(
+                    "[called {}({})]",
+                    tool_call.function.name, tool_call.function.arguments
+                ));
+            }
+            if let Some(function_call) = &message.function_call {
+                lines.push(format!(
+                    "[called {}({})]",
+                    function_call.name, function_call.arguments
+                ));
+            }
+            lines.join("\n")
+        }
+        ChatCompletionRequestMessage::Tool(message) => match &message.content {
+            ChatCompletionRequestToolMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestToolMessageContent::Array(parts) => parts
+                .iter()
+                .map(|ChatCompletionRequestToolMessageContentPart::Text(part)| part.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::Function(message) => {
+            message.content.clone().unwrap_or_default()
+        }
+    }
+}
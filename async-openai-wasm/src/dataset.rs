@@ -0,0 +1,506 @@
+//! JSONL datasets for [fine-tuning](https://platform.openai.com/docs/guides/fine-tuning) and the [Batch API](https://platform.openai.com/docs/guides/batch),
+//! built from recorded conversations.
+//!
+//! A [DatasetWriter] checks every record before writing it, including the role ordering rules of chat examples,
+//! and produces a [CreateFileRequest] ready for [crate::Files::create].
+//!
+//! ```
+//! use async_openai_wasm::dataset::{ChatFineTuningExample, DatasetWriter};
+//! use async_openai_wasm::types::{
+//!     ChatCompletionRequestAssistantMessage, ChatCompletionRequestSystemMessage,
+//!     ChatCompletionRequestUserMessage,
+//! };
+//!
+//! let example = ChatFineTuningExample::new(vec![
+//!     ChatCompletionRequestSystemMessage::from("Marv is a sarcastic chatbot.").into(),
+//!     ChatCompletionRequestUserMessage::from("What's the capital of France?").into(),
+//!     ChatCompletionRequestAssistantMessage::from("Paris, as if everyone doesn't know that already.").into(),
+//! ]);
+//!
+//! let mut writer = DatasetWriter::new();
+//! writer.push(&example).unwrap();
+//! let request = writer.into_file_request("marv.jsonl");
+//! ```
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{InvalidArgumentDetail, OpenAIError};
+use crate::types::{
+    BatchEndpoint, BatchRequestInput, BatchRequestInputMethod,
+    ChatCompletionRequestAssistantMessage, ChatCompletionRequestMessage, ChatCompletionTool,
+    CreateChatCompletionRequest, CreateChatCompletionResponse, CreateFileRequest, FileInput,
+    FilePurpose, InputSource, Role, Validate,
+};
+
+/// A message of a chat fine-tuning example.
+///
+/// The `weight` of an assistant message is either 0 or 1, assistant messages with weight 0 are not trained on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FineTuningMessage {
+    #[serde(flatten)]
+    pub message: ChatCompletionRequestMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u8>,
+}
+
+impl From<ChatCompletionRequestMessage> for FineTuningMessage {
+    fn from(message: ChatCompletionRequestMessage) -> Self {
+        Self {
+            message,
+            weight: None,
+        }
+    }
+}
+
+/// A line of a supervised fine-tuning dataset in the chat format.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatFineTuningExample {
+    pub messages: Vec<FineTuningMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ChatCompletionTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+}
+
+impl ChatFineTuningExample {
+    pub fn new(messages: Vec<ChatCompletionRequestMessage>) -> Self {
+        Self {
+            messages: messages.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// An example of the messages and tools of a request, completed with the message of the first choice of its response.
+    pub fn from_exchange(
+        request: &CreateChatCompletionRequest,
+        response: &CreateChatCompletionResponse,
+    ) -> Result<Self, OpenAIError> {
+        let mut example = Self::new(request.messages.clone());
+        example.messages.push(first_choice(response)?.into());
+        example.tools.clone_from(&request.tools);
+        example.parallel_tool_calls = request.parallel_tool_calls;
+        Ok(example)
+    }
+
+    pub fn with_tools(mut self, tools: Vec<ChatCompletionTool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    pub fn with_parallel_tool_calls(mut self, parallel_tool_calls: bool) -> Self {
+        self.parallel_tool_calls = Some(parallel_tool_calls);
+        self
+    }
+
+    /// Sets the weight of the message at `index`, which has to be an assistant message. Use 0 to exclude it from training.
+    pub fn with_weight(mut self, index: usize, weight: u8) -> Result<Self, OpenAIError> {
+        match self.messages.get_mut(index) {
+            Some(message)
+                if matches!(message.message, ChatCompletionRequestMessage::Assistant(_)) =>
+            {
+                message.weight = Some(weight);
+                Ok(self)
+            }
+            _ => Err(OpenAIError::InvalidArgument(format!(
+                "messages[{index}] is not an assistant message"
+            ))),
+        }
+    }
+}
+
+fn first_choice(
+    response: &CreateChatCompletionResponse,
+) -> Result<ChatCompletionRequestMessage, OpenAIError> {
+    let choice = response.choices.first().ok_or_else(|| {
+        OpenAIError::InvalidArgument(format!("response {} has no choices", response.id))
+    })?;
+    Ok(ChatCompletionRequestAssistantMessage::from(choice.message.clone()).into())
+}
+
+/// Checks the role ordering rules of a conversation:
+/// system and developer messages come first, followed by a user message,
+/// and every tool or function result answers a pending call of the assistant message before it.
+#[allow(deprecated)]
+fn order_violations<'a>(
+    param: &str,
+    messages: impl IntoIterator<Item = &'a ChatCompletionRequestMessage>,
+) -> Vec<InvalidArgumentDetail> {
+    let mut violations = Vec::new();
+    let mut add = |index: usize, message: String| {
+        violations.push(InvalidArgumentDetail {
+            param: format!("{param}[{index}]"),
+            message,
+        })
+    };
+
+    let mut seen_conversation = false;
+    let mut pending_tool_calls: HashSet<&str> = HashSet::new();
+    let mut pending_function: Option<&str> = None;
+    for (index, message) in messages.into_iter().enumerate() {
+        if !matches!(message, ChatCompletionRequestMessage::Tool(_))
+            && !pending_tool_calls.is_empty()
+        {
+            add(
+                index,
+                format!(
+                    "{} message before the results of all tool calls of the previous assistant message",
+                    message.role()
+                ),
+            );
+            pending_tool_calls.clear();
+        }
+        match message {
+            ChatCompletionRequestMessage::System(_)
+            | ChatCompletionRequestMessage::Developer(_) => {
+                if seen_conversation {
+                    add(
+                        index,
+                        format!(
+                            "{} messages must come before other messages",
+                            message.role()
+                        ),
+                    );
+                }
+            }
+            ChatCompletionRequestMessage::User(_) => seen_conversation = true,
+            ChatCompletionRequestMessage::Assistant(assistant) => {
+                if !seen_conversation {
+                    add(
+                        index,
+                        "the conversation must start with a user message".into(),
+                    );
+                }
+                seen_conversation = true;
+                pending_tool_calls = assistant
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|tool_call| tool_call.id.as_str())
+                    .collect();
+                pending_function = assistant
+                    .function_call
+                    .as_ref()
+                    .map(|function_call| function_call.name.as_str());
+            }
+            ChatCompletionRequestMessage::Tool(tool) => {
+                if !pending_tool_calls.remove(tool.tool_call_id.as_str()) {
+                    add(
+                        index,
+                        format!(
+                            "tool message `{}` does not answer a tool call of the previous assistant message",
+                            tool.tool_call_id
+                        ),
+                    );
+                }
+            }
+            ChatCompletionRequestMessage::Function(function) => {
+                if pending_function.take() != Some(function.name.as_str()) {
+                    add(
+                        index,
+                        format!(
+                            "function message `{}` does not answer the function call of the previous assistant message",
+                            function.name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    violations
+}
+
+fn last_role_violation(
+    param: &str,
+    messages: &[&ChatCompletionRequestMessage],
+    expected: Role,
+) -> Option<InvalidArgumentDetail> {
+    match messages.last() {
+        Some(message) if message.role() == expected => None,
+        _ => Some(InvalidArgumentDetail {
+            param: param.into(),
+            message: format!("must end with an {expected} message"),
+        }),
+    }
+}
+
+impl Validate for ChatFineTuningExample {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let messages: Vec<&ChatCompletionRequestMessage> = self
+            .messages
+            .iter()
+            .map(|message| &message.message)
+            .collect();
+        let mut violations = order_violations("messages", messages.iter().copied());
+        violations.extend(last_role_violation("messages", &messages, Role::Assistant));
+        for (index, message) in self.messages.iter().enumerate() {
+            match (message.weight, &message.message) {
+                (None, _) | (Some(0 | 1), ChatCompletionRequestMessage::Assistant(_)) => {}
+                (Some(0 | 1), _) => violations.push(InvalidArgumentDetail {
+                    param: format!("messages[{index}].weight"),
+                    message: "only assistant messages can have a weight".into(),
+                }),
+                (Some(weight), _) => violations.push(InvalidArgumentDetail {
+                    param: format!("messages[{index}].weight"),
+                    message: format!("must be 0 or 1, got {weight}"),
+                }),
+            }
+        }
+        violations
+    }
+}
+
+/// The conversation of a [PreferenceExample], ending with the user message both outputs respond to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PreferenceInput {
+    pub messages: Vec<ChatCompletionRequestMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<ChatCompletionTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+}
+
+/// A line of a [DPO](https://platform.openai.com/docs/guides/direct-preference-optimization) fine-tuning dataset:
+/// a preferred and a non-preferred assistant response to the same conversation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PreferenceExample {
+    pub input: PreferenceInput,
+    pub preferred_output: Vec<ChatCompletionRequestMessage>,
+    pub non_preferred_output: Vec<ChatCompletionRequestMessage>,
+}
+
+impl PreferenceExample {
+    pub fn new(
+        messages: Vec<ChatCompletionRequestMessage>,
+        preferred: impl Into<ChatCompletionRequestAssistantMessage>,
+        non_preferred: impl Into<ChatCompletionRequestAssistantMessage>,
+    ) -> Self {
+        Self {
+            input: PreferenceInput {
+                messages,
+                ..Default::default()
+            },
+            preferred_output: vec![preferred.into().into()],
+            non_preferred_output: vec![non_preferred.into().into()],
+        }
+    }
+
+    /// An example from two responses to the same request, using the message of the first choice of each.
+    pub fn from_responses(
+        request: &CreateChatCompletionRequest,
+        preferred: &CreateChatCompletionResponse,
+        non_preferred: &CreateChatCompletionResponse,
+    ) -> Result<Self, OpenAIError> {
+        Ok(Self {
+            input: PreferenceInput {
+                messages: request.messages.clone(),
+                tools: request.tools.clone(),
+                parallel_tool_calls: request.parallel_tool_calls,
+            },
+            preferred_output: vec![first_choice(preferred)?],
+            non_preferred_output: vec![first_choice(non_preferred)?],
+        })
+    }
+}
+
+impl Validate for PreferenceExample {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let messages: Vec<&ChatCompletionRequestMessage> = self.input.messages.iter().collect();
+        let mut violations = order_violations("input.messages", messages.iter().copied());
+        violations.extend(last_role_violation("input.messages", &messages, Role::User));
+        for (param, output) in [
+            ("preferred_output", &self.preferred_output),
+            ("non_preferred_output", &self.non_preferred_output),
+        ] {
+            if !matches!(
+                output.as_slice(),
+                [ChatCompletionRequestMessage::Assistant(_)]
+            ) {
+                violations.push(InvalidArgumentDetail {
+                    param: param.into(),
+                    message: "must be a single assistant message".into(),
+                });
+            }
+        }
+        violations
+    }
+}
+
+impl BatchRequestInput {
+    /// A line of a batch input file for a chat completion request.
+    pub fn chat_completion(
+        custom_id: impl Into<String>,
+        request: &CreateChatCompletionRequest,
+    ) -> Result<Self, OpenAIError> {
+        Ok(Self {
+            custom_id: custom_id.into(),
+            method: BatchRequestInputMethod::POST,
+            url: BatchEndpoint::V1ChatCompletions,
+            body: Some(
+                serde_json::to_value(request)
+                    .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))?,
+            ),
+        })
+    }
+}
+
+impl Validate for BatchRequestInput {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut violations = Vec::new();
+        if self.custom_id.is_empty() {
+            violations.push(InvalidArgumentDetail {
+                param: "custom_id".into(),
+                message: "must not be empty".into(),
+            });
+        }
+        if !matches!(self.body, Some(serde_json::Value::Object(_))) {
+            violations.push(InvalidArgumentDetail {
+                param: "body".into(),
+                message: "must be a request object".into(),
+            });
+        }
+        if let (BatchEndpoint::V1ChatCompletions, Some(body)) = (&self.url, &self.body) {
+            if body.get("stream").and_then(serde_json::Value::as_bool) == Some(true) {
+                violations.push(InvalidArgumentDetail {
+                    param: "body.stream".into(),
+                    message: "batch requests cannot be streamed".into(),
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// A record of a JSONL dataset file, see [DatasetWriter].
+pub trait DatasetRecord: Serialize + Validate {
+    /// The purpose of files of these records.
+    fn purpose() -> FilePurpose;
+
+    /// An identifier that has to be unique among the records of a file.
+    fn unique_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl DatasetRecord for ChatFineTuningExample {
+    fn purpose() -> FilePurpose {
+        FilePurpose::FineTune
+    }
+}
+
+impl DatasetRecord for PreferenceExample {
+    fn purpose() -> FilePurpose {
+        FilePurpose::FineTune
+    }
+}
+
+impl DatasetRecord for BatchRequestInput {
+    fn purpose() -> FilePurpose {
+        FilePurpose::Batch
+    }
+
+    fn unique_id(&self) -> Option<&str> {
+        Some(&self.custom_id)
+    }
+}
+
+/// Writes checked records of one kind as JSON lines.
+#[derive(Debug, Clone)]
+pub struct DatasetWriter<R> {
+    jsonl: String,
+    len: usize,
+    ids: HashMap<String, usize>,
+    _record: PhantomData<R>,
+}
+
+impl<R> Default for DatasetWriter<R> {
+    fn default() -> Self {
+        Self {
+            jsonl: String::new(),
+            len: 0,
+            ids: HashMap::new(),
+            _record: PhantomData,
+        }
+    }
+}
+
+impl<R: DatasetRecord> DatasetWriter<R> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Checks and appends a record. Fails with [OpenAIError::InvalidArguments] listing every violation,
+    /// with the parameters prefixed by the index of the record, e.g. `lines[3].messages[1]`.
+    pub fn push(&mut self, record: &R) -> Result<(), OpenAIError> {
+        let prefix = format!("lines[{}]", self.len);
+        let mut violations = record.violations();
+        if let Some(id) = record.unique_id() {
+            if let Some(line) = self.ids.get(id) {
+                violations.push(InvalidArgumentDetail {
+                    param: "custom_id".into(),
+                    message: format!("`{id}` is already used by lines[{line}]"),
+                });
+            }
+        }
+        if !violations.is_empty() {
+            return Err(OpenAIError::InvalidArguments(
+                violations
+                    .into_iter()
+                    .map(|detail| InvalidArgumentDetail {
+                        param: format!("{prefix}.{}", detail.param),
+                        message: detail.message,
+                    })
+                    .collect(),
+            ));
+        }
+
+        let line = serde_json::to_string(record)
+            .map_err(|e| OpenAIError::InvalidArgument(e.to_string()))?;
+        if let Some(id) = record.unique_id() {
+            self.ids.insert(id.to_string(), self.len);
+        }
+        self.jsonl.push_str(&line);
+        self.jsonl.push('\n');
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends every record, stopping at the first invalid one.
+    pub fn extend<'a>(
+        &mut self,
+        records: impl IntoIterator<Item = &'a R>,
+    ) -> Result<(), OpenAIError>
+    where
+        R: 'a,
+    {
+        records.into_iter().try_for_each(|record| self.push(record))
+    }
+
+    /// Number of records written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The JSONL contents written so far.
+    pub fn as_str(&self) -> &str {
+        &self.jsonl
+    }
+
+    /// A request to upload the dataset with [crate::Files::create], with the purpose of the records.
+    pub fn into_file_request(self, filename: impl Into<String>) -> CreateFileRequest {
+        CreateFileRequest {
+            file: FileInput {
+                source: InputSource::Bytes {
+                    filename: filename.into(),
+                    bytes: self.jsonl.into(),
+                },
+            },
+            purpose: R::purpose(),
+        }
+    }
+}
@@ -1,10 +1,16 @@
+use std::future::Future;
+
+use futures::{stream, StreamExt};
+
 use crate::{
     Client,
     config::Config,
-    error::OpenAIError,
+    embedding_batch::{BatchResponse, EmbeddingBatchOptions, EmbeddingBatchProgress},
+    embedding_cache::{select_inputs, CachedResponse, EmbeddingCacheKey},
+    error::{InvalidArgumentDetail, OpenAIError},
     types::{
         CreateBase64EmbeddingResponse, CreateEmbeddingRequest, CreateEmbeddingResponse,
-        EncodingFormat,
+        EmbeddingInput, EmbeddingUsage, EncodingFormat, Validate,
     },
 };
 
@@ -31,7 +37,10 @@ impl<'c, C: Config> Embeddings<'c, C> {
                 "When encoding_format is base64, use Embeddings::create_base64".into(),
             ));
         }
-        self.client.post("/embeddings", request).await
+        self.client.check_embedding_request(&request)?;
+        self.client.validate_request(&request)?;
+        self.cached(request, |request| self.client.post("/embeddings", request))
+            .await
     }
 
     /// Creates an embedding vector representing the input text.
@@ -46,8 +55,217 @@ impl<'c, C: Config> Embeddings<'c, C> {
                 "When encoding_format is not base64, use Embeddings::create".into(),
             ));
         }
+        self.client.check_embedding_request(&request)?;
+        self.client.validate_request(&request)?;
+
+        self.cached(request, |request| self.client.post("/embeddings", request))
+            .await
+    }
+
+    /// Creates embedding vectors for any number of inputs, split into as many requests as needed.
+    ///
+    /// The requests are sent concurrently and retried according to `options`, and `progress` is called after each
+    /// of them. The embeddings of the response are in the order of the inputs, with the usage of all requests.
+    /// See [crate::embedding_batch].
+    pub async fn create_many<F>(
+        &self,
+        request: CreateEmbeddingRequest,
+        options: &EmbeddingBatchOptions,
+        progress: F,
+    ) -> Result<CreateEmbeddingResponse, OpenAIError>
+    where
+        F: FnMut(&EmbeddingBatchProgress),
+    {
+        if matches!(request.encoding_format, Some(EncodingFormat::Base64)) {
+            return Err(OpenAIError::InvalidArgument(
+                "When encoding_format is base64, use Embeddings::create_many_base64".into(),
+            ));
+        }
+        self.cached(request, |request| {
+            self.create_batched(request, options, progress)
+        })
+        .await
+    }
+
+    /// Creates embedding vectors for any number of inputs in base64 format, see [Embeddings::create_many].
+    pub async fn create_many_base64<F>(
+        &self,
+        request: CreateEmbeddingRequest,
+        options: &EmbeddingBatchOptions,
+        progress: F,
+    ) -> Result<CreateBase64EmbeddingResponse, OpenAIError>
+    where
+        F: FnMut(&EmbeddingBatchProgress),
+    {
+        if !matches!(request.encoding_format, Some(EncodingFormat::Base64)) {
+            return Err(OpenAIError::InvalidArgument(
+                "When encoding_format is not base64, use Embeddings::create_many".into(),
+            ));
+        }
+        self.cached(request, |request| {
+            self.create_batched(request, options, progress)
+        })
+        .await
+    }
+
+    async fn create_batched<R, F>(
+        &self,
+        request: CreateEmbeddingRequest,
+        options: &EmbeddingBatchOptions,
+        mut progress: F,
+    ) -> Result<R, OpenAIError>
+    where
+        R: BatchResponse,
+        F: FnMut(&EmbeddingBatchProgress),
+    {
+        self.client.check_embedding_request(&request)?;
+        self.client.validate_request(&BatchedRequest(&request))?;
+
+        let mut offset = 0;
+        let requests: Vec<(u32, usize, CreateEmbeddingRequest)> = options
+            .split(&request.model, request.input.clone())
+            .into_iter()
+            .map(|input| {
+                let inputs = input_count(&input);
+                let batch = (
+                    offset,
+                    inputs,
+                    CreateEmbeddingRequest {
+                        input,
+                        ..request.clone()
+                    },
+                );
+                offset += inputs as u32;
+                batch
+            })
+            .collect();
+        if requests.is_empty() {
+            return Err(OpenAIError::InvalidArgument(
+                "input must not be empty".into(),
+            ));
+        }
+
+        let mut status = EmbeddingBatchProgress {
+            completed_requests: 0,
+            total_requests: requests.len(),
+            completed_inputs: 0,
+            total_inputs: offset as usize,
+            usage: EmbeddingUsage {
+                prompt_tokens: 0,
+                total_tokens: 0,
+            },
+        };
+
+        let mut responses = stream::iter(requests)
+            .map(|(offset, inputs, request)| async move {
+                let response = options
+                    .requests
+                    .retry(|| self.client.post::<_, R>("/embeddings", &request))
+                    .await;
+                (offset, inputs, response)
+            })
+            .buffer_unordered(options.requests.concurrency.max(1));
+
+        let mut merged = Vec::with_capacity(status.total_requests);
+        while let Some((offset, inputs, response)) = responses.next().await {
+            let response = response?;
+            status.completed_requests += 1;
+            status.completed_inputs += inputs;
+            status.usage.prompt_tokens += response.usage().prompt_tokens;
+            status.usage.total_tokens += response.usage().total_tokens;
+            progress(&status);
+            merged.push((offset, response));
+        }
+
+        Ok(R::merge(merged))
+    }
+
+    /// Sends only the inputs missing from the cache of the client, if any, and merges the response with the cached
+    /// embeddings. See [crate::embedding_cache].
+    async fn cached<R, F, Fut>(
+        &self,
+        request: CreateEmbeddingRequest,
+        send: F,
+    ) -> Result<R, OpenAIError>
+    where
+        R: CachedResponse,
+        F: FnOnce(CreateEmbeddingRequest) -> Fut,
+        Fut: Future<Output = Result<R, OpenAIError>>,
+    {
+        let Some(cache) = self.client.embedding_cache() else {
+            return send(request).await;
+        };
+
+        let keys = EmbeddingCacheKey::for_request(&request);
+        let mut embeddings = match cache.get(&keys).await {
+            Ok(hits) if hits.len() == keys.len() => hits,
+            Ok(_) => vec![None; keys.len()],
+            Err(e) => {
+                tracing::warn!("Embedding cache lookup failed: {e}");
+                vec![None; keys.len()]
+            }
+        };
+        let missing: Vec<usize> = (0..keys.len())
+            .filter(|&position| embeddings[position].is_none())
+            .collect();
+
+        let mut model = request.model.clone();
+        let mut usage = EmbeddingUsage {
+            prompt_tokens: 0,
+            total_tokens: 0,
+        };
+        if !missing.is_empty() {
+            let input = select_inputs(&request.input, &missing);
+            let response = send(CreateEmbeddingRequest { input, ..request }).await?;
+            let vectors;
+            (model, usage, vectors) = response.into_parts()?;
+
+            let mut entries = Vec::with_capacity(vectors.len());
+            for (index, vector) in vectors {
+                if let Some(&position) = missing.get(index as usize) {
+                    entries.push((keys[position].clone(), vector.clone()));
+                    embeddings[position] = Some(vector);
+                }
+            }
+            if let Err(e) = cache.put(&entries).await {
+                tracing::warn!("Embedding cache update failed: {e}");
+            }
+        }
+
+        let embeddings = embeddings
+            .into_iter()
+            .enumerate()
+            .map(|(position, embedding)| {
+                embedding.ok_or_else(|| {
+                    OpenAIError::InvalidArgument(format!(
+                        "response has no embedding for input {position}"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(R::from_parts(model, usage, embeddings))
+    }
+}
+
+fn input_count(input: &EmbeddingInput) -> usize {
+    match input {
+        EmbeddingInput::String(_) | EmbeddingInput::IntegerArray(_) => 1,
+        EmbeddingInput::StringArray(inputs) => inputs.len(),
+        EmbeddingInput::ArrayOfIntegerArray(inputs) => inputs.len(),
+    }
+}
 
-        self.client.post("/embeddings", request).await
+/// A request validated before it is split, where only the batches have to respect the limit on the number of inputs.
+struct BatchedRequest<'a>(&'a CreateEmbeddingRequest);
+
+impl Validate for BatchedRequest<'_> {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let batched = input_count(&self.0.input) > 1;
+        self.0
+            .violations()
+            .into_iter()
+            .filter(|violation| !(batched && violation.param == "input"))
+            .collect()
     }
 }
 
@@ -0,0 +1,165 @@
+//! Embedding of any number of inputs with [crate::Embeddings::create_many].
+//!
+//! The inputs are split into requests within the per-request limits of the API, which are sent concurrently and
+//! retried when rate limited. The embeddings are returned in the order of the inputs, as if sent in one request.
+//!
+//! ```
+//! use async_openai_wasm::embedding_batch::EmbeddingBatchOptions;
+//! use async_openai_wasm::types::EmbeddingInput;
+//!
+//! let options = EmbeddingBatchOptions::default().with_max_inputs(2);
+//! let batches = options.split("text-embedding-3-small", EmbeddingInput::from(["a", "b", "c"]));
+//! assert_eq!(batches.len(), 2);
+//! ```
+use serde::de::DeserializeOwned;
+
+use crate::types::{
+    CreateBase64EmbeddingResponse, CreateEmbeddingResponse, EmbeddingInput, EmbeddingUsage,
+};
+use crate::util::BatchRequestOptions;
+
+/// Limits and retry policy of [crate::Embeddings::create_many].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingBatchOptions {
+    /// The maximum number of inputs of a request, 2048 by default.
+    pub max_inputs: usize,
+    /// The maximum number of tokens summed over the inputs of a request, 300,000 by default.
+    ///
+    /// Tokens are counted with the encoding of the model if the `tokenizer` feature is enabled, otherwise they
+    /// are estimated as one token per three bytes of text, which overestimates most text.
+    /// An input exceeding the limit on its own is sent alone.
+    pub max_tokens: usize,
+    /// Concurrency and retries of the requests.
+    pub requests: BatchRequestOptions,
+}
+
+impl Default for EmbeddingBatchOptions {
+    fn default() -> Self {
+        Self {
+            max_inputs: 2048,
+            max_tokens: 300_000,
+            requests: BatchRequestOptions::default(),
+        }
+    }
+}
+
+impl EmbeddingBatchOptions {
+    pub fn with_max_inputs(mut self, max_inputs: usize) -> Self {
+        self.max_inputs = max_inputs;
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn with_requests(mut self, requests: BatchRequestOptions) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Splits the inputs into consecutive batches within [Self::max_inputs] and [Self::max_tokens].
+    ///
+    /// A single string or token array is returned as is.
+    #[cfg_attr(not(feature = "tokenizer"), allow(unused_variables))]
+    pub fn split(&self, model: &str, input: EmbeddingInput) -> Vec<EmbeddingInput> {
+        #[cfg(feature = "tokenizer")]
+        let tokenizer = crate::tokenizer::Tokenizer::for_model(model).ok();
+        let count_tokens = |text: &str| {
+            #[cfg(feature = "tokenizer")]
+            if let Some(tokenizer) = &tokenizer {
+                return tokenizer.count(text);
+            }
+            (text.len() + 2) / 3
+        };
+
+        match input {
+            EmbeddingInput::StringArray(inputs) => self
+                .split_by(inputs, |text| count_tokens(text))
+                .into_iter()
+                .map(EmbeddingInput::StringArray)
+                .collect(),
+            EmbeddingInput::ArrayOfIntegerArray(inputs) => self
+                .split_by(inputs, Vec::len)
+                .into_iter()
+                .map(EmbeddingInput::ArrayOfIntegerArray)
+                .collect(),
+            input => vec![input],
+        }
+    }
+
+    fn split_by<T>(&self, inputs: Vec<T>, tokens: impl Fn(&T) -> usize) -> Vec<Vec<T>> {
+        let max_inputs = self.max_inputs.max(1);
+        let mut batches = vec![];
+        let mut batch = vec![];
+        let mut batch_tokens = 0;
+        for input in inputs {
+            let input_tokens = tokens(&input);
+            if !batch.is_empty()
+                && (batch.len() >= max_inputs || batch_tokens + input_tokens > self.max_tokens)
+            {
+                batches.push(std::mem::take(&mut batch));
+                batch_tokens = 0;
+            }
+            batch_tokens += input_tokens;
+            batch.push(input);
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+}
+
+/// Progress of [crate::Embeddings::create_many], reported after every completed request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingBatchProgress {
+    pub completed_requests: usize,
+    pub total_requests: usize,
+    pub completed_inputs: usize,
+    pub total_inputs: usize,
+    /// The usage of the completed requests.
+    pub usage: EmbeddingUsage,
+}
+
+/// A response of a batch, merged into one response for all inputs.
+pub(crate) trait BatchResponse: DeserializeOwned {
+    fn usage(&self) -> &EmbeddingUsage;
+
+    /// Merges responses of consecutive batches, given with the index of their first input.
+    fn merge(responses: Vec<(u32, Self)>) -> Self;
+}
+
+macro_rules! impl_batch_response {
+    ($response:ty) => {
+        impl BatchResponse for $response {
+            fn usage(&self) -> &EmbeddingUsage {
+                &self.usage
+            }
+
+            fn merge(responses: Vec<(u32, Self)>) -> Self {
+                let mut merged: Option<Self> = None;
+                for (offset, mut response) in responses {
+                    for embedding in &mut response.data {
+                        embedding.index += offset;
+                    }
+                    match &mut merged {
+                        Some(merged) => {
+                            merged.data.append(&mut response.data);
+                            merged.usage.prompt_tokens += response.usage.prompt_tokens;
+                            merged.usage.total_tokens += response.usage.total_tokens;
+                        }
+                        None => merged = Some(response),
+                    }
+                }
+                let mut merged = merged.expect("at least one batch");
+                merged.data.sort_by_key(|embedding| embedding.index);
+                merged
+            }
+        }
+    };
+}
+
+impl_batch_response!(CreateEmbeddingResponse);
+impl_batch_response!(CreateBase64EmbeddingResponse);
@@ -0,0 +1,444 @@
+//! Caching of embeddings per input, see [crate::Client::with_embedding_cache].
+//!
+//! Every string or token array of a request is looked up on its own, so only the inputs missing from the cache are
+//! sent to the API, and the cached and new embeddings are merged back in the order of the inputs. The usage of a
+//! response only counts the tokens of the inputs that were sent.
+//!
+//! Embeddings are cached by [EmbeddingCacheKey]: the model, the requested dimensions and encoding, and the SHA-256
+//! hash of the input. [MemoryEmbeddingCache] keeps them for the life of the process, [DiskEmbeddingCache] in a
+//! directory, and [KvEmbeddingCache] in any key-value store implementing [KeyValueStore], such as Cloudflare Workers KV.
+//!
+//! ```
+//! use async_openai_wasm::embedding_cache::MemoryEmbeddingCache;
+//! use async_openai_wasm::Client;
+//!
+//! let client = Client::new().with_embedding_cache(MemoryEmbeddingCache::new());
+//! ```
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use base64::engine::{general_purpose, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::error::OpenAIError;
+use crate::types::{
+    Base64Embedding, Base64EmbeddingVector, CreateBase64EmbeddingResponse, CreateEmbeddingRequest,
+    CreateEmbeddingResponse, Embedding, EmbeddingInput, EmbeddingUsage, EncodingFormat,
+};
+
+/// `Send + Sync` except on wasm32, where JS handles such as a Workers KV binding are neither.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync + ?Sized> MaybeSendSync for T {}
+
+/// `Send + Sync` except on wasm32, where JS handles such as a Workers KV binding are neither.
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T: ?Sized> MaybeSendSync for T {}
+
+/// The future returned by caches, `Send` except on wasm32.
+#[cfg(not(target_arch = "wasm32"))]
+pub type CacheFuture<'a, T> = futures::future::BoxFuture<'a, Result<T, OpenAIError>>;
+/// The future returned by caches, `Send` except on wasm32.
+#[cfg(target_arch = "wasm32")]
+pub type CacheFuture<'a, T> = futures::future::LocalBoxFuture<'a, Result<T, OpenAIError>>;
+
+/// Identifies the embedding of one input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmbeddingCacheKey {
+    pub model: String,
+    pub dimensions: Option<u32>,
+    pub encoding_format: EncodingFormat,
+    /// SHA-256 of the text, or of the little-endian tokens, with a prefix to tell both apart.
+    pub hash: [u8; 32],
+}
+
+impl EmbeddingCacheKey {
+    /// The keys of every input of a request, in order.
+    pub fn for_request(request: &CreateEmbeddingRequest) -> Vec<Self> {
+        let key = |hash| Self {
+            model: request.model.clone(),
+            dimensions: request.dimensions,
+            encoding_format: request.encoding_format.clone().unwrap_or_default(),
+            hash,
+        };
+        match &request.input {
+            EmbeddingInput::String(text) => vec![key(text_hash(text))],
+            EmbeddingInput::StringArray(texts) => {
+                texts.iter().map(|text| key(text_hash(text))).collect()
+            }
+            EmbeddingInput::IntegerArray(tokens) => vec![key(tokens_hash(tokens))],
+            EmbeddingInput::ArrayOfIntegerArray(inputs) => inputs
+                .iter()
+                .map(|tokens| key(tokens_hash(tokens)))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for EmbeddingCacheKey {
+    /// `{model}/{dimensions}/{encoding}/{hash}`, with `default` dimensions if not requested and a hex hash.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/", self.model)?;
+        match self.dimensions {
+            Some(dimensions) => write!(f, "{dimensions}/")?,
+            None => f.write_str("default/")?,
+        }
+        match self.encoding_format {
+            EncodingFormat::Float => f.write_str("float/")?,
+            EncodingFormat::Base64 => f.write_str("base64/")?,
+        }
+        self.hash
+            .iter()
+            .try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+fn text_hash(text: &str) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(b"text:")
+        .chain_update(text.as_bytes())
+        .finalize()
+        .into()
+}
+
+fn tokens_hash(tokens: &[u32]) -> [u8; 32] {
+    let mut hasher = Sha256::new().chain_update(b"tokens:");
+    for token in tokens {
+        hasher.update(token.to_le_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// A store of embeddings by input.
+///
+/// Errors of a cache are logged and otherwise ignored by the client: a failed lookup counts as a miss.
+pub trait EmbeddingCache: MaybeSendSync {
+    /// The cached embedding of every key, `None` for a miss.
+    fn get<'a>(&'a self, keys: &'a [EmbeddingCacheKey]) -> CacheFuture<'a, Vec<Option<Vec<f32>>>>;
+
+    /// Stores new embeddings.
+    fn put<'a>(&'a self, entries: &'a [(EmbeddingCacheKey, Vec<f32>)]) -> CacheFuture<'a, ()>;
+}
+
+/// The cache of a [crate::Client], which is `Debug` and `Clone` whatever the cache.
+#[derive(Clone)]
+pub(crate) struct SharedEmbeddingCache(pub(crate) Arc<dyn EmbeddingCache>);
+
+impl std::fmt::Debug for SharedEmbeddingCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EmbeddingCache")
+    }
+}
+
+/// Caches embeddings in memory. Clones share the same embeddings.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryEmbeddingCache {
+    embeddings: Arc<Mutex<HashMap<EmbeddingCacheKey, Vec<f32>>>>,
+}
+
+impl MemoryEmbeddingCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.embeddings.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.embeddings.lock().unwrap().clear();
+    }
+}
+
+impl EmbeddingCache for MemoryEmbeddingCache {
+    fn get<'a>(&'a self, keys: &'a [EmbeddingCacheKey]) -> CacheFuture<'a, Vec<Option<Vec<f32>>>> {
+        let embeddings = self.embeddings.lock().unwrap();
+        let hits = keys
+            .iter()
+            .map(|key| embeddings.get(key).cloned())
+            .collect();
+        Box::pin(futures::future::ready(Ok(hits)))
+    }
+
+    fn put<'a>(&'a self, entries: &'a [(EmbeddingCacheKey, Vec<f32>)]) -> CacheFuture<'a, ()> {
+        self.embeddings
+            .lock()
+            .unwrap()
+            .extend(entries.iter().cloned());
+        Box::pin(futures::future::ready(Ok(())))
+    }
+}
+
+/// Caches embeddings as files in a directory, one file per input.
+///
+/// File names are the SHA-256 hash of the [EmbeddingCacheKey], so any model name is a valid path.
+/// Files are read and written synchronously, and replaced atomically so that other processes can share the directory.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct DiskEmbeddingCache {
+    directory: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DiskEmbeddingCache {
+    /// A cache in `directory`, which is created if it does not exist.
+    pub fn new(directory: impl Into<std::path::PathBuf>) -> Result<Self, OpenAIError> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| OpenAIError::FileSaveError(format!("{}: {e}", directory.display())))?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, key: &EmbeddingCacheKey) -> std::path::PathBuf {
+        use std::fmt::Write;
+
+        let hash = Sha256::digest(key.to_string().as_bytes());
+        let mut name = String::with_capacity(64);
+        hash.iter()
+            .for_each(|byte| write!(name, "{byte:02x}").expect("write to string"));
+        self.directory.join(name)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl EmbeddingCache for DiskEmbeddingCache {
+    fn get<'a>(&'a self, keys: &'a [EmbeddingCacheKey]) -> CacheFuture<'a, Vec<Option<Vec<f32>>>> {
+        let hits = keys
+            .iter()
+            .map(|key| {
+                std::fs::read(self.path(key))
+                    .ok()
+                    .and_then(|bytes| decode(&bytes))
+            })
+            .collect();
+        Box::pin(futures::future::ready(Ok(hits)))
+    }
+
+    fn put<'a>(&'a self, entries: &'a [(EmbeddingCacheKey, Vec<f32>)]) -> CacheFuture<'a, ()> {
+        static WRITES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        let result = entries.iter().try_for_each(|(key, embedding)| {
+            // written to a file of its own then renamed, so concurrent readers never see a partial file
+            let path = self.path(key);
+            let write = WRITES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let temporary = path.with_extension(format!("{}-{write}.tmp", std::process::id()));
+            std::fs::write(&temporary, encode(embedding))
+                .and_then(|_| std::fs::rename(&temporary, &path))
+                .map_err(|e| {
+                    let _ = std::fs::remove_file(&temporary);
+                    OpenAIError::FileSaveError(format!("{}: {e}", path.display()))
+                })
+        });
+        Box::pin(futures::future::ready(result))
+    }
+}
+
+/// A key-value store holding bytes, such as Cloudflare Workers KV or a Redis client, used by [KvEmbeddingCache].
+pub trait KeyValueStore: MaybeSendSync {
+    /// The value under `key`, `None` if there is none.
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<Vec<u8>>>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> CacheFuture<'a, ()>;
+}
+
+/// Caches embeddings in a [KeyValueStore], under the [EmbeddingCacheKey] with a prefix.
+///
+/// Keys are looked up one at a time, concurrently.
+#[derive(Debug, Clone)]
+pub struct KvEmbeddingCache<S> {
+    store: S,
+    prefix: String,
+}
+
+impl<S: KeyValueStore> KvEmbeddingCache<S> {
+    /// A cache with keys prefixed by `embeddings/`.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            prefix: "embeddings/".into(),
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, key: &EmbeddingCacheKey) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+impl<S: KeyValueStore> EmbeddingCache for KvEmbeddingCache<S> {
+    fn get<'a>(&'a self, keys: &'a [EmbeddingCacheKey]) -> CacheFuture<'a, Vec<Option<Vec<f32>>>> {
+        Box::pin(async move {
+            let keys: Vec<String> = keys.iter().map(|key| self.key(key)).collect();
+            let values =
+                futures::future::try_join_all(keys.iter().map(|key| self.store.get(key))).await?;
+            Ok(values
+                .into_iter()
+                .map(|value| value.and_then(|bytes| decode(&bytes)))
+                .collect())
+        })
+    }
+
+    fn put<'a>(&'a self, entries: &'a [(EmbeddingCacheKey, Vec<f32>)]) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            let keys: Vec<String> = entries.iter().map(|(key, _)| self.key(key)).collect();
+            futures::future::try_join_all(
+                keys.iter()
+                    .zip(entries)
+                    .map(|(key, (_, embedding))| self.store.put(key, encode(embedding))),
+            )
+            .await?;
+            Ok(())
+        })
+    }
+}
+
+/// The length of the header of a cached embedding: the number of dimensions and a checksum of the values.
+const HEADER_LEN: usize = 8;
+
+/// The first bytes of the SHA-256 hash of the encoded values.
+fn checksum(values: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(values);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// The values of an embedding as little-endian `f32`.
+fn le_bytes(embedding: &[f32]) -> Vec<u8> {
+    embedding
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Encodes an embedding as a [HEADER_LEN] byte header followed by its [le_bytes].
+fn encode(embedding: &[f32]) -> Vec<u8> {
+    let values = le_bytes(embedding);
+    let mut bytes = Vec::with_capacity(HEADER_LEN + values.len());
+    bytes.extend_from_slice(&(embedding.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&checksum(&values));
+    bytes.extend_from_slice(&values);
+    bytes
+}
+
+/// Decodes an [encode]d embedding, `None` if the bytes are truncated or damaged.
+fn decode(bytes: &[u8]) -> Option<Vec<f32>> {
+    if bytes.len() <= HEADER_LEN {
+        return None;
+    }
+    let (header, values) = bytes.split_at(HEADER_LEN);
+    let dimensions = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if dimensions.checked_mul(4) != Some(values.len()) || header[4..] != checksum(values) {
+        return None;
+    }
+    Some(
+        values
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect(),
+    )
+}
+
+/// The model, the usage and the embeddings with their index of a response.
+pub(crate) type ResponseParts = (String, EmbeddingUsage, Vec<(u32, Vec<f32>)>);
+
+/// A response whose embeddings are cached and merged with cached ones.
+pub(crate) trait CachedResponse: Sized {
+    /// The model, the usage and the embeddings with their index.
+    fn into_parts(self) -> Result<ResponseParts, OpenAIError>;
+
+    /// A response with embeddings in the order of the inputs.
+    fn from_parts(model: String, usage: EmbeddingUsage, embeddings: Vec<Vec<f32>>) -> Self;
+}
+
+impl CachedResponse for CreateEmbeddingResponse {
+    fn into_parts(self) -> Result<ResponseParts, OpenAIError> {
+        let embeddings = self
+            .data
+            .into_iter()
+            .map(|embedding| (embedding.index, embedding.embedding))
+            .collect();
+        Ok((self.model, self.usage, embeddings))
+    }
+
+    fn from_parts(model: String, usage: EmbeddingUsage, embeddings: Vec<Vec<f32>>) -> Self {
+        Self {
+            object: "list".into(),
+            model,
+            data: embeddings
+                .into_iter()
+                .enumerate()
+                .map(|(index, embedding)| Embedding {
+                    index: index as u32,
+                    object: "embedding".into(),
+                    embedding,
+                })
+                .collect(),
+            usage,
+        }
+    }
+}
+
+impl CachedResponse for CreateBase64EmbeddingResponse {
+    fn into_parts(self) -> Result<ResponseParts, OpenAIError> {
+        let mut buffer = vec![];
+        let embeddings = self
+            .data
+            .into_iter()
+            .map(|embedding| {
+                let mut values = vec![];
+                embedding.embedding.decode_into(&mut buffer, &mut values)?;
+                Ok((embedding.index, values))
+            })
+            .collect::<Result<_, OpenAIError>>()?;
+        Ok((self.model, self.usage, embeddings))
+    }
+
+    fn from_parts(model: String, usage: EmbeddingUsage, embeddings: Vec<Vec<f32>>) -> Self {
+        Self {
+            object: "list".into(),
+            model,
+            data: embeddings
+                .into_iter()
+                .enumerate()
+                .map(|(index, embedding)| Base64Embedding {
+                    index: index as u32,
+                    object: "embedding".into(),
+                    embedding: Base64EmbeddingVector(
+                        general_purpose::STANDARD.encode(le_bytes(&embedding)),
+                    ),
+                })
+                .collect(),
+            usage,
+        }
+    }
+}
+
+/// The inputs at the given positions, in the same form as `input`.
+pub(crate) fn select_inputs(input: &EmbeddingInput, positions: &[usize]) -> EmbeddingInput {
+    match input {
+        EmbeddingInput::StringArray(inputs) => EmbeddingInput::StringArray(
+            positions
+                .iter()
+                .map(|&position| inputs[position].clone())
+                .collect(),
+        ),
+        EmbeddingInput::ArrayOfIntegerArray(inputs) => EmbeddingInput::ArrayOfIntegerArray(
+            positions
+                .iter()
+                .map(|&position| inputs[position].clone())
+                .collect(),
+        ),
+        input => input.clone(),
+    }
+}
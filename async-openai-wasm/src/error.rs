@@ -1,6 +1,8 @@
 //! Errors originating from API calls, parsing responses, and reading-or-writing to the file system.
 use serde::Deserialize;
 
+use crate::types::FinishReason;
+
 #[derive(Debug, thiserror::Error)]
 pub enum OpenAIError {
     /// Underlying error from reqwest library after an API call was made
@@ -25,6 +27,36 @@ pub enum OpenAIError {
     /// or when builder fails to build request before making API call
     #[error("invalid args: {0}")]
     InvalidArgument(String),
+    /// Error from client side validation of request parameters, see [crate::types::Validate],
+    /// with every parameter violating a constraint of the API
+    #[error("invalid args: {}", display_details(.0))]
+    InvalidArguments(Vec<InvalidArgumentDetail>),
+    /// Error when a response cannot be parsed because the model stopped generating early,
+    /// either on reaching the maximum number of tokens (`length`) or due to the content filter (`content_filter`)
+    #[error("response is incomplete and cannot be parsed (finish_reason: {0:?})")]
+    IncompleteResponse(FinishReason),
+}
+
+/// A request parameter violating a constraint of the API
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidArgumentDetail {
+    /// Path of the parameter, e.g. `messages` or `tools[0].function.name`
+    pub param: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for InvalidArgumentDetail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.param, self.message)
+    }
+}
+
+fn display_details(details: &[InvalidArgumentDetail]) -> String {
+    details
+        .iter()
+        .map(|detail| detail.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// OpenAI API returns error object on failure
@@ -31,6 +31,7 @@ impl<'c, C: Config> FineTuning<'c, C> {
         &self,
         request: CreateFineTuningJobRequest,
     ) -> Result<FineTuningJob, OpenAIError> {
+        self.client.validate_request(&request)?;
         self.client.post("/fine_tuning/jobs", request).await
     }
 
@@ -0,0 +1,370 @@
+//! Conversion between the deprecated `functions`/`function_call` style of chat requests and `tools`/`tool_choice`.
+//!
+//! Some OpenAI-compatible servers only support one of the two styles. Set the style a server supports with
+//! [crate::config::OpenAIConfig::with_function_calling_style] and [crate::Chat] rewrites requests in the other style to it,
+//! converting responses back to the style of the request:
+//!
+//! ```
+//! use async_openai_wasm::config::OpenAIConfig;
+//! use async_openai_wasm::function_calling::FunctionCallingStyle;
+//!
+//! // a server that predates tools
+//! let config = OpenAIConfig::new()
+//!     .with_api_base("http://localhost:8000/v1")
+//!     .with_function_calling_style(FunctionCallingStyle::Functions);
+//! ```
+//!
+//! Legacy function calling makes at most one call per assistant message: requests rewritten to tools disable
+//! parallel tool calls, and assistant messages with several tool calls are split when rewritten to functions.
+#![allow(deprecated)]
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::error::OpenAIError;
+use crate::types::{
+    ChatCompletionFunctionCall, ChatCompletionFunctions, ChatCompletionMessageToolCall,
+    ChatCompletionMessageToolCallChunk, ChatCompletionNamedToolChoice,
+    ChatCompletionRequestAssistantMessage, ChatCompletionRequestFunctionMessage,
+    ChatCompletionRequestMessage, ChatCompletionRequestToolMessage,
+    ChatCompletionRequestToolMessageContent, ChatCompletionRequestToolMessageContentPart,
+    ChatCompletionTool, ChatCompletionToolChoiceOption, ChatCompletionToolType,
+    CreateChatCompletionRequest, CreateChatCompletionResponse, CreateChatCompletionStreamResponse,
+    FinishReason, FunctionName, FunctionObject,
+};
+
+/// The function calling style supported by a server, see the [module docs](self).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunctionCallingStyle {
+    /// Requests are sent as they are.
+    #[default]
+    Unchanged,
+    /// The server supports `tools` and `tool_choice`, legacy functions are rewritten to tools.
+    Tools,
+    /// The server supports `functions` and `function_call`, tools are rewritten to legacy functions.
+    Functions,
+}
+
+impl FunctionCallingStyle {
+    /// Rewrites a request in the other style to this style.
+    ///
+    /// Returns whether the request was rewritten, in which case responses to it are converted back with
+    /// [FunctionCallingStyle::restore_response] and [FunctionCallingStyle::restore_chunk].
+    pub fn convert_request(
+        &self,
+        request: &mut CreateChatCompletionRequest,
+    ) -> Result<bool, OpenAIError> {
+        match self {
+            Self::Tools if uses_functions(request) => functions_to_tools(request).map(|_| true),
+            Self::Functions if uses_tools(request) => tools_to_functions(request).map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Converts a response of the server in this style back to the style of the original request.
+    pub fn restore_response(&self, response: &mut CreateChatCompletionResponse) {
+        match self {
+            Self::Unchanged => {}
+            Self::Tools => response_tools_to_functions(response),
+            Self::Functions => response_functions_to_tools(response),
+        }
+    }
+
+    /// Converts a chunk of the server in this style back to the style of the original request.
+    pub fn restore_chunk(&self, chunk: &mut CreateChatCompletionStreamResponse) {
+        match self {
+            Self::Unchanged => {}
+            Self::Tools => chunk_tools_to_functions(chunk),
+            Self::Functions => chunk_functions_to_tools(chunk),
+        }
+    }
+}
+
+fn uses_functions(request: &CreateChatCompletionRequest) -> bool {
+    request.functions.is_some()
+        || request.function_call.is_some()
+        || request.messages.iter().any(|message| match message {
+            ChatCompletionRequestMessage::Assistant(message) => message.function_call.is_some(),
+            ChatCompletionRequestMessage::Function(_) => true,
+            _ => false,
+        })
+}
+
+fn uses_tools(request: &CreateChatCompletionRequest) -> bool {
+    request.tools.is_some()
+        || request.tool_choice.is_some()
+        || request.messages.iter().any(|message| match message {
+            ChatCompletionRequestMessage::Assistant(message) => message.tool_calls.is_some(),
+            ChatCompletionRequestMessage::Tool(_) => true,
+            _ => false,
+        })
+}
+
+fn tool_call_id(id: &str, index: u32) -> String {
+    format!("call_{id}_{index}")
+}
+
+/// Rewrites `functions`, `function_call` and function messages of a request to tools.
+///
+/// Function calls of assistant messages get generated IDs, which the function messages that follow them are matched to by name.
+pub fn functions_to_tools(request: &mut CreateChatCompletionRequest) -> Result<(), OpenAIError> {
+    if let Some(functions) = request.functions.take() {
+        request
+            .tools
+            .get_or_insert_with(Vec::new)
+            .extend(functions.into_iter().map(|function| ChatCompletionTool {
+                r#type: ChatCompletionToolType::Function,
+                function: FunctionObject {
+                    name: function.name,
+                    description: function.description,
+                    parameters: Some(function.parameters),
+                    strict: None,
+                },
+            }));
+        // legacy responses can hold a single function call only
+        request.parallel_tool_calls = Some(false);
+    }
+    if let Some(function_call) = request.function_call.take() {
+        request.tool_choice = Some(match function_call {
+            ChatCompletionFunctionCall::None => ChatCompletionToolChoiceOption::None,
+            ChatCompletionFunctionCall::Auto => ChatCompletionToolChoiceOption::Auto,
+            ChatCompletionFunctionCall::Function { name } => {
+                ChatCompletionToolChoiceOption::Named(ChatCompletionNamedToolChoice {
+                    r#type: ChatCompletionToolType::Function,
+                    function: FunctionName { name },
+                })
+            }
+        });
+    }
+
+    // IDs of the calls without a result yet, by function name
+    let mut pending: HashMap<String, String> = HashMap::new();
+    for (index, message) in request.messages.iter_mut().enumerate() {
+        match message {
+            ChatCompletionRequestMessage::Assistant(message) => {
+                if let Some(function_call) = message.function_call.take() {
+                    let id = tool_call_id("legacy", index as u32);
+                    pending.insert(function_call.name.clone(), id.clone());
+                    message.tool_calls.get_or_insert_with(Vec::new).push(
+                        ChatCompletionMessageToolCall {
+                            id,
+                            r#type: ChatCompletionToolType::Function,
+                            function: function_call,
+                        },
+                    );
+                }
+            }
+            ChatCompletionRequestMessage::Function(function_message) => {
+                let id = pending.remove(&function_message.name).ok_or_else(|| {
+                    OpenAIError::InvalidArgument(format!(
+                        "function message `{}` does not follow a call of the function",
+                        function_message.name
+                    ))
+                })?;
+                let content = function_message.content.take().unwrap_or_default();
+                *message = ChatCompletionRequestMessage::Tool(ChatCompletionRequestToolMessage {
+                    content: ChatCompletionRequestToolMessageContent::Text(content),
+                    tool_call_id: id,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites `tools`, `tool_choice` and tool messages of a request to legacy functions.
+///
+/// An assistant message with several tool calls is split into one assistant message per call, each following the result of the previous call.
+/// Fails when a tool call has no result, and for a `required` tool choice with more than one function, which has no legacy equivalent.
+pub fn tools_to_functions(request: &mut CreateChatCompletionRequest) -> Result<(), OpenAIError> {
+    if let Some(tools) = request.tools.take() {
+        request
+            .functions
+            .get_or_insert_with(Vec::new)
+            .extend(tools.into_iter().map(|tool| {
+                ChatCompletionFunctions {
+                    name: tool.function.name,
+                    description: tool.function.description,
+                    parameters: tool
+                        .function
+                        .parameters
+                        .unwrap_or_else(|| serde_json::json!({"type": "object", "properties": {}})),
+                }
+            }));
+    }
+    request.parallel_tool_calls = None;
+    if let Some(tool_choice) = request.tool_choice.take() {
+        request.function_call = Some(match tool_choice {
+            ChatCompletionToolChoiceOption::None => ChatCompletionFunctionCall::None,
+            ChatCompletionToolChoiceOption::Auto => ChatCompletionFunctionCall::Auto,
+            ChatCompletionToolChoiceOption::Named(choice) => ChatCompletionFunctionCall::Function {
+                name: choice.function.name,
+            },
+            ChatCompletionToolChoiceOption::Required => match request.functions.as_deref() {
+                Some([function]) => ChatCompletionFunctionCall::Function {
+                    name: function.name.clone(),
+                },
+                _ => return Err(OpenAIError::InvalidArgument(
+                    "tool_choice `required` has no legacy equivalent with more than one function"
+                        .into(),
+                )),
+            },
+        });
+    }
+
+    // the assistant message whose tool calls wait for their results, and the results received so far
+    let mut pending: Option<(
+        ChatCompletionRequestAssistantMessage,
+        Vec<ChatCompletionMessageToolCall>,
+    )> = None;
+    let mut results = HashMap::new();
+    let mut messages = Vec::with_capacity(request.messages.len());
+    for message in std::mem::take(&mut request.messages) {
+        if let ChatCompletionRequestMessage::Tool(message) = message {
+            let name = pending
+                .as_ref()
+                .and_then(|(_, tool_calls)| {
+                    tool_calls
+                        .iter()
+                        .find(|tool_call| tool_call.id == message.tool_call_id)
+                })
+                .map(|tool_call| tool_call.function.name.clone())
+                .ok_or_else(|| {
+                    OpenAIError::InvalidArgument(format!(
+                        "tool message `{}` does not follow a tool call with its ID",
+                        message.tool_call_id
+                    ))
+                })?;
+            let content = match message.content {
+                ChatCompletionRequestToolMessageContent::Text(text) => text,
+                ChatCompletionRequestToolMessageContent::Array(parts) => parts
+                    .into_iter()
+                    .map(|part| match part {
+                        ChatCompletionRequestToolMessageContentPart::Text(part) => part.text,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            };
+            results.insert(
+                message.tool_call_id,
+                ChatCompletionRequestFunctionMessage {
+                    content: Some(content),
+                    name,
+                },
+            );
+            continue;
+        }
+        if let Some((assistant, tool_calls)) = pending.take() {
+            split_tool_calls(&mut messages, assistant, tool_calls, &mut results)?;
+        }
+        match message {
+            ChatCompletionRequestMessage::Assistant(mut message) => {
+                match message
+                    .tool_calls
+                    .take()
+                    .filter(|tool_calls| !tool_calls.is_empty())
+                {
+                    Some(tool_calls) => pending = Some((message, tool_calls)),
+                    None => messages.push(ChatCompletionRequestMessage::Assistant(message)),
+                }
+            }
+            message => messages.push(message),
+        }
+    }
+    if let Some((assistant, tool_calls)) = pending {
+        split_tool_calls(&mut messages, assistant, tool_calls, &mut results)?;
+    }
+    request.messages = messages;
+    Ok(())
+}
+
+/// Pushes an assistant message per tool call, in the order of the calls, each followed by the result of its call.
+fn split_tool_calls(
+    messages: &mut Vec<ChatCompletionRequestMessage>,
+    mut assistant: ChatCompletionRequestAssistantMessage,
+    tool_calls: Vec<ChatCompletionMessageToolCall>,
+    results: &mut HashMap<String, ChatCompletionRequestFunctionMessage>,
+) -> Result<(), OpenAIError> {
+    for tool_call in tool_calls {
+        let result = results.remove(&tool_call.id).ok_or_else(|| {
+            OpenAIError::InvalidArgument(format!(
+                "tool call `{}` is not followed by a tool message with its result",
+                tool_call.id
+            ))
+        })?;
+        assistant.function_call = Some(tool_call.function);
+        messages.push(ChatCompletionRequestMessage::Assistant(std::mem::take(
+            &mut assistant,
+        )));
+        messages.push(ChatCompletionRequestMessage::Function(result));
+    }
+    Ok(())
+}
+
+/// Converts the tool calls of a response to a legacy function call. Only the first tool call of a message is kept.
+pub fn response_tools_to_functions(response: &mut CreateChatCompletionResponse) {
+    for choice in &mut response.choices {
+        if let Some(tool_calls) = choice.message.tool_calls.take() {
+            choice.message.function_call = tool_calls
+                .into_iter()
+                .next()
+                .map(|tool_call| tool_call.function);
+        }
+        if choice.finish_reason == Some(FinishReason::ToolCalls) {
+            choice.finish_reason = Some(FinishReason::FunctionCall);
+        }
+    }
+}
+
+/// Converts the legacy function call of a response to a tool call, with an ID generated from the response ID.
+pub fn response_functions_to_tools(response: &mut CreateChatCompletionResponse) {
+    for choice in &mut response.choices {
+        if let Some(function_call) = choice.message.function_call.take() {
+            choice.message.tool_calls = Some(vec![ChatCompletionMessageToolCall {
+                id: tool_call_id(&response.id, choice.index),
+                r#type: ChatCompletionToolType::Function,
+                function: function_call,
+            }]);
+        }
+        if choice.finish_reason == Some(FinishReason::FunctionCall) {
+            choice.finish_reason = Some(FinishReason::ToolCalls);
+        }
+    }
+}
+
+/// Same as [response_tools_to_functions] for a chunk of a streamed response.
+pub fn chunk_tools_to_functions(chunk: &mut CreateChatCompletionStreamResponse) {
+    for choice in &mut chunk.choices {
+        if let Some(tool_calls) = choice.delta.tool_calls.take() {
+            choice.delta.function_call = tool_calls
+                .into_iter()
+                .find(|tool_call| tool_call.index == 0)
+                .and_then(|tool_call| tool_call.function);
+        }
+        if choice.finish_reason == Some(FinishReason::ToolCalls) {
+            choice.finish_reason = Some(FinishReason::FunctionCall);
+        }
+    }
+}
+
+/// Same as [response_functions_to_tools] for a chunk of a streamed response.
+/// The ID and type of the tool call are sent with the chunk that starts the function call.
+pub fn chunk_functions_to_tools(chunk: &mut CreateChatCompletionStreamResponse) {
+    for choice in &mut chunk.choices {
+        if let Some(function_call) = choice.delta.function_call.take() {
+            let starts = function_call.name.is_some();
+            choice.delta.tool_calls = Some(vec![ChatCompletionMessageToolCallChunk {
+                index: 0,
+                id: starts.then(|| tool_call_id(&chunk.id, choice.index)),
+                r#type: starts.then_some(ChatCompletionToolType::Function),
+                function: Some(function_call),
+            }]);
+        }
+        if choice.finish_reason == Some(FinishReason::FunctionCall) {
+            choice.finish_reason = Some(FinishReason::ToolCalls);
+        }
+    }
+}
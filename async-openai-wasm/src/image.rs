@@ -21,6 +21,7 @@ impl<'c, C: Config> Images<'c, C> {
 
     /// Creates an image given a prompt.
     pub async fn create(&self, request: CreateImageRequest) -> Result<ImagesResponse, OpenAIError> {
+        self.client.validate_request(&request)?;
         self.client.post("/images/generations", request).await
     }
 
@@ -81,25 +81,42 @@
 mod assistant_files;
 mod assistants;
 mod audio;
+pub mod audio_format;
 mod batches;
+pub mod capabilities;
 mod chat;
+pub mod chunking;
 mod client;
 mod completion;
 pub mod config;
+pub mod conversation;
+pub mod dataset;
 mod embedding;
+pub mod embedding_batch;
+pub mod embedding_cache;
 pub mod error;
 mod file;
 mod fine_tuning;
+pub mod function_calling;
 mod image;
 mod message_files;
 mod messages;
 mod model;
 mod moderation;
+pub mod partial_json;
 mod runs;
+pub mod speech_batch;
 mod steps;
+pub mod stream;
+pub mod subtitle;
 mod threads;
+#[cfg(feature = "tokenizer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokenizer")))]
+pub mod tokenizer;
+pub mod transcription_batch;
 pub mod types;
 mod util;
+pub mod vector;
 mod vector_store_file_batches;
 mod vector_store_files;
 mod vector_stores;
@@ -122,6 +139,7 @@ pub use moderation::Moderations;
 pub use runs::Runs;
 pub use steps::Steps;
 pub use threads::Threads;
+pub use util::BatchRequestOptions;
 pub use vector_store_file_batches::VectorStoreFileBatches;
 pub use vector_store_files::VectorStoreFiles;
 pub use vector_stores::VectorStores;
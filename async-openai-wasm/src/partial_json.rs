@@ -0,0 +1,436 @@
+//! Tolerant incremental parsing of JSON documents which are still being generated.
+//!
+//! Structured outputs and tool call arguments are streamed as fragments of a JSON document.
+//! [PartialJsonParser] consumes those fragments as they arrive and gives a best-effort [serde_json::Value]
+//! snapshot of everything received so far, closing any open strings, arrays and objects.
+//!
+//! ```
+//! use async_openai_wasm::partial_json::PartialJsonParser;
+//! use serde_json::json;
+//!
+//! let mut parser = PartialJsonParser::new();
+//! parser.push(r#"{"title": "The Rust Prog"#).unwrap();
+//! assert_eq!(parser.snapshot(), Some(json!({"title": "The Rust Prog"})));
+//!
+//! parser.push(r#"ramming Language", "tags": ["rust", "#).unwrap();
+//! assert_eq!(parser.snapshot(), Some(json!({"title": "The Rust Programming Language", "tags": ["rust"]})));
+//! ```
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+use crate::error::OpenAIError;
+
+#[derive(Debug, Clone)]
+enum Frame {
+    Object {
+        map: Map<String, Value>,
+        key: Option<String>,
+    },
+    Array(Vec<Value>),
+}
+
+/// What the parser accepts next outside of strings, numbers and literals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    /// The start of a value, e.g. after `:` or after `,` in an array.
+    Value,
+    /// A value or `]`, right after `[`.
+    ValueOrClose,
+    /// An object key, after `,` in an object.
+    Key,
+    /// An object key or `}`, right after `{`.
+    KeyOrClose,
+    /// The `:` after an object key.
+    Colon,
+    /// A `,` or the end of the enclosing array or object, after a value.
+    CommaOrClose,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    None,
+    String {
+        buffer: String,
+        is_key: bool,
+        escape: Escape,
+    },
+    Number(String),
+    Literal(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Escape {
+    None,
+    Backslash,
+    Unicode {
+        digits: String,
+        high_surrogate: Option<u16>,
+    },
+    /// A high surrogate was decoded, the backslash of the low surrogate `\uXXXX` is expected next.
+    PendingLow(u16),
+    /// The `u` of the low surrogate is expected next.
+    PendingLowU(u16),
+}
+
+/// Incremental parser of a single JSON document, fed with fragments of the document as they arrive.
+///
+/// Anything before the first `{` or `[` (e.g. whitespace or a markdown code fence) and anything
+/// after the end of the document is ignored.
+#[derive(Debug, Clone)]
+pub struct PartialJsonParser {
+    stack: Vec<Frame>,
+    token: Token,
+    expect: Expect,
+    root: Option<Value>,
+    started: bool,
+    error: Option<String>,
+    include_incomplete_strings: bool,
+}
+
+impl Default for PartialJsonParser {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            token: Token::None,
+            expect: Expect::Value,
+            root: None,
+            started: false,
+            error: None,
+            include_incomplete_strings: true,
+        }
+    }
+}
+
+impl PartialJsonParser {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether strings which are still being generated are included in snapshots. Defaults to `true`.
+    ///
+    /// Disable this to only ever see complete string values, e.g. for enums or identifiers.
+    pub fn with_incomplete_strings(mut self, include: bool) -> Self {
+        self.include_incomplete_strings = include;
+        self
+    }
+
+    /// Feeds the next fragment of the document.
+    ///
+    /// Returns [OpenAIError::InvalidArgument] once the input can no longer be a valid JSON document,
+    /// after which further fragments are ignored and [PartialJsonParser::snapshot] keeps returning the last valid state.
+    pub fn push(&mut self, fragment: &str) -> Result<(), OpenAIError> {
+        if let Some(error) = &self.error {
+            return Err(OpenAIError::InvalidArgument(error.clone()));
+        }
+        for c in fragment.chars() {
+            if self.root.is_some() {
+                break;
+            }
+            if let Err(error) = self.push_char(c) {
+                self.error = Some(error.clone());
+                return Err(OpenAIError::InvalidArgument(error));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the complete document has been received.
+    pub fn is_complete(&self) -> bool {
+        self.root.is_some()
+    }
+
+    /// Whether the input stopped being valid JSON.
+    pub fn is_invalid(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// Best-effort value of the document received so far, `None` until the document has started.
+    ///
+    /// Open strings, arrays and objects are closed. Object keys whose value has not started yet are omitted,
+    /// as are numbers and literals (`true`, `false`, `null`) until they end, since they cannot be completed unambiguously.
+    ///
+    /// Every call clones the document received so far: taking a snapshot after every fragment of a stream costs
+    /// quadratic time in the length of the document.
+    pub fn snapshot(&self) -> Option<Value> {
+        if let Some(root) = &self.root {
+            return Some(root.clone());
+        }
+        if self.stack.is_empty() {
+            return None;
+        }
+
+        let mut child = self.partial_token();
+        for frame in self.stack.iter().rev() {
+            child = Some(match frame {
+                Frame::Object { map, key } => {
+                    let mut map = map.clone();
+                    if let (Some(key), Some(child)) = (key, child) {
+                        map.insert(key.clone(), child);
+                    }
+                    Value::Object(map)
+                }
+                Frame::Array(items) => {
+                    let mut items = items.clone();
+                    items.extend(child);
+                    Value::Array(items)
+                }
+            });
+        }
+        child
+    }
+
+    /// Deserializes the current snapshot into `T`, `None` if the snapshot does not fit `T` yet.
+    ///
+    /// For this to be useful while the document is incomplete, all fields of `T` should be `Option`s
+    /// or have `#[serde(default)]`.
+    pub fn snapshot_as<T: DeserializeOwned>(&self) -> Option<T> {
+        self.snapshot()
+            .and_then(|value| serde_json::from_value(value).ok())
+    }
+
+    fn partial_token(&self) -> Option<Value> {
+        match &self.token {
+            // a number may have more digits to come, and would change value from one snapshot to the next
+            Token::None | Token::Number(_) | Token::Literal(_) => None,
+            Token::String { is_key: true, .. } => None,
+            Token::String { buffer, .. } => self
+                .include_incomplete_strings
+                .then(|| Value::String(buffer.clone())),
+        }
+    }
+
+    fn push_char(&mut self, c: char) -> Result<(), String> {
+        match &mut self.token {
+            Token::String {
+                buffer,
+                is_key,
+                escape,
+            } => {
+                match escape {
+                    Escape::None => match c {
+                        '\\' => *escape = Escape::Backslash,
+                        '"' => {
+                            let value = std::mem::take(buffer);
+                            let is_key = *is_key;
+                            self.token = Token::None;
+                            if is_key {
+                                match self.stack.last_mut() {
+                                    Some(Frame::Object { key, .. }) => *key = Some(value),
+                                    _ => return Err("unexpected object key".into()),
+                                }
+                                self.expect = Expect::Colon;
+                            } else {
+                                self.emit(Value::String(value))?;
+                            }
+                        }
+                        c => buffer.push(c),
+                    },
+                    Escape::Backslash => {
+                        *escape = Escape::None;
+                        match c {
+                            '"' => buffer.push('"'),
+                            '\\' => buffer.push('\\'),
+                            '/' => buffer.push('/'),
+                            'b' => buffer.push('\u{8}'),
+                            'f' => buffer.push('\u{c}'),
+                            'n' => buffer.push('\n'),
+                            'r' => buffer.push('\r'),
+                            't' => buffer.push('\t'),
+                            'u' => {
+                                *escape = Escape::Unicode {
+                                    digits: String::new(),
+                                    high_surrogate: None,
+                                }
+                            }
+                            c => return Err(format!("invalid escape sequence \\{c}")),
+                        }
+                    }
+                    Escape::PendingLow(high) => {
+                        if c != '\\' {
+                            return Err("unpaired surrogate in \\u escape".into());
+                        }
+                        *escape = Escape::PendingLowU(*high);
+                    }
+                    Escape::PendingLowU(high) => {
+                        if c != 'u' {
+                            return Err("unpaired surrogate in \\u escape".into());
+                        }
+                        *escape = Escape::Unicode {
+                            digits: String::new(),
+                            high_surrogate: Some(*high),
+                        };
+                    }
+                    Escape::Unicode {
+                        digits,
+                        high_surrogate,
+                    } => {
+                        if !c.is_ascii_hexdigit() {
+                            return Err(format!("invalid \\u escape digit {c}"));
+                        }
+                        digits.push(c);
+                        if digits.len() == 4 {
+                            let unit = u16::from_str_radix(digits, 16).map_err(|e| e.to_string())?;
+                            match (*high_surrogate, unit) {
+                                (None, 0xD800..=0xDBFF) => *escape = Escape::PendingLow(unit),
+                                (None, unit) => {
+                                    let decoded = char::from_u32(unit as u32)
+                                        .ok_or("unpaired surrogate in \\u escape")?;
+                                    buffer.push(decoded);
+                                    *escape = Escape::None;
+                                }
+                                (Some(high), 0xDC00..=0xDFFF) => {
+                                    let code = 0x10000
+                                        + (((high as u32) - 0xD800) << 10)
+                                        + ((unit as u32) - 0xDC00);
+                                    let decoded = char::from_u32(code)
+                                        .ok_or("invalid surrogate pair in \\u escape")?;
+                                    buffer.push(decoded);
+                                    *escape = Escape::None;
+                                }
+                                (Some(_), _) => return Err("unpaired surrogate in \\u escape".into()),
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Token::Number(number) => {
+                if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                    number.push(c);
+                    Ok(())
+                } else {
+                    let value = parse_number(number)?;
+                    self.token = Token::None;
+                    self.emit(value)?;
+                    self.push_char(c)
+                }
+            }
+            Token::Literal(literal) => {
+                if c.is_ascii_alphabetic() {
+                    literal.push(c);
+                    if !["true", "false", "null"].iter().any(|l| l.starts_with(literal.as_str())) {
+                        return Err(format!("invalid literal {literal}"));
+                    }
+                    Ok(())
+                } else {
+                    let value = match literal.as_str() {
+                        "true" => Value::Bool(true),
+                        "false" => Value::Bool(false),
+                        "null" => Value::Null,
+                        literal => return Err(format!("invalid literal {literal}")),
+                    };
+                    self.token = Token::None;
+                    self.emit(value)?;
+                    self.push_char(c)
+                }
+            }
+            Token::None => self.push_structural(c),
+        }
+    }
+
+    fn push_structural(&mut self, c: char) -> Result<(), String> {
+        if !self.started {
+            match c {
+                '{' | '[' => self.started = true,
+                // skip leading text such as code fences
+                _ => return Ok(()),
+            }
+        }
+
+        match (self.expect, c) {
+            (_, c) if c.is_whitespace() => Ok(()),
+            (Expect::CommaOrClose, ',') => {
+                self.expect = match self.stack.last() {
+                    Some(Frame::Object { .. }) => Expect::Key,
+                    _ => Expect::Value,
+                };
+                Ok(())
+            }
+            (Expect::Colon, ':') => {
+                self.expect = Expect::Value;
+                Ok(())
+            }
+            (Expect::KeyOrClose | Expect::CommaOrClose, '}') => match self.stack.pop() {
+                Some(Frame::Object { map, .. }) => self.emit(Value::Object(map)),
+                _ => Err("unexpected `}`".into()),
+            },
+            (Expect::ValueOrClose | Expect::CommaOrClose, ']') => match self.stack.pop() {
+                Some(Frame::Array(items)) => self.emit(Value::Array(items)),
+                _ => Err("unexpected `]`".into()),
+            },
+            (Expect::Key | Expect::KeyOrClose, '"') => {
+                self.token = Token::String {
+                    buffer: String::new(),
+                    is_key: true,
+                    escape: Escape::None,
+                };
+                Ok(())
+            }
+            (Expect::Key | Expect::KeyOrClose, c) => {
+                Err(format!("expected object key, found `{c}`"))
+            }
+            (Expect::Colon, c) => Err(format!("expected `:`, found `{c}`")),
+            (Expect::CommaOrClose, c) => {
+                Err(format!("expected `,` or a closing bracket, found `{c}`"))
+            }
+            (_, '{') => {
+                self.stack.push(Frame::Object {
+                    map: Map::new(),
+                    key: None,
+                });
+                self.expect = Expect::KeyOrClose;
+                Ok(())
+            }
+            (_, '[') => {
+                self.stack.push(Frame::Array(Vec::new()));
+                self.expect = Expect::ValueOrClose;
+                Ok(())
+            }
+            (_, '"') => {
+                self.token = Token::String {
+                    buffer: String::new(),
+                    is_key: false,
+                    escape: Escape::None,
+                };
+                Ok(())
+            }
+            (_, c) if c.is_ascii_digit() || c == '-' => {
+                self.token = Token::Number(c.to_string());
+                Ok(())
+            }
+            (_, c) if c.is_ascii_alphabetic() => {
+                self.token = Token::Literal(String::new());
+                self.push_char(c)
+            }
+            (_, c) => Err(format!("unexpected character `{c}`")),
+        }
+    }
+
+    fn emit(&mut self, value: Value) -> Result<(), String> {
+        self.expect = Expect::CommaOrClose;
+        match self.stack.last_mut() {
+            None => self.root = Some(value),
+            Some(Frame::Object { map, key }) => match key.take() {
+                Some(key) => {
+                    map.insert(key, value);
+                }
+                None => return Err("object value without a key".into()),
+            },
+            Some(Frame::Array(items)) => items.push(value),
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(number: &str) -> Result<Value, String> {
+    serde_json::from_str::<Number>(number)
+        .map(Value::Number)
+        .map_err(|_| format!("invalid number {number}"))
+}
+
+/// Parses a possibly incomplete JSON document in one go, see [PartialJsonParser::snapshot].
+pub fn parse_partial_json(input: &str) -> Option<Value> {
+    let mut parser = PartialJsonParser::new();
+    let _ = parser.push(input);
+    parser.snapshot()
+}
@@ -34,6 +34,7 @@ impl<'c, C: Config> Runs<'c, C> {
 
     /// Create a run.
     pub async fn create(&self, request: CreateRunRequest) -> Result<RunObject, OpenAIError> {
+        self.client.validate_request(&request)?;
         self.client
             .post(&format!("/threads/{}/runs", self.thread_id), request)
             .await
@@ -51,6 +52,7 @@ impl<'c, C: Config> Runs<'c, C> {
         }
 
         request.stream = Some(true);
+        self.client.validate_request(&request)?;
 
         Ok(self
             .client
@@ -0,0 +1,216 @@
+//! Speech of any length with [crate::Audio::speech_long].
+//!
+//! The input is split at paragraph and sentence boundaries into segments within the character limit of a request,
+//! which are synthesized concurrently with the same voice and model and joined into one file, see
+//! [crate::audio_format]. The response lists where every segment starts in the input and in the audio.
+//!
+//! ```
+//! use async_openai_wasm::speech_batch::SpeechBatchOptions;
+//!
+//! let text = "First paragraph. It has two sentences.\n\nSecond paragraph.";
+//! let segments = SpeechBatchOptions::default().with_max_chars(30).split(text);
+//! let segments: Vec<&str> = segments.into_iter().map(|range| &text[range]).collect();
+//! assert_eq!(segments, ["First paragraph.", "It has two sentences.", "Second paragraph."]);
+//! ```
+use std::ops::Range;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::types::SpeechResponseFormat;
+use crate::util::BatchRequestOptions;
+
+/// Segmentation, concurrency and retry policy of [crate::Audio::speech_long].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechBatchOptions {
+    /// The maximum number of characters of a segment, 4096 by default as allowed by the API.
+    pub max_chars: usize,
+    /// Concurrency and retries of the requests.
+    pub requests: BatchRequestOptions,
+}
+
+impl Default for SpeechBatchOptions {
+    fn default() -> Self {
+        Self {
+            max_chars: 4096,
+            requests: BatchRequestOptions::default(),
+        }
+    }
+}
+
+/// Where the text is split, from the preferred boundaries down.
+#[derive(Debug, Clone, Copy)]
+enum Boundary {
+    Paragraph,
+    Line,
+    Sentence,
+    Word,
+    Char,
+}
+
+const BOUNDARIES: [Boundary; 5] = [
+    Boundary::Paragraph,
+    Boundary::Line,
+    Boundary::Sentence,
+    Boundary::Word,
+    Boundary::Char,
+];
+
+impl SpeechBatchOptions {
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = max_chars;
+        self
+    }
+
+    pub fn with_requests(mut self, requests: BatchRequestOptions) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Splits the text into segments of at most [Self::max_chars] characters, as byte ranges without surrounding
+    /// whitespace.
+    ///
+    /// Segments end at paragraph breaks where possible, otherwise at line breaks, sentences, words, and only as a
+    /// last resort within a word. Consecutive pieces are joined into segments as long as they fit.
+    pub fn split(&self, text: &str) -> Vec<Range<usize>> {
+        let mut segments = vec![];
+        split_range(
+            text,
+            0..text.len(),
+            &BOUNDARIES,
+            self.max_chars.max(1),
+            &mut segments,
+        );
+        segments
+            .into_iter()
+            .filter_map(|segment| trim(text, segment))
+            .collect()
+    }
+}
+
+/// Splits `range` into segments of at most `max` characters at the first of `boundaries` found in it. Pieces
+/// between these boundaries are joined as long as they fit, longer pieces are split at the following boundaries.
+fn split_range(
+    text: &str,
+    range: Range<usize>,
+    boundaries: &[Boundary],
+    max: usize,
+    segments: &mut Vec<Range<usize>>,
+) {
+    let chars = text[range.clone()].chars().count();
+    if chars <= max {
+        segments.push(range);
+        return;
+    }
+    let Some((boundary, boundaries)) = boundaries.split_first() else {
+        return;
+    };
+
+    let slice = &text[range.clone()];
+    let ends: Vec<usize> = match boundary {
+        Boundary::Paragraph => slice
+            .match_indices("\n\n")
+            .map(|(offset, _)| offset + 2)
+            .collect(),
+        Boundary::Line => slice
+            .match_indices('\n')
+            .map(|(offset, _)| offset + 1)
+            .collect(),
+        Boundary::Sentence => {
+            let mut ends = vec![];
+            let mut chars = slice.char_indices().peekable();
+            while let Some((offset, c)) = chars.next() {
+                let next = chars.peek().map(|(_, next)| *next);
+                let ends_sentence = matches!(c, '.' | '!' | '?' | '…')
+                    && next.map_or(false, char::is_whitespace)
+                    || matches!(c, '。' | '！' | '？');
+                if ends_sentence {
+                    ends.push(offset + c.len_utf8());
+                }
+            }
+            ends
+        }
+        Boundary::Word => slice
+            .match_indices(char::is_whitespace)
+            .map(|(offset, space)| offset + space.len())
+            .collect(),
+        Boundary::Char => slice
+            .char_indices()
+            .skip(max)
+            .step_by(max)
+            .map(|(offset, _)| offset)
+            .collect(),
+    };
+
+    // the pending segment of pieces that fit, and its number of characters
+    let mut pending: Option<(Range<usize>, usize)> = None;
+    let mut start = range.start;
+    for end in ends
+        .into_iter()
+        .map(|end| range.start + end)
+        .chain([range.end])
+    {
+        if end <= start {
+            continue;
+        }
+        let piece = start..end;
+        start = end;
+        let chars = text[piece.clone()].chars().count();
+        pending = match pending {
+            Some((segment, pending_chars)) if pending_chars + chars <= max => {
+                Some((segment.start..piece.end, pending_chars + chars))
+            }
+            pending if chars <= max => {
+                segments.extend(pending.map(|(segment, _)| segment));
+                Some((piece, chars))
+            }
+            pending => {
+                segments.extend(pending.map(|(segment, _)| segment));
+                split_range(text, piece, boundaries, max, segments);
+                None
+            }
+        };
+    }
+    segments.extend(pending.map(|(segment, _)| segment));
+}
+
+fn trim(text: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let slice = &text[range.clone()];
+    let trimmed = slice.trim_start();
+    let start = range.start + slice.len() - trimmed.len();
+    let end = start + trimmed.trim_end().len();
+    (end > start).then_some(start..end)
+}
+
+/// A segment of the input of [crate::Audio::speech_long].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeechSegment {
+    /// The position of the segment, starting at 0.
+    pub index: usize,
+    /// The text of the segment, as sent to the API.
+    pub text: String,
+    /// The byte range of the text in the input.
+    pub range: Range<usize>,
+    /// Where the audio of the segment starts in the joined audio.
+    pub offset: Duration,
+    /// The duration of the audio of the segment in the joined audio, see [crate::audio_format::durations].
+    pub duration: Duration,
+}
+
+/// The joined audio of [crate::Audio::speech_long].
+#[derive(Debug, Clone)]
+pub struct LongSpeechResponse {
+    pub bytes: Bytes,
+    pub response_format: SpeechResponseFormat,
+    pub segments: Vec<SpeechSegment>,
+}
+
+impl LongSpeechResponse {
+    /// The duration of the joined audio.
+    pub fn duration(&self) -> Duration {
+        self.segments
+            .last()
+            .map(|segment| segment.offset + segment.duration)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::partial_json::PartialJsonParser;
+use crate::types::{
+    ChatChoice, ChatChoiceLogprobs, ChatCompletionMessageToolCall, ChatCompletionResponseMessage,
+    ChatCompletionResponseMessageAnnotation, ChatCompletionTokenLogprob, ChatCompletionToolType,
+    CompletionUsage, CreateChatCompletionResponse, CreateChatCompletionStreamResponse,
+    FinishReason, FunctionCall, Role, ServiceTierResponse,
+};
+
+#[derive(Debug, Clone, Default)]
+struct ToolCallAccumulator {
+    id: Option<String>,
+    name: Option<String>,
+    arguments: String,
+    arguments_json: PartialJsonParser,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ChoiceAccumulator {
+    role: Option<Role>,
+    content: Option<String>,
+    content_json: PartialJsonParser,
+    refusal: Option<String>,
+    tool_calls: BTreeMap<i32, ToolCallAccumulator>,
+    function_call: Option<FunctionCall>,
+    finish_reason: Option<FinishReason>,
+    logprobs: Option<ChatChoiceLogprobs>,
+    annotations: Vec<ChatCompletionResponseMessageAnnotation>,
+}
+
+/// Best-effort parse of a streamed tool call, see [PartialChatChoice].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialToolCall {
+    /// The index of the tool call in the list of tool calls of the choice.
+    pub index: i32,
+    /// The ID of the tool call.
+    pub id: Option<String>,
+    /// The name of the function to call.
+    pub name: Option<String>,
+    /// The arguments received so far, parsed as partial JSON.
+    pub arguments: Option<Value>,
+}
+
+/// Best-effort parse of the JSON content and tool call arguments received so far for one choice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialChatChoice {
+    /// The index of the choice in the list of choices.
+    pub index: u32,
+    /// The content received so far, parsed as partial JSON. `None` until the JSON document starts,
+    /// or if the content is not JSON.
+    pub content: Option<Value>,
+    pub tool_calls: Vec<PartialToolCall>,
+    /// Set once the choice is complete.
+    pub finish_reason: Option<FinishReason>,
+}
+
+impl PartialChatChoice {
+    /// Deserializes the partial content into `T`, see [PartialJsonParser::snapshot_as].
+    pub fn content_as<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        self.content
+            .clone()
+            .and_then(|value| serde_json::from_value(value).ok())
+    }
+}
+
+/// Accumulates the chunks of a streamed chat completion into a [CreateChatCompletionResponse].
+///
+/// The content and tool call arguments of each choice are also parsed incrementally,
+/// so structured outputs can be rendered while they are generated.
+#[derive(Debug, Clone, Default)]
+pub struct ChatCompletionStreamAccumulator {
+    id: String,
+    created: u32,
+    model: String,
+    service_tier: Option<ServiceTierResponse>,
+    system_fingerprint: Option<String>,
+    usage: Option<CompletionUsage>,
+    choices: BTreeMap<u32, ChoiceAccumulator>,
+}
+
+impl ChatCompletionStreamAccumulator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Merges the next chunk of the stream.
+    #[allow(deprecated)]
+    pub fn push(&mut self, chunk: &CreateChatCompletionStreamResponse) {
+        self.id.clone_from(&chunk.id);
+        self.created = chunk.created;
+        self.model.clone_from(&chunk.model);
+        if chunk.service_tier.is_some() {
+            self.service_tier.clone_from(&chunk.service_tier);
+        }
+        if chunk.system_fingerprint.is_some() {
+            self.system_fingerprint.clone_from(&chunk.system_fingerprint);
+        }
+        if chunk.usage.is_some() {
+            self.usage.clone_from(&chunk.usage);
+        }
+
+        for choice in &chunk.choices {
+            let accumulator = self.choices.entry(choice.index).or_default();
+            let delta = &choice.delta;
+
+            if delta.role.is_some() {
+                accumulator.role = delta.role;
+            }
+            if let Some(content) = &delta.content {
+                accumulator
+                    .content
+                    .get_or_insert_with(String::new)
+                    .push_str(content);
+                // content is not necessarily JSON, in which case the snapshot is simply unavailable
+                let _ = accumulator.content_json.push(content);
+            }
+            if let Some(refusal) = &delta.refusal {
+                accumulator
+                    .refusal
+                    .get_or_insert_with(String::new)
+                    .push_str(refusal);
+            }
+            for tool_call in delta.tool_calls.iter().flatten() {
+                let tool_call_accumulator = accumulator.tool_calls.entry(tool_call.index).or_default();
+                if tool_call.id.is_some() {
+                    tool_call_accumulator.id.clone_from(&tool_call.id);
+                }
+                if let Some(function) = &tool_call.function {
+                    if let Some(name) = &function.name {
+                        tool_call_accumulator
+                            .name
+                            .get_or_insert_with(String::new)
+                            .push_str(name);
+                    }
+                    if let Some(arguments) = &function.arguments {
+                        tool_call_accumulator.arguments.push_str(arguments);
+                        let _ = tool_call_accumulator.arguments_json.push(arguments);
+                    }
+                }
+            }
+            if let Some(function_call) = &delta.function_call {
+                let accumulated = accumulator.function_call.get_or_insert_with(|| FunctionCall {
+                    name: String::new(),
+                    arguments: String::new(),
+                });
+                if let Some(name) = &function_call.name {
+                    accumulated.name.push_str(name);
+                }
+                if let Some(arguments) = &function_call.arguments {
+                    accumulated.arguments.push_str(arguments);
+                }
+            }
+            if let Some(annotations) = &delta.annotations {
+                accumulator.annotations.extend(annotations.iter().cloned());
+            }
+            if choice.finish_reason.is_some() {
+                accumulator.finish_reason = choice.finish_reason;
+            }
+            if let Some(logprobs) = &choice.logprobs {
+                match &mut accumulator.logprobs {
+                    Some(accumulated) => {
+                        append_tokens(&mut accumulated.content, &logprobs.content);
+                        append_tokens(&mut accumulated.refusal, &logprobs.refusal);
+                    }
+                    None => accumulator.logprobs = Some(logprobs.clone()),
+                }
+            }
+        }
+    }
+
+    /// Best-effort parse of the content and tool call arguments of the choice with the given index.
+    pub fn partial_choice(&self, index: u32) -> Option<PartialChatChoice> {
+        let choice = self.choices.get(&index)?;
+        Some(PartialChatChoice {
+            index,
+            content: choice.content_json.snapshot(),
+            tool_calls: choice
+                .tool_calls
+                .iter()
+                .map(|(index, tool_call)| PartialToolCall {
+                    index: *index,
+                    id: tool_call.id.clone(),
+                    name: tool_call.name.clone(),
+                    arguments: tool_call.arguments_json.snapshot(),
+                })
+                .collect(),
+            finish_reason: choice.finish_reason,
+        })
+    }
+
+    /// Best-effort parse of the content of the choice with the given index.
+    pub fn partial_content(&self, index: u32) -> Option<Value> {
+        self.choices.get(&index)?.content_json.snapshot()
+    }
+
+    /// Best-effort parse of the arguments of a tool call of the choice with the given index.
+    pub fn partial_tool_arguments(&self, index: u32, tool_call_index: i32) -> Option<Value> {
+        self.choices
+            .get(&index)?
+            .tool_calls
+            .get(&tool_call_index)?
+            .arguments_json
+            .snapshot()
+    }
+
+    /// The response accumulated so far.
+    #[allow(deprecated)]
+    pub fn response(&self) -> CreateChatCompletionResponse {
+        CreateChatCompletionResponse {
+            id: self.id.clone(),
+            choices: self
+                .choices
+                .iter()
+                .map(|(index, choice)| ChatChoice {
+                    index: *index,
+                    message: ChatCompletionResponseMessage {
+                        content: choice.content.clone(),
+                        refusal: choice.refusal.clone(),
+                        tool_calls: (!choice.tool_calls.is_empty()).then(|| {
+                            choice
+                                .tool_calls
+                                .values()
+                                .map(|tool_call| ChatCompletionMessageToolCall {
+                                    id: tool_call.id.clone().unwrap_or_default(),
+                                    r#type: ChatCompletionToolType::Function,
+                                    function: FunctionCall {
+                                        name: tool_call.name.clone().unwrap_or_default(),
+                                        arguments: tool_call.arguments.clone(),
+                                    },
+                                })
+                                .collect()
+                        }),
+                        annotations: (!choice.annotations.is_empty())
+                            .then(|| choice.annotations.clone()),
+                        role: choice.role.unwrap_or(Role::Assistant),
+                        function_call: choice.function_call.clone(),
+                    },
+                    finish_reason: choice.finish_reason,
+                    logprobs: choice.logprobs.clone(),
+                })
+                .collect(),
+            created: self.created,
+            model: self.model.clone(),
+            service_tier: self.service_tier.clone(),
+            system_fingerprint: self.system_fingerprint.clone(),
+            object: "chat.completion".into(),
+            usage: self.usage.clone(),
+        }
+    }
+}
+
+fn append_tokens(
+    accumulated: &mut Option<Vec<ChatCompletionTokenLogprob>>,
+    tokens: &Option<Vec<ChatCompletionTokenLogprob>>,
+) {
+    if let Some(tokens) = tokens {
+        accumulated
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(tokens);
+    }
+}
@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::Stream;
+use futures_timer::Delay;
+use pin_project::pin_project;
+
+use crate::error::OpenAIError;
+
+/// Joins text deltas into batches, yielded once `interval` has passed since the first delta of the batch
+/// or once the batch holds `max_chars` characters.
+///
+/// Created by [super::OpenAIStreamExt::coalesce]. The batch is flushed before an error and at the end of the stream,
+/// so no text is lost. Batches are only yielded when the stream is polled, the interval merely bounds how long a
+/// delta is held back from a waiting consumer.
+#[pin_project]
+pub struct Coalesce<S> {
+    #[pin]
+    stream: S,
+    interval: Duration,
+    max_chars: usize,
+    buffer: String,
+    chars: usize,
+    delay: Option<Delay>,
+    error: Option<OpenAIError>,
+    done: bool,
+}
+
+impl<S> Coalesce<S> {
+    pub fn new(stream: S, interval: Duration, max_chars: usize) -> Self {
+        Self {
+            stream,
+            interval,
+            max_chars,
+            buffer: String::new(),
+            chars: 0,
+            delay: None,
+            error: None,
+            done: false,
+        }
+    }
+}
+
+impl<S, T> Stream for Coalesce<S>
+where
+    S: Stream<Item = Result<T, OpenAIError>>,
+    T: AsRef<str>,
+{
+    type Item = Result<String, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        if let Some(e) = this.error.take() {
+            return Poll::Ready(Some(Err(e)));
+        }
+
+        let flush = |buffer: &mut String, chars: &mut usize, delay: &mut Option<Delay>| {
+            *chars = 0;
+            *delay = None;
+            Poll::Ready(Some(Ok(std::mem::take(buffer))))
+        };
+
+        loop {
+            if *this.done {
+                return if this.buffer.is_empty() {
+                    Poll::Ready(None)
+                } else {
+                    flush(this.buffer, this.chars, this.delay)
+                };
+            }
+
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(text))) => {
+                    let text = text.as_ref();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    this.buffer.push_str(text);
+                    *this.chars += text.chars().count();
+                    if *this.chars >= *this.max_chars {
+                        return flush(this.buffer, this.chars, this.delay);
+                    }
+                    if this.delay.is_none() {
+                        *this.delay = Some(Delay::new(*this.interval));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    if this.buffer.is_empty() {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    *this.error = Some(e);
+                    return flush(this.buffer, this.chars, this.delay);
+                }
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => {
+                    let elapsed = match this.delay.as_mut() {
+                        Some(delay) => Pin::new(delay).poll(cx).is_ready(),
+                        None => false,
+                    };
+                    return if elapsed {
+                        flush(this.buffer, this.chars, this.delay)
+                    } else {
+                        Poll::Pending
+                    };
+                }
+            }
+        }
+    }
+}
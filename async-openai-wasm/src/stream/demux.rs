@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures::task::ArcWake;
+use futures::Stream;
+use pin_project::pin_project;
+
+use crate::error::OpenAIError;
+use crate::types::{ChatChoiceStream, CreateChatCompletionStreamResponse};
+
+/// Yields every choice of the inner stream with its index, one at a time.
+///
+/// Created by [super::ChatCompletionStreamExt::keyed_choices]. Chunks without choices, such as the final usage chunk, are skipped.
+#[pin_project]
+pub struct KeyedChoiceStream<S> {
+    #[pin]
+    stream: S,
+    pending: VecDeque<ChatChoiceStream>,
+}
+
+impl<S> KeyedChoiceStream<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<S> Stream for KeyedChoiceStream<S>
+where
+    S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+{
+    type Item = Result<(u32, ChatChoiceStream), OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if let Some(choice) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok((choice.index, choice))));
+            }
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.pending.extend(chunk.choices),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct ChoiceState {
+    buffer: VecDeque<Result<ChatChoiceStream, OpenAIError>>,
+    finished: bool,
+    dropped: bool,
+}
+
+struct Shared<S> {
+    stream: Pin<Box<S>>,
+    done: bool,
+    choices: Vec<ChoiceState>,
+}
+
+/// Wakes every stream waiting for a shared inner stream, whichever of them polled it last.
+pub(crate) struct Wakers(pub(crate) Mutex<Vec<Option<Waker>>>);
+
+impl Wakers {
+    pub(crate) fn wake_one(&self, index: usize) {
+        let waker = self.0.lock().unwrap()[index].take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl ArcWake for Wakers {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        let wakers: Vec<Waker> = arc_self
+            .0
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(Option::take)
+            .collect();
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// The chunks of a single choice of a stream with `n > 1`, ending after the chunk with its `finish_reason`.
+///
+/// Created by [super::ChatCompletionStreamExt::split_choices]. All streams share the inner stream, which is polled by
+/// whichever of them needs the next chunk; chunks of other choices are buffered until their stream is polled.
+/// An error of the inner stream is returned by the stream that polled it, the others end with an [OpenAIError::StreamError].
+pub struct ChoiceStream<S> {
+    index: usize,
+    shared: Arc<Mutex<Shared<S>>>,
+    wakers: Arc<Wakers>,
+}
+
+impl<S> ChoiceStream<S>
+where
+    S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+{
+    pub(crate) fn split(stream: S, n: usize) -> Vec<Self> {
+        let shared = Arc::new(Mutex::new(Shared {
+            stream: Box::pin(stream),
+            done: false,
+            choices: (0..n).map(|_| ChoiceState::default()).collect(),
+        }));
+        let wakers = Arc::new(Wakers(Mutex::new(vec![None; n])));
+        (0..n)
+            .map(|index| Self {
+                index,
+                shared: shared.clone(),
+                wakers: wakers.clone(),
+            })
+            .collect()
+    }
+}
+
+impl<S> ChoiceStream<S> {
+    /// The index of the choice in the list of choices.
+    pub fn index(&self) -> u32 {
+        self.index as u32
+    }
+}
+
+impl<S> Stream for ChoiceStream<S>
+where
+    S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+{
+    type Item = Result<ChatChoiceStream, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut guard = this.shared.lock().unwrap();
+        let shared = &mut *guard;
+        loop {
+            let state = &mut shared.choices[this.index];
+            if let Some(item) = state.buffer.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            if state.finished || shared.done {
+                return Poll::Ready(None);
+            }
+
+            this.wakers.0.lock().unwrap()[this.index] = Some(cx.waker().clone());
+            let waker = futures::task::waker(this.wakers.clone());
+            match shared
+                .stream
+                .as_mut()
+                .poll_next(&mut Context::from_waker(&waker))
+            {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    for choice in chunk.choices {
+                        let index = choice.index as usize;
+                        let Some(state) = shared.choices.get_mut(index) else {
+                            continue;
+                        };
+                        if state.finished {
+                            continue;
+                        }
+                        state.finished = choice.finish_reason.is_some();
+                        if !state.dropped {
+                            state.buffer.push_back(Ok(choice));
+                        }
+                        if index != this.index {
+                            this.wakers.wake_one(index);
+                        }
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    let message = match &e {
+                        OpenAIError::StreamError(message) => message.clone(),
+                        e => e.to_string(),
+                    };
+                    shared.done = true;
+                    for (index, state) in shared.choices.iter_mut().enumerate() {
+                        if index != this.index && !state.finished && !state.dropped {
+                            state
+                                .buffer
+                                .push_back(Err(OpenAIError::StreamError(message.clone())));
+                        }
+                    }
+                    ArcWake::wake_by_ref(&this.wakers);
+                    return Poll::Ready(Some(Err(e)));
+                }
+                Poll::Ready(None) => {
+                    shared.done = true;
+                    ArcWake::wake_by_ref(&this.wakers);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<S> Drop for ChoiceStream<S> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            let state = &mut shared.choices[self.index];
+            state.dropped = true;
+            state.buffer.clear();
+        }
+    }
+}
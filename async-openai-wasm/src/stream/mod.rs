@@ -0,0 +1,103 @@
+//! Utilities for streamed chat completions, see [ChatCompletionStreamExt], and for streamed responses in general,
+//! see [OpenAIStreamExt].
+//!
+//! Streamed `pcm` speech is regrouped into whole samples by [OpenAIStreamExt::pcm_frames].
+//!
+//! The adapters use no async runtime: timers are provided by `futures-timer`, backed by `setTimeout` on wasm32.
+use std::time::Duration;
+
+use futures::Stream;
+
+use crate::error::OpenAIError;
+use crate::types::CreateChatCompletionStreamResponse;
+
+pub use accumulator::*;
+pub use coalesce::*;
+pub use demux::*;
+pub use partial::*;
+pub use pcm::*;
+pub use tee::*;
+pub use text::*;
+pub use timeout::*;
+
+mod accumulator;
+mod coalesce;
+mod demux;
+mod partial;
+mod pcm;
+mod tee;
+mod text;
+mod timeout;
+
+/// Adapters for streams of chat completion chunks, such as [crate::types::ChatCompletionResponseStream].
+pub trait ChatCompletionStreamExt:
+    Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>> + Sized
+{
+    /// Yields a best-effort parse of the JSON content and tool call arguments of every choice updated by the chunks
+    /// received since the previous item, see [PartialJsonStream].
+    fn partial_json(self) -> PartialJsonStream<Self> {
+        PartialJsonStream::new(self)
+    }
+
+    /// Yields only the content deltas of the first choice, see [TextStream].
+    fn text(self) -> TextStream<Self> {
+        TextStream::new(self)
+    }
+
+    /// Yields the choices of every chunk one at a time with their index, to tell apart the choices of a request with `n > 1`,
+    /// see [KeyedChoiceStream].
+    fn keyed_choices(self) -> KeyedChoiceStream<Self> {
+        KeyedChoiceStream::new(self)
+    }
+
+    /// Splits a stream of a request with `n > 1` into one stream per choice, each ending on its own `finish_reason`,
+    /// see [ChoiceStream].
+    ///
+    /// The streams can be polled independently, e.g. to render candidate answers side by side.
+    /// Choices with an index of `n` or more are ignored.
+    fn split_choices(self, n: usize) -> Vec<ChoiceStream<Self>> {
+        ChoiceStream::split(self, n)
+    }
+}
+
+impl<S> ChatCompletionStreamExt for S where
+    S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>> + Sized
+{
+}
+
+/// Adapters for any stream of results, such as [crate::types::ChatCompletionResponseStream] or [TextStream].
+pub trait OpenAIStreamExt<T>: Stream<Item = Result<T, OpenAIError>> + Sized {
+    /// Joins text deltas into batches of at most one per `interval` or of `max_chars` characters, see [Coalesce].
+    ///
+    /// Useful to limit the updates of a UI, which would otherwise re-render for every token.
+    fn coalesce(self, interval: Duration, max_chars: usize) -> Coalesce<Self>
+    where
+        T: AsRef<str>,
+    {
+        Coalesce::new(self, interval, max_chars)
+    }
+
+    /// Fails with an [OpenAIError::StreamError] if no item arrives within `timeout`, see [IdleTimeout].
+    fn idle_timeout(self, timeout: Duration) -> IdleTimeout<Self> {
+        IdleTimeout::new(self, timeout)
+    }
+
+    /// Copies the stream to `n` consumers, see [Tee].
+    fn tee(self, n: usize) -> Vec<Tee<Self, T>>
+    where
+        T: Clone,
+    {
+        Tee::split(self, n)
+    }
+
+    /// Regroups `pcm` audio, such as of [crate::Audio::speech_stream], into frames of `samples` whole samples,
+    /// see [PcmFrames].
+    fn pcm_frames(self, samples: usize) -> PcmFrames<Self>
+    where
+        T: AsRef<[u8]>,
+    {
+        PcmFrames::new(self, samples)
+    }
+}
+
+impl<S, T> OpenAIStreamExt<T> for S where S: Stream<Item = Result<T, OpenAIError>> + Sized {}
@@ -0,0 +1,98 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use pin_project::pin_project;
+
+use crate::error::OpenAIError;
+use crate::types::CreateChatCompletionStreamResponse;
+
+use super::{ChatCompletionStreamAccumulator, PartialChatChoice};
+
+/// Yields a [PartialChatChoice] for every choice updated by the chunks of the inner stream.
+///
+/// Created by [super::ChatCompletionStreamExt::partial_json]. The complete response is available
+/// from [PartialJsonStream::accumulator] once the stream ends.
+///
+/// All chunks which are ready when the stream is polled are accumulated before the updated choices
+/// are snapshotted once, so a consumer which falls behind is not slowed down further by snapshots
+/// of chunks it never sees. Each [crate::partial_json::PartialJsonParser::snapshot] still clones the
+/// document received so far.
+#[pin_project]
+pub struct PartialJsonStream<S> {
+    #[pin]
+    stream: S,
+    accumulator: ChatCompletionStreamAccumulator,
+    /// Indices of the choices updated since the last item.
+    updated: Vec<u32>,
+    /// An error of the inner stream, yielded after the updates received before it.
+    error: Option<OpenAIError>,
+    done: bool,
+}
+
+impl<S> PartialJsonStream<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            accumulator: ChatCompletionStreamAccumulator::new(),
+            updated: Vec::new(),
+            error: None,
+            done: false,
+        }
+    }
+
+    /// The chunks received so far.
+    pub fn accumulator(&self) -> &ChatCompletionStreamAccumulator {
+        &self.accumulator
+    }
+}
+
+impl<S> Stream for PartialJsonStream<S>
+where
+    S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+{
+    type Item = Result<Vec<PartialChatChoice>, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if let Some(e) = this.error.take() {
+            return Poll::Ready(Some(Err(e)));
+        }
+        while !*this.done {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.accumulator.push(&chunk);
+                    // chunks without choices, e.g. the final usage chunk, have nothing to report
+                    for choice in &chunk.choices {
+                        if !this.updated.contains(&choice.index) {
+                            this.updated.push(choice.index);
+                        }
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    if this.updated.is_empty() {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    *this.error = Some(e);
+                    break;
+                }
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if this.updated.is_empty() {
+            return if *this.done {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            };
+        }
+        let choices = this
+            .updated
+            .drain(..)
+            .filter_map(|index| this.accumulator.partial_choice(index))
+            .collect();
+        Poll::Ready(Some(Ok(choices)))
+    }
+}
@@ -0,0 +1,104 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use futures::Stream;
+use pin_project::pin_project;
+
+use crate::error::OpenAIError;
+
+/// The sample rate of `pcm` speech, which is 16-bit signed little-endian mono.
+pub const PCM_SAMPLE_RATE: u32 = 24_000;
+/// The size of a sample of `pcm` speech.
+pub const PCM_BYTES_PER_SAMPLE: usize = 2;
+
+/// The number of samples of `pcm` speech in `duration`, rounded down.
+pub fn pcm_samples(duration: Duration) -> usize {
+    (duration.as_secs_f64() * PCM_SAMPLE_RATE as f64) as usize
+}
+
+/// The duration of `len` bytes of `pcm` speech.
+pub fn pcm_duration(len: usize) -> Duration {
+    Duration::from_secs_f64((len / PCM_BYTES_PER_SAMPLE) as f64 / PCM_SAMPLE_RATE as f64)
+}
+
+/// Decodes 16-bit little-endian samples, ignoring a trailing odd byte.
+pub fn pcm_to_i16(bytes: &[u8]) -> Vec<i16> {
+    bytes
+        .chunks_exact(PCM_BYTES_PER_SAMPLE)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+        .collect()
+}
+
+/// Decodes 16-bit little-endian samples scaled to `-1.0..1.0`, as played by the Web Audio API.
+pub fn pcm_to_f32(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(PCM_BYTES_PER_SAMPLE)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0)
+        .collect()
+}
+
+/// Regroups a stream of `pcm` audio into frames of a fixed number of samples, as audio outputs consume them.
+///
+/// Network chunks have arbitrary sizes and may end within a sample. Every frame but the last has exactly the
+/// requested number of samples, the last has the remaining whole samples. A trailing odd byte fails with
+/// [OpenAIError::StreamError].
+///
+/// Created by [super::OpenAIStreamExt::pcm_frames].
+#[pin_project]
+pub struct PcmFrames<S> {
+    #[pin]
+    stream: S,
+    frame_len: usize,
+    buffer: BytesMut,
+    done: bool,
+}
+
+impl<S> PcmFrames<S> {
+    pub fn new(stream: S, samples: usize) -> Self {
+        let frame_len = samples.max(1) * PCM_BYTES_PER_SAMPLE;
+        Self {
+            stream,
+            frame_len,
+            buffer: BytesMut::with_capacity(frame_len),
+            done: false,
+        }
+    }
+}
+
+impl<S, T> Stream for PcmFrames<S>
+where
+    S: Stream<Item = Result<T, OpenAIError>>,
+    T: AsRef<[u8]>,
+{
+    type Item = Result<Bytes, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            if this.buffer.len() >= *this.frame_len {
+                return Poll::Ready(Some(Ok(this.buffer.split_to(*this.frame_len).freeze())));
+            }
+            if *this.done {
+                let whole = this.buffer.len() - this.buffer.len() % PCM_BYTES_PER_SAMPLE;
+                if whole > 0 {
+                    return Poll::Ready(Some(Ok(this.buffer.split_to(whole).freeze())));
+                }
+                if !this.buffer.is_empty() {
+                    this.buffer.clear();
+                    return Poll::Ready(Some(Err(OpenAIError::StreamError(
+                        "pcm audio ended within a sample".into(),
+                    ))));
+                }
+                return Poll::Ready(None);
+            }
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buffer.extend_from_slice(chunk.as_ref()),
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => *this.done = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
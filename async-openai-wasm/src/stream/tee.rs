@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures::task::ArcWake;
+use futures::Stream;
+
+use super::demux::Wakers;
+use crate::error::OpenAIError;
+
+struct ConsumerState<T> {
+    buffer: VecDeque<Result<T, OpenAIError>>,
+    dropped: bool,
+}
+
+struct Shared<S, T> {
+    stream: Pin<Box<S>>,
+    done: bool,
+    consumers: Vec<ConsumerState<T>>,
+}
+
+/// One of several copies of a stream, e.g. to update a UI and log the response at the same time.
+///
+/// Created by [super::OpenAIStreamExt::tee]. All copies share the inner stream, which is polled by whichever of them
+/// needs the next item; items are cloned into the buffers of the other copies until they are polled, so a copy that is
+/// never polled should be dropped. An error of the inner stream is returned by the copy that polled it, the others get
+/// an [OpenAIError::StreamError] with the same message.
+pub struct Tee<S, T> {
+    index: usize,
+    shared: Arc<Mutex<Shared<S, T>>>,
+    wakers: Arc<Wakers>,
+}
+
+impl<S, T> Tee<S, T>
+where
+    S: Stream<Item = Result<T, OpenAIError>>,
+    T: Clone,
+{
+    pub(crate) fn split(stream: S, n: usize) -> Vec<Self> {
+        let shared = Arc::new(Mutex::new(Shared {
+            stream: Box::pin(stream),
+            done: false,
+            consumers: (0..n)
+                .map(|_| ConsumerState {
+                    buffer: VecDeque::new(),
+                    dropped: false,
+                })
+                .collect(),
+        }));
+        let wakers = Arc::new(Wakers(Mutex::new(vec![None; n])));
+        (0..n)
+            .map(|index| Self {
+                index,
+                shared: shared.clone(),
+                wakers: wakers.clone(),
+            })
+            .collect()
+    }
+}
+
+impl<S, T> Stream for Tee<S, T>
+where
+    S: Stream<Item = Result<T, OpenAIError>>,
+    T: Clone,
+{
+    type Item = Result<T, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut guard = this.shared.lock().unwrap();
+        let shared = &mut *guard;
+
+        if let Some(item) = shared.consumers[this.index].buffer.pop_front() {
+            return Poll::Ready(Some(item));
+        }
+        if shared.done {
+            return Poll::Ready(None);
+        }
+
+        this.wakers.0.lock().unwrap()[this.index] = Some(cx.waker().clone());
+        let waker = futures::task::waker(this.wakers.clone());
+        match shared
+            .stream
+            .as_mut()
+            .poll_next(&mut Context::from_waker(&waker))
+        {
+            Poll::Ready(Some(item)) => {
+                let copy = match &item {
+                    Ok(value) => Ok(value.clone()),
+                    Err(OpenAIError::StreamError(message)) => Err(message.clone()),
+                    Err(e) => Err(e.to_string()),
+                };
+                for (index, consumer) in shared.consumers.iter_mut().enumerate() {
+                    if index != this.index && !consumer.dropped {
+                        consumer
+                            .buffer
+                            .push_back(copy.clone().map_err(OpenAIError::StreamError));
+                        this.wakers.wake_one(index);
+                    }
+                }
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => {
+                shared.done = true;
+                ArcWake::wake_by_ref(&this.wakers);
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<S, T> Drop for Tee<S, T> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            let consumer = &mut shared.consumers[self.index];
+            consumer.dropped = true;
+            consumer.buffer.clear();
+        }
+    }
+}
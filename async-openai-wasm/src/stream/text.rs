@@ -0,0 +1,53 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use pin_project::pin_project;
+
+use crate::error::OpenAIError;
+use crate::types::CreateChatCompletionStreamResponse;
+
+/// Yields the content deltas of the first choice, skipping chunks without content.
+///
+/// Created by [super::ChatCompletionStreamExt::text]. Use [super::ChatCompletionStreamExt::split_choices] first for the
+/// other choices of a request with `n > 1`.
+#[pin_project]
+pub struct TextStream<S> {
+    #[pin]
+    stream: S,
+}
+
+impl<S> TextStream<S> {
+    pub fn new(stream: S) -> Self {
+        Self { stream }
+    }
+}
+
+impl<S> Stream for TextStream<S>
+where
+    S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+{
+    type Item = Result<String, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let content = chunk
+                        .choices
+                        .into_iter()
+                        .find(|choice| choice.index == 0)
+                        .and_then(|choice| choice.delta.content)
+                        .filter(|content| !content.is_empty());
+                    if let Some(content) = content {
+                        return Poll::Ready(Some(Ok(content)));
+                    }
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::Stream;
+use futures_timer::Delay;
+use pin_project::pin_project;
+
+use crate::error::OpenAIError;
+
+/// Ends the stream with an [OpenAIError::StreamError] when the inner stream stays pending for `timeout` without an item.
+///
+/// Created by [super::OpenAIStreamExt::idle_timeout]. The deadline restarts whenever an item arrives, time spent by the
+/// consumer between polls does not count. The inner stream is dropped on timeout, which closes the underlying connection.
+#[pin_project]
+pub struct IdleTimeout<S> {
+    #[pin]
+    stream: Option<S>,
+    timeout: Duration,
+    delay: Option<Delay>,
+}
+
+impl<S> IdleTimeout<S> {
+    pub fn new(stream: S, timeout: Duration) -> Self {
+        Self {
+            stream: Some(stream),
+            timeout,
+            delay: None,
+        }
+    }
+}
+
+impl<S, T> Stream for IdleTimeout<S>
+where
+    S: Stream<Item = Result<T, OpenAIError>>,
+{
+    type Item = Result<T, OpenAIError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        let Some(stream) = this.stream.as_mut().as_pin_mut() else {
+            return Poll::Ready(None);
+        };
+
+        match stream.poll_next(cx) {
+            Poll::Ready(Some(item)) => {
+                *this.delay = None;
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => {
+                this.stream.set(None);
+                Poll::Ready(None)
+            }
+            Poll::Pending => {
+                let timeout = *this.timeout;
+                let delay = this.delay.get_or_insert_with(|| Delay::new(timeout));
+                if Pin::new(delay).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                this.stream.set(None);
+                Poll::Ready(Some(Err(OpenAIError::StreamError(format!(
+                    "no event received within {timeout:?}"
+                )))))
+            }
+        }
+    }
+}
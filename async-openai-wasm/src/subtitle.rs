@@ -0,0 +1,374 @@
+//! SubRip (`srt`) and WebVTT (`vtt`) subtitles of transcriptions, see [crate::Audio::transcribe_srt] and
+//! [crate::Audio::transcribe_vtt].
+//!
+//! A [Subtitle] is parsed from and written to both formats, and generated from the segments or words of a
+//! `verbose_json` transcription, wrapped to lines of [SubtitleOptions::max_chars_per_line].
+//!
+//! ```
+//! use async_openai_wasm::subtitle::Subtitle;
+//! use std::time::Duration;
+//!
+//! let srt = "1\n00:00:00,000 --> 00:00:02,500\nHello there.\n\n2\n00:00:02,500 --> 00:00:04,000\nGeneral Kenobi!\n";
+//! let subtitle = Subtitle::parse_srt(srt).unwrap();
+//! assert_eq!(subtitle.cues[1].start, Duration::from_millis(2500));
+//! assert_eq!(subtitle.cues[1].text, "General Kenobi!");
+//!
+//! let vtt = subtitle.to_vtt();
+//! assert!(vtt.starts_with("WEBVTT\n\n1\n00:00:00.000 --> 00:00:02.500\nHello there.\n"));
+//! assert_eq!(Subtitle::parse_vtt(&vtt).unwrap(), subtitle);
+//! ```
+use std::time::Duration;
+
+use crate::error::OpenAIError;
+use crate::types::{
+    CreateTranscriptionResponseVerboseJson, TranscriptionSegment, TranscriptionWord,
+};
+
+/// A subtitle shown from `start` to `end`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cue {
+    /// The identifier of the cue, a number in SubRip files and optional in WebVTT files.
+    pub id: Option<String>,
+    pub start: Duration,
+    pub end: Duration,
+    /// The text of the cue, with lines separated by `\n`.
+    pub text: String,
+}
+
+impl Cue {
+    pub fn new(start: Duration, end: Duration, text: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            start,
+            end,
+            text: text.into(),
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// How subtitles are generated from transcription segments and words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleOptions {
+    /// The maximum number of characters of a line, 42 by default. Longer words are kept on a line of their own.
+    pub max_chars_per_line: usize,
+    /// The maximum number of lines of a cue, 2 by default. Longer segments are split into cues, and their time
+    /// is shared by the number of characters.
+    pub max_lines: usize,
+    /// Cues shorter than this, 1 second by default, are merged with the next cue if the text of both fits.
+    pub min_duration: Duration,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            max_chars_per_line: 42,
+            max_lines: 2,
+            min_duration: Duration::from_secs(1),
+        }
+    }
+}
+
+impl SubtitleOptions {
+    pub fn with_max_chars_per_line(mut self, max_chars_per_line: usize) -> Self {
+        self.max_chars_per_line = max_chars_per_line;
+        self
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    pub fn with_min_duration(mut self, min_duration: Duration) -> Self {
+        self.min_duration = min_duration;
+        self
+    }
+
+    /// Wraps the words of the text into lines of at most [Self::max_chars_per_line] characters.
+    fn wrap(&self, text: &str) -> Vec<String> {
+        let max = self.max_chars_per_line.max(1);
+        let mut lines: Vec<String> = vec![];
+        for word in text.split_whitespace() {
+            match lines.last_mut() {
+                Some(line) if line.chars().count() + 1 + word.chars().count() <= max => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                _ => lines.push(word.to_string()),
+            }
+        }
+        lines
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        self.wrap(text).len() <= self.max_lines.max(1)
+    }
+}
+
+/// The cues of a subtitle file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Subtitle {
+    pub cues: Vec<Cue>,
+}
+
+impl Subtitle {
+    pub fn new(cues: Vec<Cue>) -> Self {
+        Self { cues }
+    }
+
+    /// Parses a SubRip file.
+    pub fn parse_srt(text: &str) -> Result<Self, OpenAIError> {
+        let cues = blocks(text)
+            .into_iter()
+            .map(|(line, block)| parse_cue("srt", line, &block))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { cues })
+    }
+
+    /// Parses a WebVTT file. Comments, styles and regions are skipped, as are the settings of cues.
+    pub fn parse_vtt(text: &str) -> Result<Self, OpenAIError> {
+        let mut blocks = blocks(text).into_iter();
+        match blocks.next() {
+            Some((_, header)) if header[0].starts_with("WEBVTT") => {}
+            _ => return Err(invalid("vtt", 1, "missing WEBVTT header")),
+        }
+        let cues = blocks
+            .filter(|(_, block)| {
+                !["NOTE", "STYLE", "REGION"].iter().any(|kind| {
+                    block[0] == *kind
+                        || block[0].starts_with(&format!("{kind} "))
+                        || block[0].starts_with(&format!("{kind}\t"))
+                })
+            })
+            .map(|(line, block)| parse_cue("vtt", line, &block))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { cues })
+    }
+
+    /// Writes a SubRip file, with cues numbered from 1.
+    pub fn to_srt(&self) -> String {
+        let mut srt = String::new();
+        for (index, cue) in self.cues.iter().enumerate() {
+            srt.push_str(&format!(
+                "{}\n{} --> {}\n{}\n\n",
+                index + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                cue.text
+            ));
+        }
+        srt
+    }
+
+    /// Writes a WebVTT file, with the identifiers of the cues.
+    pub fn to_vtt(&self) -> String {
+        let mut vtt = String::from("WEBVTT\n\n");
+        for cue in &self.cues {
+            if let Some(id) = &cue.id {
+                vtt.push_str(id);
+                vtt.push('\n');
+            }
+            vtt.push_str(&format!(
+                "{} --> {}\n{}\n\n",
+                timestamp(cue.start, '.'),
+                timestamp(cue.end, '.'),
+                cue.text
+            ));
+        }
+        vtt
+    }
+
+    /// Generates cues from transcription segments.
+    pub fn from_segments(segments: &[TranscriptionSegment], options: &SubtitleOptions) -> Self {
+        let pieces = segments
+            .iter()
+            .map(|segment| {
+                Cue::new(
+                    seconds(segment.start),
+                    seconds(segment.end),
+                    segment.text.trim(),
+                )
+            })
+            .collect();
+        Self::layout(pieces, options)
+    }
+
+    /// Generates cues from the timestamps of words, as many words per cue as fit.
+    pub fn from_words(words: &[TranscriptionWord], options: &SubtitleOptions) -> Self {
+        let mut pieces: Vec<Cue> = vec![];
+        for word in words {
+            let text = word.word.trim();
+            if text.is_empty() {
+                continue;
+            }
+            match pieces.last_mut() {
+                Some(piece) if options.fits(&format!("{} {text}", piece.text)) => {
+                    piece.text.push(' ');
+                    piece.text.push_str(text);
+                    piece.end = seconds(word.end);
+                }
+                _ => pieces.push(Cue::new(seconds(word.start), seconds(word.end), text)),
+            }
+        }
+        Self::layout(pieces, options)
+    }
+
+    /// Generates cues from the words of a transcription if it has them, otherwise from its segments or, without
+    /// either, from its text over the whole duration.
+    pub fn from_verbose_json(
+        response: &CreateTranscriptionResponseVerboseJson,
+        options: &SubtitleOptions,
+    ) -> Self {
+        match (&response.words, &response.segments) {
+            (Some(words), _) if !words.is_empty() => Self::from_words(words, options),
+            (_, Some(segments)) if !segments.is_empty() => Self::from_segments(segments, options),
+            _ => Self::layout(
+                vec![Cue::new(
+                    Duration::ZERO,
+                    seconds(response.duration),
+                    response.text.trim(),
+                )],
+                options,
+            ),
+        }
+    }
+
+    /// Merges short pieces into their neighbours and wraps and splits the text of the pieces into cues.
+    fn layout(pieces: Vec<Cue>, options: &SubtitleOptions) -> Self {
+        let mut merged: Vec<Cue> = vec![];
+        for piece in pieces.into_iter().filter(|piece| !piece.text.is_empty()) {
+            if let Some(last) = merged.last_mut() {
+                let short = last.duration() < options.min_duration
+                    || piece.duration() < options.min_duration;
+                let text = format!("{} {}", last.text, piece.text);
+                if short && options.fits(&text) {
+                    last.text = text;
+                    last.end = piece.end;
+                    continue;
+                }
+            }
+            merged.push(piece);
+        }
+
+        let mut cues = vec![];
+        for piece in merged {
+            let lines = options.wrap(&piece.text);
+            let chunks: Vec<&[String]> = lines.chunks(options.max_lines.max(1)).collect();
+            let chars: Vec<usize> = chunks
+                .iter()
+                .map(|chunk| chunk.iter().map(|line| line.chars().count()).sum())
+                .collect();
+            let total: usize = chars.iter().sum();
+            let mut start = piece.start;
+            let mut elapsed = 0;
+            for (chunk, chars) in chunks.into_iter().zip(chars) {
+                elapsed += chars;
+                let end = if elapsed == total {
+                    piece.end
+                } else {
+                    piece.start + piece.duration().mul_f64(elapsed as f64 / total as f64)
+                };
+                cues.push(Cue::new(start, end, chunk.join("\n")));
+                start = end;
+            }
+        }
+        Self { cues }
+    }
+}
+
+fn invalid(format: &str, line: usize, message: &str) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!(
+        "invalid {format} subtitles at line {line}: {message}"
+    ))
+}
+
+/// Seconds of the API rounded to milliseconds.
+fn seconds(seconds: f32) -> Duration {
+    Duration::from_millis((seconds.max(0.0) as f64 * 1000.0).round() as u64)
+}
+
+fn timestamp(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Parses `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm`.
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let (time, millis) = timestamp.split_once([',', '.'])?;
+    let parts: Vec<&str> = time.split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        [minutes, seconds] => ("0", minutes, seconds),
+        _ => return None,
+    };
+    let number = |digits: &str| {
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse::<u64>().ok()
+    };
+    if millis.len() != 3 || minutes.len() != 2 || seconds.len() != 2 {
+        return None;
+    }
+    // only the hours are unbounded, a timestamp too far out to count in milliseconds is invalid
+    let seconds = number(hours)?
+        .checked_mul(3600)?
+        .checked_add(number(minutes)? * 60 + number(seconds)?)?;
+    let millis = seconds.checked_mul(1000)?.checked_add(number(millis)?)?;
+    Some(Duration::from_millis(millis))
+}
+
+/// Splits a file into blocks of lines separated by blank lines, with the line number of their first line.
+fn blocks(text: &str) -> Vec<(usize, Vec<&str>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut blocks: Vec<(usize, Vec<&str>)> = vec![];
+    let mut in_block = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            in_block = false;
+        } else if in_block {
+            blocks.last_mut().unwrap().1.push(line);
+        } else {
+            blocks.push((index + 1, vec![line]));
+            in_block = true;
+        }
+    }
+    blocks
+}
+
+/// Parses an optional identifier line, a timing line and the text of a cue.
+fn parse_cue(format: &str, line: usize, block: &[&str]) -> Result<Cue, OpenAIError> {
+    let (id, timing, text) = if block[0].contains("-->") {
+        (None, block[0], &block[1..])
+    } else if let Some(timing) = block.get(1) {
+        (Some(block[0].trim().to_string()), *timing, &block[2..])
+    } else {
+        return Err(invalid(format, line, "missing timing line"));
+    };
+    let timing_line = line + usize::from(id.is_some());
+    let (start, end) = timing
+        .split_once("-->")
+        .ok_or_else(|| invalid(format, timing_line, "missing timing line"))?;
+    // cue settings of WebVTT follow the end
+    let end = end.split_whitespace().next().unwrap_or_default();
+    let (Some(start), Some(end)) = (parse_timestamp(start.trim()), parse_timestamp(end)) else {
+        return Err(invalid(format, timing_line, "invalid timestamp"));
+    };
+    Ok(Cue {
+        id,
+        start,
+        end,
+        text: text.join("\n"),
+    })
+}
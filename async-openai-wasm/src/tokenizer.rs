@@ -0,0 +1,404 @@
+//! Client-side token counting, compatible with OpenAI's [tiktoken](https://github.com/openai/tiktoken).
+//!
+//! The BPE ranks of `cl100k_base` and `o200k_base` are bundled with the crate, so counting works
+//! without network access, including on `wasm32-unknown-unknown`.
+//!
+//! ```
+//! use async_openai_wasm::tokenizer::{Encoding, Tokenizer};
+//!
+//! let tokenizer = Tokenizer::for_model("gpt-4o").unwrap();
+//! assert_eq!(tokenizer.encoding(), Encoding::O200kBase);
+//!
+//! let tokens = tokenizer.encode("hello world");
+//! assert_eq!(tokenizer.decode(&tokens).unwrap(), "hello world");
+//! ```
+use std::ops::Range;
+
+use tiktoken_rs::CoreBPE;
+
+use crate::error::OpenAIError;
+use crate::types::{
+    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestAssistantMessageContentPart,
+    ChatCompletionRequestDeveloperMessageContent, ChatCompletionRequestDeveloperMessageContentPart,
+    ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageContent,
+    ChatCompletionRequestSystemMessageContentPart, ChatCompletionRequestToolMessageContent,
+    ChatCompletionRequestToolMessageContentPart, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestUserMessageContentPart, ChatCompletionTool, CreateChatCompletionRequest,
+    EmbeddingInput, ImageDetail,
+};
+
+/// Every message is wrapped in `<|start|>{role/name}\n{content}<|end|>\n`, on top of the tokens of its role.
+const TOKENS_PER_MESSAGE: usize = 3;
+/// A name replaces the role, costing one extra token.
+const TOKENS_PER_NAME: usize = 1;
+/// Every reply is primed with `<|start|>assistant<|message|>`.
+const TOKENS_PER_REPLY: usize = 3;
+
+/// Byte pair encodings used by OpenAI models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Used by `gpt-4`, `gpt-3.5-turbo` and the `text-embedding-3` and `text-embedding-ada-002` models.
+    Cl100kBase,
+    /// Used by `gpt-4o`, `gpt-4.1`, `o1`, `o3` and newer models.
+    O200kBase,
+}
+
+/// Model id prefixes and their encodings, more specific prefixes first.
+const MODEL_PREFIX_ENCODINGS: &[(&str, Encoding)] = &[
+    ("gpt-4o", Encoding::O200kBase),
+    ("chatgpt-4o", Encoding::O200kBase),
+    ("gpt-4.1", Encoding::O200kBase),
+    ("gpt-4.5", Encoding::O200kBase),
+    ("gpt-5", Encoding::O200kBase),
+    ("gpt-oss", Encoding::O200kBase),
+    ("o1", Encoding::O200kBase),
+    ("o3", Encoding::O200kBase),
+    ("o4", Encoding::O200kBase),
+    ("gpt-4", Encoding::Cl100kBase),
+    ("gpt-3.5-turbo", Encoding::Cl100kBase),
+    ("gpt-35-turbo", Encoding::Cl100kBase),
+    ("text-embedding-3", Encoding::Cl100kBase),
+    ("text-embedding-ada-002", Encoding::Cl100kBase),
+    ("davinci-002", Encoding::Cl100kBase),
+    ("babbage-002", Encoding::Cl100kBase),
+];
+
+impl Encoding {
+    /// The encoding used by a model, matched by the prefix of the model id.
+    /// Fine-tuned models (`ft:{base model}:...`) use the encoding of their base model.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.strip_prefix("ft:").unwrap_or(model);
+        MODEL_PREFIX_ENCODINGS
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, encoding)| *encoding)
+    }
+
+    /// The name of the encoding as used by tiktoken.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::O200kBase => "o200k_base",
+        }
+    }
+}
+
+/// A BPE tokenizer for one [Encoding].
+///
+/// The ranks of each encoding are loaded once on first use and shared by all tokenizers.
+#[derive(Clone, Copy)]
+pub struct Tokenizer {
+    encoding: Encoding,
+    bpe: &'static CoreBPE,
+}
+
+impl std::fmt::Debug for Tokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tokenizer")
+            .field("encoding", &self.encoding)
+            .finish()
+    }
+}
+
+impl Tokenizer {
+    pub fn new(encoding: Encoding) -> Self {
+        let bpe = match encoding {
+            Encoding::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+            Encoding::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        };
+        Self { encoding, bpe }
+    }
+
+    /// Tokenizer of the encoding used by the given model, see [Encoding::for_model].
+    pub fn for_model(model: &str) -> Result<Self, OpenAIError> {
+        Encoding::for_model(model).map(Self::new).ok_or_else(|| {
+            OpenAIError::InvalidArgument(format!("no known tokenizer encoding for model {model}"))
+        })
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Encodes text into tokens, treating special tokens such as `<|endoftext|>` as ordinary text.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        self.bpe.encode_ordinary(text)
+    }
+
+    /// Decodes tokens into text.
+    pub fn decode(&self, tokens: &[u32]) -> Result<String, OpenAIError> {
+        self.bpe
+            .decode(tokens.to_vec())
+            .map_err(|e| OpenAIError::InvalidArgument(format!("cannot decode tokens: {e}")))
+    }
+
+    /// Number of tokens in the text.
+    pub fn count(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+
+    /// Byte ranges of the tokens in the text. Tokens ending within a multi-byte character are merged with the
+    /// following ones, so every range can be used to slice the text.
+    pub fn token_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = vec![];
+        let mut start = 0;
+        let mut pending = vec![];
+        for token in self.encode(text) {
+            pending.push(token);
+            // the bytes of the pending tokens only decode once they end on a character boundary
+            if let Ok(decoded) = self.bpe.decode(pending.clone()) {
+                ranges.push(start..start + decoded.len());
+                start += decoded.len();
+                pending.clear();
+            }
+        }
+        ranges
+    }
+
+    /// Encodes a list of texts into [EmbeddingInput::ArrayOfIntegerArray].
+    pub fn encode_embedding_input<S: AsRef<str>>(&self, texts: &[S]) -> EmbeddingInput {
+        EmbeddingInput::ArrayOfIntegerArray(
+            texts
+                .iter()
+                .map(|text| self.encode(text.as_ref()))
+                .collect(),
+        )
+    }
+
+    /// Decodes every input of an [EmbeddingInput] back into text.
+    pub fn decode_embedding_input(
+        &self,
+        input: &EmbeddingInput,
+    ) -> Result<Vec<String>, OpenAIError> {
+        match input {
+            EmbeddingInput::String(text) => Ok(vec![text.clone()]),
+            EmbeddingInput::StringArray(texts) => Ok(texts.clone()),
+            EmbeddingInput::IntegerArray(tokens) => Ok(vec![self.decode(tokens)?]),
+            EmbeddingInput::ArrayOfIntegerArray(inputs) => {
+                inputs.iter().map(|tokens| self.decode(tokens)).collect()
+            }
+        }
+    }
+
+    /// Number of prompt tokens of a list of messages, including the per-message overhead of the chat format
+    /// and the tokens priming the reply.
+    ///
+    /// Images are counted with [image_tokens], with the size read from the image of a base64 data URL, such as built by
+    /// [crate::types::ImageUrl::from_bytes]. The size of images referenced by other URLs is unknown on the client,
+    /// they are counted as 1024x1024 images unless their detail is `low`.
+    pub fn count_message_tokens(&self, messages: &[ChatCompletionRequestMessage]) -> usize {
+        messages
+            .iter()
+            .map(|message| self.count_message(message))
+            .sum::<usize>()
+            + TOKENS_PER_REPLY
+    }
+
+    /// Number of prompt tokens of a request: its messages and the definitions of its tools.
+    #[allow(deprecated)]
+    pub fn count_request_tokens(&self, request: &CreateChatCompletionRequest) -> usize {
+        let mut count = self.count_message_tokens(&request.messages);
+        if let Some(tools) = &request.tools {
+            count += self.count_tool_tokens(tools);
+        }
+        if let Some(functions) = &request.functions {
+            let tools: Vec<ChatCompletionTool> = functions
+                .iter()
+                .map(|function| ChatCompletionTool {
+                    r#type: Default::default(),
+                    function: crate::types::FunctionObject {
+                        name: function.name.clone(),
+                        description: function.description.clone(),
+                        parameters: Some(function.parameters.clone()),
+                        strict: None,
+                    },
+                })
+                .collect();
+            count += self.count_tool_tokens(&tools);
+        }
+        count
+    }
+
+    /// Number of tokens of tool definitions, following the format the definitions are rendered in for the model.
+    /// The count is an estimate for parameters with nested objects.
+    pub fn count_tool_tokens(&self, tools: &[ChatCompletionTool]) -> usize {
+        if tools.is_empty() {
+            return 0;
+        }
+        // (function start, function end) overhead, which differs between encodings
+        let (function_init, function_end) = match self.encoding {
+            Encoding::O200kBase => (7, 12),
+            Encoding::Cl100kBase => (10, 12),
+        };
+        const PROPERTY_INIT: usize = 3;
+        const PROPERTY_KEY: usize = 3;
+        const ENUM_ITEM: usize = 3;
+        // the first enum item replaces part of the property overhead
+        const ENUM_INIT_SAVING: usize = 3;
+
+        let mut count = 0;
+        for tool in tools {
+            let function = &tool.function;
+            count += function_init;
+            let description = function.description.as_deref().unwrap_or_default();
+            let description = description.strip_suffix('.').unwrap_or(description);
+            count += self.count(&format!("{}:{}", function.name, description));
+
+            let properties = function
+                .parameters
+                .as_ref()
+                .and_then(|parameters| parameters.get("properties"))
+                .and_then(|properties| properties.as_object());
+            let Some(properties) = properties.filter(|p| !p.is_empty()) else {
+                continue;
+            };
+            count += PROPERTY_INIT;
+            for (name, property) in properties {
+                count += PROPERTY_KEY;
+                if let Some(items) = property.get("enum").and_then(|e| e.as_array()) {
+                    count = count.saturating_sub(ENUM_INIT_SAVING);
+                    for item in items {
+                        count += ENUM_ITEM;
+                        count += self.count(
+                            &item
+                                .as_str()
+                                .map(String::from)
+                                .unwrap_or_else(|| item.to_string()),
+                        );
+                    }
+                }
+                let r#type = match property.get("type") {
+                    Some(serde_json::Value::String(t)) => t.clone(),
+                    Some(other) => other.to_string(),
+                    None => String::new(),
+                };
+                let description = property
+                    .get("description")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or_default();
+                let description = description.strip_suffix('.').unwrap_or(description);
+                count += self.count(&format!("{name}:{type}:{description}"));
+            }
+        }
+        count + function_end
+    }
+
+    #[allow(deprecated)]
+    fn count_message(&self, message: &ChatCompletionRequestMessage) -> usize {
+        let mut count = TOKENS_PER_MESSAGE + self.count(&message.role().to_string());
+        let count_name = |name: &Option<String>| {
+            name.as_ref()
+                .map(|name| self.count(name) + TOKENS_PER_NAME)
+                .unwrap_or_default()
+        };
+        match message {
+            ChatCompletionRequestMessage::System(message) => {
+                count += count_name(&message.name);
+                count += match &message.content {
+                    ChatCompletionRequestSystemMessageContent::Text(text) => self.count(text),
+                    ChatCompletionRequestSystemMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(
+                            |ChatCompletionRequestSystemMessageContentPart::Text(part)| {
+                                self.count(&part.text)
+                            },
+                        )
+                        .sum(),
+                };
+            }
+            ChatCompletionRequestMessage::Developer(message) => {
+                count += count_name(&message.name);
+                count += match &message.content {
+                    ChatCompletionRequestDeveloperMessageContent::Text(text) => self.count(text),
+                    ChatCompletionRequestDeveloperMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(
+                            |ChatCompletionRequestDeveloperMessageContentPart::Text(part)| {
+                                self.count(&part.text)
+                            },
+                        )
+                        .sum(),
+                };
+            }
+            ChatCompletionRequestMessage::User(message) => {
+                count += count_name(&message.name);
+                count += match &message.content {
+                    ChatCompletionRequestUserMessageContent::Text(text) => self.count(text),
+                    ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ChatCompletionRequestUserMessageContentPart::Text(part) => {
+                                self.count(&part.text)
+                            }
+                            ChatCompletionRequestUserMessageContentPart::ImageUrl(part) => {
+                                part.image_url.estimated_tokens().unwrap_or_else(|| {
+                                    let detail = part.image_url.detail.clone().unwrap_or_default();
+                                    image_tokens(1024, 1024, &detail)
+                                })
+                            }
+                        })
+                        .sum(),
+                };
+            }
+            ChatCompletionRequestMessage::Assistant(message) => {
+                count += count_name(&message.name);
+                count += match &message.content {
+                    None => 0,
+                    Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => {
+                        self.count(text)
+                    }
+                    Some(ChatCompletionRequestAssistantMessageContent::Array(parts)) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ChatCompletionRequestAssistantMessageContentPart::Text(part) => {
+                                self.count(&part.text)
+                            }
+                            ChatCompletionRequestAssistantMessageContentPart::Refusal(part) => {
+                                self.count(&part.refusal)
+                            }
+                        })
+                        .sum(),
+                };
+                count += message
+                    .refusal
+                    .as_deref()
+                    .map(|r| self.count(r))
+                    .unwrap_or_default();
+                for tool_call in message.tool_calls.iter().flatten() {
+                    count += self.count(&tool_call.function.name)
+                        + self.count(&tool_call.function.arguments);
+                }
+                if let Some(function_call) = &message.function_call {
+                    count += self.count(&function_call.name) + self.count(&function_call.arguments);
+                }
+            }
+            ChatCompletionRequestMessage::Tool(message) => {
+                count += self.count(&message.tool_call_id);
+                count += match &message.content {
+                    ChatCompletionRequestToolMessageContent::Text(text) => self.count(text),
+                    ChatCompletionRequestToolMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(|ChatCompletionRequestToolMessageContentPart::Text(part)| {
+                            self.count(&part.text)
+                        })
+                        .sum(),
+                };
+            }
+            ChatCompletionRequestMessage::Function(message) => {
+                count += self.count(&message.name) + TOKENS_PER_NAME;
+                count += message
+                    .content
+                    .as_deref()
+                    .map(|c| self.count(c))
+                    .unwrap_or_default();
+            }
+        }
+        count
+    }
+}
+
+/// Number of tokens of an image input of the given size, as billed for `gpt-4o` and `gpt-4.1`,
+/// see [ImageDetail::image_tokens].
+pub fn image_tokens(width: u32, height: u32, detail: &ImageDetail) -> usize {
+    detail.image_tokens(width, height)
+}
@@ -0,0 +1,377 @@
+//! Transcription of WAV and raw PCM audio over the upload limit with [crate::Audio::transcribe_long].
+//!
+//! The audio is split into WAV chunks of at most [MAX_UPLOAD_BYTES], preferably in the quietest moment before
+//! the chunk duration, with some overlap so that no word is cut. The chunks are transcribed concurrently and
+//! their segments and words are merged with the offsets of the chunks, keeping every item of the overlap once.
+//!
+//! ```
+//! use async_openai_wasm::audio_format::wav_header;
+//! use async_openai_wasm::transcription_batch::TranscriptionBatchOptions;
+//! use std::time::Duration;
+//!
+//! // 25 seconds of 16-bit mono at 16 kHz
+//! let mut wav = wav_header(1, 16_000, 16, 800_000);
+//! wav.extend(vec![0; 800_000]);
+//!
+//! let options = TranscriptionBatchOptions::default()
+//!     .with_chunk_duration(Duration::from_secs(10))
+//!     .with_overlap(Duration::from_secs(1))
+//!     .with_split_on_silence(false);
+//! let chunks = options.split(&wav).unwrap();
+//! let starts: Vec<u64> = chunks.iter().map(|chunk| chunk.start.as_secs()).collect();
+//! assert_eq!(starts, [0, 9, 18]);
+//! assert_eq!(chunks[2].end, Duration::from_secs(25));
+//! ```
+use std::ops::Range;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use crate::audio_format::{wav_header, Wav};
+use crate::error::OpenAIError;
+use crate::stream::PCM_SAMPLE_RATE;
+use crate::types::{
+    CreateTranscriptionResponseVerboseJson, TranscriptionSegment, TranscriptionWord,
+};
+use crate::util::BatchRequestOptions;
+
+/// The maximum size of an audio file uploaded for transcription.
+pub const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+/// The length of the windows compared when looking for silence.
+const SILENCE_WINDOW: Duration = Duration::from_millis(50);
+
+/// About the number of words in the last 224 tokens of a prompt, which are all the API uses.
+const PROMPT_WORDS: usize = 150;
+
+/// The fewest repeated words taken for the overlap of two chunks, as a single word such as "the" often repeats anyway.
+const MIN_OVERLAP_WORDS: usize = 2;
+
+/// Chunking, concurrency and retry policy of [crate::Audio::transcribe_long].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptionBatchOptions {
+    /// The maximum duration of a chunk, 5 minutes by default. Chunks are shorter if they would exceed
+    /// [MAX_UPLOAD_BYTES].
+    pub chunk_duration: Duration,
+    /// How much of the end of a chunk is repeated at the start of the next, 2 seconds by default.
+    pub overlap: Duration,
+    /// Whether chunks end in the quietest moment of the last [Self::silence_search] of their duration instead of
+    /// exactly at [Self::chunk_duration], true by default.
+    pub split_on_silence: bool,
+    /// How far back from the end of a chunk silence is looked for, 15 seconds by default.
+    pub silence_search: Duration,
+    /// The number of channels of raw PCM input, 1 by default.
+    pub pcm_channels: u16,
+    /// The sample rate of raw 16-bit PCM input, 24 kHz by default as returned by [crate::Audio::speech].
+    pub pcm_sample_rate: u32,
+    /// Whether the end of the text of the previous chunk follows the `prompt` of the request for the next chunk, for a
+    /// consistent style and spelling across chunks. The chunks are then transcribed one after another. False by default.
+    pub previous_text_prompt: bool,
+    /// Concurrency and retries of the requests.
+    pub requests: BatchRequestOptions,
+}
+
+impl Default for TranscriptionBatchOptions {
+    fn default() -> Self {
+        Self {
+            chunk_duration: Duration::from_secs(300),
+            overlap: Duration::from_secs(2),
+            split_on_silence: true,
+            silence_search: Duration::from_secs(15),
+            pcm_channels: 1,
+            pcm_sample_rate: PCM_SAMPLE_RATE,
+            previous_text_prompt: false,
+            requests: BatchRequestOptions::default(),
+        }
+    }
+}
+
+/// A chunk of the audio of [crate::Audio::transcribe_long].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioChunk {
+    /// The position of the chunk, starting at 0.
+    pub index: usize,
+    /// Where the chunk starts in the audio.
+    pub start: Duration,
+    /// Where the chunk ends in the audio.
+    pub end: Duration,
+    /// The chunk as a WAV file.
+    pub wav: Bytes,
+}
+
+impl TranscriptionBatchOptions {
+    pub fn with_chunk_duration(mut self, chunk_duration: Duration) -> Self {
+        self.chunk_duration = chunk_duration;
+        self
+    }
+
+    pub fn with_overlap(mut self, overlap: Duration) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn with_split_on_silence(mut self, split_on_silence: bool) -> Self {
+        self.split_on_silence = split_on_silence;
+        self
+    }
+
+    pub fn with_silence_search(mut self, silence_search: Duration) -> Self {
+        self.silence_search = silence_search;
+        self
+    }
+
+    /// The format of raw 16-bit PCM input.
+    pub fn with_pcm_format(mut self, channels: u16, sample_rate: u32) -> Self {
+        self.pcm_channels = channels;
+        self.pcm_sample_rate = sample_rate;
+        self
+    }
+
+    pub fn with_previous_text_prompt(mut self, previous_text_prompt: bool) -> Self {
+        self.previous_text_prompt = previous_text_prompt;
+        self
+    }
+
+    pub fn with_requests(mut self, requests: BatchRequestOptions) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Splits a WAV file, or raw PCM in the format of the options, into overlapping WAV chunks.
+    pub fn split(&self, audio: &[u8]) -> Result<Vec<AudioChunk>, OpenAIError> {
+        let wav = if audio.starts_with(b"RIFF") {
+            Wav::parse(audio)?
+        } else {
+            if self.pcm_channels == 0 || self.pcm_sample_rate == 0 {
+                return Err(OpenAIError::InvalidArgument(
+                    "the PCM format must have channels and a sample rate".into(),
+                ));
+            }
+            Wav {
+                channels: self.pcm_channels,
+                sample_rate: self.pcm_sample_rate,
+                bits_per_sample: 16,
+                data: 0..audio.len(),
+            }
+        };
+        let samples = &audio[wav.data.clone()];
+        let frame = wav.block_align() as usize;
+        let rate = wav.sample_rate as f64;
+        let frames = samples.len() / frame;
+        let to_frames = |duration: Duration| (duration.as_secs_f64() * rate) as usize;
+        let to_duration = |frames: usize| Duration::from_secs_f64(frames as f64 / rate);
+
+        let max_frames = to_frames(self.chunk_duration)
+            .min((MAX_UPLOAD_BYTES - 44) / frame)
+            .max(1);
+        // the overlap leaves chunks at least half new audio
+        let overlap = to_frames(self.overlap).min(max_frames / 2);
+        let window = to_frames(SILENCE_WINDOW).max(1);
+
+        let mut chunks = vec![];
+        let mut start = 0;
+        while start < frames {
+            let mut end = (start + max_frames).min(frames);
+            if self.split_on_silence && end < frames {
+                let earliest = (start + overlap + max_frames / 2)
+                    .max(end.saturating_sub(to_frames(self.silence_search)));
+                end = quietest(samples, &wav, earliest..end, window).unwrap_or(end);
+            }
+            let data = &samples[start * frame..end * frame];
+            let mut file = wav_header(
+                wav.channels,
+                wav.sample_rate,
+                wav.bits_per_sample,
+                data.len() as u32,
+            );
+            file.extend_from_slice(data);
+            chunks.push(AudioChunk {
+                index: chunks.len(),
+                start: to_duration(start),
+                end: to_duration(end),
+                wav: file.into(),
+            });
+            if end == frames {
+                break;
+            }
+            start = end - overlap;
+        }
+        Ok(chunks)
+    }
+}
+
+/// The frame in the middle of the quietest window of `frames`, by mean amplitude, preferring later windows.
+fn quietest(samples: &[u8], wav: &Wav, frames: Range<usize>, window: usize) -> Option<usize> {
+    let frame = wav.block_align() as usize;
+    let width = frame / wav.channels as usize;
+    // the two most significant bytes of every little-endian sample, 8-bit samples are unsigned
+    let amplitude = |sample: &[u8]| -> u64 {
+        if width == 1 {
+            (sample[0] as i16 - 128).unsigned_abs() as u64
+        } else {
+            i16::from_le_bytes([sample[width - 2], sample[width - 1]]).unsigned_abs() as u64
+        }
+    };
+    (frames.start..frames.end.saturating_sub(window))
+        .step_by(window)
+        // the latest of equally quiet windows
+        .rev()
+        .map(|start| {
+            let loudness: u64 = samples[start * frame..(start + window) * frame]
+                .chunks_exact(width)
+                .map(amplitude)
+                .sum();
+            (loudness, start + window / 2)
+        })
+        .min_by_key(|(loudness, _)| *loudness)
+        .map(|(_, frame)| frame)
+}
+
+/// Merges the transcriptions of the chunks into one, with timestamps in the whole audio.
+///
+/// Segments and words in the overlap of two chunks are taken from the first chunk when they start in the first
+/// half of the overlap and from the second chunk otherwise. Without either, the words repeated at the start of a
+/// text are removed.
+pub(crate) fn merge(
+    chunks: &[AudioChunk],
+    transcriptions: Vec<CreateTranscriptionResponseVerboseJson>,
+    duration: Duration,
+) -> CreateTranscriptionResponseVerboseJson {
+    // the time from which items are taken from every chunk
+    let cuts: Vec<f64> = chunks
+        .iter()
+        .enumerate()
+        .map(
+            |(index, chunk)| match index.checked_sub(1).map(|index| &chunks[index]) {
+                Some(previous) if previous.end > chunk.start => {
+                    (chunk.start + previous.end).as_secs_f64() / 2.0
+                }
+                _ => chunk.start.as_secs_f64(),
+            },
+        )
+        .chain([f64::INFINITY])
+        .collect();
+
+    let mut language = None;
+    let mut text = String::new();
+    let mut words: Option<Vec<TranscriptionWord>> = None;
+    let mut segments: Option<Vec<TranscriptionSegment>> = None;
+    for (index, (chunk, transcription)) in chunks.iter().zip(transcriptions).enumerate() {
+        let offset = chunk.start.as_secs_f64();
+        let keep = |start: f32| {
+            let start = offset + start as f64;
+            cuts[index] <= start && start < cuts[index + 1]
+        };
+        language.get_or_insert(transcription.language);
+
+        let chunk_words = transcription.words.map(|chunk_words| {
+            chunk_words
+                .into_iter()
+                .filter(|word| keep(word.start))
+                .map(|word| TranscriptionWord {
+                    start: (offset + word.start as f64) as f32,
+                    end: (offset + word.end as f64) as f32,
+                    ..word
+                })
+                .collect::<Vec<_>>()
+        });
+        let chunk_segments = transcription.segments.map(|chunk_segments| {
+            chunk_segments
+                .into_iter()
+                .filter(|segment| keep(segment.start))
+                .map(|segment| TranscriptionSegment {
+                    start: (offset + segment.start as f64) as f32,
+                    end: (offset + segment.end as f64) as f32,
+                    // seek is in frames of 10 ms
+                    seek: segment.seek + (offset * 100.0) as i32,
+                    ..segment
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let chunk_text = match (&chunk_segments, &chunk_words) {
+            (Some(segments), _) => segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect(),
+            (_, Some(words)) => words
+                .iter()
+                .map(|word| word.word.trim())
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => dedupe_overlap(&text, &transcription.text).to_string(),
+        };
+        let chunk_text = chunk_text.trim();
+        if !chunk_text.is_empty() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(chunk_text);
+        }
+        if let Some(chunk_words) = chunk_words {
+            words.get_or_insert_with(Vec::new).extend(chunk_words);
+        }
+        if let Some(chunk_segments) = chunk_segments {
+            segments.get_or_insert_with(Vec::new).extend(chunk_segments);
+        }
+    }
+    if let Some(segments) = &mut segments {
+        for (id, segment) in segments.iter_mut().enumerate() {
+            segment.id = id as i32;
+        }
+    }
+
+    CreateTranscriptionResponseVerboseJson {
+        language: language.unwrap_or_default(),
+        duration: duration.as_secs_f32(),
+        text,
+        words,
+        segments,
+    }
+}
+
+/// The prompt of a chunk: the `prompt` of the request, then as much of the end of the `previous` text as fits in the
+/// part of a prompt used by the API.
+pub(crate) fn chunk_prompt(prompt: Option<&str>, previous: &str) -> String {
+    let prompt = prompt.unwrap_or_default().trim();
+    let budget = PROMPT_WORDS.saturating_sub(prompt.split_whitespace().count());
+    let previous: Vec<&str> = previous.split_whitespace().collect();
+    let mut parts = vec![prompt];
+    parts.extend(&previous[previous.len().saturating_sub(budget)..]);
+    parts.retain(|part| !part.is_empty());
+    parts.join(" ")
+}
+
+/// `next` without its longest start of at least [MIN_OVERLAP_WORDS] words that repeats the end of `previous`,
+/// comparing words without case and punctuation.
+fn dedupe_overlap<'a>(previous: &str, next: &'a str) -> &'a str {
+    let normalize = |word: &str| -> String {
+        word.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let tail: Vec<String> = previous.split_whitespace().map(normalize).collect();
+    // the words of `next` with the offset after them
+    let mut end = 0;
+    let head: Vec<(usize, &str)> = next
+        .split_inclusive(char::is_whitespace)
+        .filter_map(|piece| {
+            end += piece.len();
+            let word = piece.trim();
+            (!word.is_empty()).then_some((end, word))
+        })
+        .collect();
+    let repeated = (MIN_OVERLAP_WORDS..=head.len().min(tail.len()))
+        .rev()
+        .find(|&count| {
+            tail[tail.len() - count..]
+                .iter()
+                .zip(&head[..count])
+                .all(|(previous, (_, word))| *previous == normalize(word))
+        });
+    match repeated {
+        Some(count) => &next[head[count - 1].0..],
+        None => next,
+    }
+}
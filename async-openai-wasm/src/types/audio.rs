@@ -53,10 +53,23 @@ pub enum SpeechModel {
     Tts1,
     #[serde(rename = "tts-1-hd")]
     Tts1Hd,
+    #[serde(rename = "gpt-4o-mini-tts")]
+    Gpt4oMiniTts,
     #[serde(untagged)]
     Other(String),
 }
 
+/// How the audio of a speech response is sent.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeechStreamFormat {
+    /// The audio file as the response body.
+    #[default]
+    Audio,
+    /// Server-sent events of base64 audio chunks, see [SpeechStreamEvent]. Not supported by `tts-1` and `tts-1-hd`.
+    Sse,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum TimestampGranularity {
@@ -65,6 +78,39 @@ pub enum TimestampGranularity {
     Segment,
 }
 
+/// Additional information to include in a transcription response.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionInclude {
+    /// The log probabilities of the tokens of the transcription, see [TranscriptionLogprob]. Only supported with
+    /// the `json` response format and the `gpt-4o-transcribe` and `gpt-4o-mini-transcribe` models.
+    Logprobs,
+}
+
+/// Voice activity detection settings of [TranscriptionChunkingStrategy::ServerVad], server defaults when unset.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranscriptionServerVad {
+    /// Amount of audio to include before speech starts (in milliseconds).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix_padding_ms: Option<u32>,
+    /// Duration of silence to detect speech stop (in milliseconds).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silence_duration_ms: Option<u32>,
+    /// Activation threshold for VAD (0.0 to 1.0).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f32>,
+}
+
+/// How the audio is cut into chunks before it is transcribed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum TranscriptionChunkingStrategy {
+    /// The server normalizes the loudness and chooses the boundaries with voice activity detection.
+    #[default]
+    Auto,
+    /// Voice activity detection with the given settings.
+    ServerVad(TranscriptionServerVad),
+}
+
 #[derive(Clone, Default, Debug, Builder, PartialEq)]
 #[builder(name = "CreateTranscriptionRequestArgs")]
 #[builder(pattern = "mutable")]
@@ -92,6 +138,52 @@ pub struct CreateTranscriptionRequest {
 
     /// The timestamp granularities to populate for this transcription. `response_format` must be set `verbose_json` to use timestamp granularities. Either or both of these options are supported: `word`, or `segment`. Note: There is no additional latency for segment timestamps, but generating word timestamps incurs additional latency.
     pub timestamp_granularities: Option<Vec<TimestampGranularity>>,
+
+    /// Whether the transcription is streamed as server-sent events, set by [crate::Audio::transcribe_stream]. Not
+    /// supported by `whisper-1`.
+    pub stream: Option<bool>,
+
+    /// Additional information to include in the response, `logprobs` is only supported with the `json` response
+    /// format and the `gpt-4o-transcribe` and `gpt-4o-mini-transcribe` models.
+    pub include: Option<Vec<TranscriptionInclude>>,
+
+    /// How the audio is cut into chunks. Without it, the audio is transcribed as a single block.
+    pub chunking_strategy: Option<TranscriptionChunkingStrategy>,
+}
+
+/// The log probability of a token of a transcription.
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+pub struct TranscriptionLogprob {
+    /// The token of the transcription.
+    pub token: String,
+    /// The log probability of the token.
+    pub logprob: f32,
+    /// The UTF-8 bytes of the token.
+    pub bytes: Vec<u8>,
+}
+
+/// Details of the input tokens of [TranscriptionUsage::Tokens].
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+pub struct TranscriptionInputTokenDetails {
+    pub text_tokens: u32,
+    pub audio_tokens: u32,
+}
+
+/// Usage of a transcription request, billed by tokens or by the duration of the audio.
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TranscriptionUsage {
+    Tokens {
+        input_tokens: u32,
+        output_tokens: u32,
+        total_tokens: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        input_token_details: Option<TranscriptionInputTokenDetails>,
+    },
+    Duration {
+        /// The duration of the input audio in seconds.
+        seconds: f32,
+    },
 }
 
 /// Represents a transcription response returned by model, based on the provided
@@ -100,8 +192,51 @@ pub struct CreateTranscriptionRequest {
 pub struct CreateTranscriptionResponseJson {
     /// The transcribed text.
     pub text: String,
+
+    /// The log probabilities of the tokens of the text, if `logprobs` is included in the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Vec<TranscriptionLogprob>>,
+
+    /// Usage of the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TranscriptionUsage>,
+}
+
+/// An event of [crate::Audio::transcribe_stream].
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum TranscriptionStreamEvent {
+    /// New text of the transcription.
+    #[serde(rename = "transcript.text.delta")]
+    TextDelta {
+        delta: String,
+        /// The log probabilities of the tokens of the delta, if `logprobs` is included in the request.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        logprobs: Option<Vec<TranscriptionLogprob>>,
+    },
+    /// The transcription is complete.
+    #[serde(rename = "transcript.text.done")]
+    TextDone {
+        /// The whole text of the transcription.
+        text: String,
+        /// The log probabilities of the tokens of the text, if `logprobs` is included in the request.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        logprobs: Option<Vec<TranscriptionLogprob>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        usage: Option<TranscriptionUsage>,
+    },
 }
 
+/// The events of [crate::Audio::transcribe_stream] as they arrive, `Send` except on wasm32.
+#[cfg(not(target_arch = "wasm32"))]
+pub type TranscriptionResponseStream = std::pin::Pin<
+    Box<dyn futures::Stream<Item = Result<TranscriptionStreamEvent, OpenAIError>> + Send>,
+>;
+/// The events of [crate::Audio::transcribe_stream] as they arrive, `Send` except on wasm32.
+#[cfg(target_arch = "wasm32")]
+pub type TranscriptionResponseStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = Result<TranscriptionStreamEvent, OpenAIError>>>>;
+
 /// Represents a verbose json transcription response returned by model, based on
 /// the provided input.
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -195,6 +330,14 @@ pub struct CreateSpeechRequest {
     /// The speed of the generated audio. Select a value from 0.25 to 4.0. 1.0 is the default.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub speed: Option<f32>, // default: 1.0
+
+    /// Control the voice of your generated audio with additional instructions. Does not work with `tts-1` or `tts-1-hd`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    /// The format to stream the audio in, `audio` or `sse`. `sse` is not supported for `tts-1` or `tts-1-hd`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_format: Option<SpeechStreamFormat>,
 }
 
 #[derive(Clone, Default, Debug, Builder, PartialEq)]
@@ -242,3 +385,35 @@ pub struct CreateTranslationResponseVerboseJson {
 pub struct CreateSpeechResponse {
     pub bytes: Bytes,
 }
+
+/// Token usage of a speech request, reported by [SpeechStreamEvent::AudioDone].
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+pub struct SpeechUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// An event of a speech response with `stream_format` set to [SpeechStreamFormat::Sse].
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum SpeechStreamEvent {
+    /// A chunk of the audio, in the requested `response_format`.
+    #[serde(rename = "speech.audio.delta")]
+    AudioDelta {
+        /// The base64 encoded audio bytes.
+        audio: String,
+    },
+    /// The audio is complete.
+    #[serde(rename = "speech.audio.done")]
+    AudioDone { usage: SpeechUsage },
+}
+
+/// The audio bytes of [crate::Audio::speech_stream] as they arrive, `Send` except on wasm32.
+#[cfg(not(target_arch = "wasm32"))]
+pub type SpeechResponseStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = Result<Bytes, OpenAIError>> + Send>>;
+/// The audio bytes of [crate::Audio::speech_stream] as they arrive, `Send` except on wasm32.
+#[cfg(target_arch = "wasm32")]
+pub type SpeechResponseStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = Result<Bytes, OpenAIError>>>>;
@@ -68,6 +68,7 @@ pub enum ChatCompletionFunctionCall {
 #[serde(rename_all = "lowercase")]
 pub enum Role {
     System,
+    Developer,
     #[default]
     User,
     Assistant,
@@ -109,6 +110,20 @@ pub struct ChatCompletionRequestSystemMessage {
     pub name: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Builder, PartialEq)]
+#[builder(name = "ChatCompletionRequestDeveloperMessageArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ChatCompletionRequestDeveloperMessage {
+    /// The contents of the developer message. With reasoning models, developer messages replace system messages.
+    pub content: ChatCompletionRequestDeveloperMessageContent,
+    /// An optional name for the participant. Provides the model information to differentiate between participants of the same role.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, Builder, PartialEq)]
 #[builder(name = "ChatCompletionRequestMessageContentPartTextArgs")]
 #[builder(pattern = "mutable")]
@@ -172,6 +187,13 @@ pub enum ChatCompletionRequestSystemMessageContentPart {
     Text(ChatCompletionRequestMessageContentPartText),
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum ChatCompletionRequestDeveloperMessageContentPart {
+    Text(ChatCompletionRequestMessageContentPartText),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -196,6 +218,15 @@ pub enum ChatCompletionRequestSystemMessageContent {
     Array(Vec<ChatCompletionRequestSystemMessageContentPart>),
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ChatCompletionRequestDeveloperMessageContent {
+    /// The text contents of the developer message.
+    Text(String),
+    /// An array of content parts with a defined type. For developer messages, only type `text` is supported.
+    Array(Vec<ChatCompletionRequestDeveloperMessageContentPart>),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum ChatCompletionRequestUserMessageContent {
@@ -292,6 +323,7 @@ pub struct ChatCompletionRequestFunctionMessage {
 #[serde(rename_all = "lowercase")]
 pub enum ChatCompletionRequestMessage {
     System(ChatCompletionRequestSystemMessage),
+    Developer(ChatCompletionRequestDeveloperMessage),
     User(ChatCompletionRequestUserMessage),
     Assistant(ChatCompletionRequestAssistantMessage),
     Tool(ChatCompletionRequestToolMessage),
@@ -308,6 +340,27 @@ pub struct ChatCompletionMessageToolCall {
     pub function: FunctionCall,
 }
 
+/// A citation for a web resource used to generate a chat completion message.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct UrlCitation {
+    /// The index of the first character of the URL citation in the message.
+    pub start_index: u32,
+    /// The index of the character after the last character of the URL citation in the message.
+    pub end_index: u32,
+    /// The URL of the web resource.
+    pub url: String,
+    /// The title of the web resource.
+    pub title: String,
+}
+
+/// An annotation of a chat completion message, such as a citation of a web search result.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatCompletionResponseMessageAnnotation {
+    /// A URL citation when using web search.
+    UrlCitation { url_citation: UrlCitation },
+}
+
 /// A chat completion message generated by the model.
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ChatCompletionResponseMessage {
@@ -318,6 +371,10 @@ pub struct ChatCompletionResponseMessage {
     /// The tool calls generated by the model, such as function calls.
     pub tool_calls: Option<Vec<ChatCompletionMessageToolCall>>,
 
+    /// Annotations for the message, such as the URL citations of the web search tool.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<ChatCompletionResponseMessageAnnotation>>,
+
     /// The role of the author of this message.
     pub role: Role,
 
@@ -463,6 +520,80 @@ pub enum ServiceTierResponse {
     Default,
 }
 
+/// High level guidance for the amount of context window space to use for the search.
+#[derive(Clone, Serialize, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebSearchContextSize {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+/// The type of location approximation of [WebSearchUserLocation].
+#[derive(Clone, Serialize, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebSearchUserLocationType {
+    #[default]
+    Approximate,
+}
+
+/// Approximate location parameters for the search.
+#[derive(Clone, Serialize, Debug, Default, Deserialize, Builder, PartialEq)]
+#[builder(name = "WebSearchLocationArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct WebSearchLocation {
+    /// The two-letter [ISO country code](https://en.wikipedia.org/wiki/ISO_3166-1) of the user, e.g. `US`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Free text input for the region of the user, e.g. `California`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Free text input for the city of the user, e.g. `San Francisco`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    /// The [IANA timezone](https://timeapi.io/documentation/iana-timezones) of the user, e.g. `America/Los_Angeles`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Approximate location parameters for the search.
+#[derive(Clone, Serialize, Debug, Default, Deserialize, PartialEq)]
+pub struct WebSearchUserLocation {
+    /// The type of location approximation. Always `approximate`.
+    pub r#type: WebSearchUserLocationType,
+    /// The approximate location of the user.
+    pub approximate: WebSearchLocation,
+}
+
+impl From<WebSearchLocation> for WebSearchUserLocation {
+    fn from(approximate: WebSearchLocation) -> Self {
+        Self {
+            r#type: WebSearchUserLocationType::Approximate,
+            approximate,
+        }
+    }
+}
+
+/// Options for the web search tool of the search models.
+#[derive(Clone, Serialize, Debug, Default, Deserialize, Builder, PartialEq)]
+#[builder(name = "WebSearchOptionsArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct WebSearchOptions {
+    /// High level guidance for the amount of context window space to use for the search. One of `low`, `medium`, or `high`. `medium` is the default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_context_size: Option<WebSearchContextSize>,
+    /// Approximate location parameters for the search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_location: Option<WebSearchUserLocation>,
+}
+
 #[derive(Clone, Serialize, Default, Debug, Builder, Deserialize, PartialEq)]
 #[builder(name = "CreateChatCompletionRequestArgs")]
 #[builder(pattern = "mutable")]
@@ -503,9 +634,29 @@ pub struct CreateChatCompletionRequest {
     /// The maximum number of [tokens](https://platform.openai.com/tokenizer) that can be generated in the chat completion.
     ///
     /// The total length of input tokens and generated tokens is limited by the model's context length. [Example Python code](https://cookbook.openai.com/examples/how_to_count_tokens_with_tiktoken) for counting tokens.
+    ///
+    /// Replaced by `max_completion_tokens`, and not supported by reasoning models.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
 
+    /// An upper bound for the number of tokens that can be generated for a completion, including visible output tokens and [reasoning tokens](https://platform.openai.com/docs/guides/reasoning).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+
+    /// Constrains effort on reasoning for [reasoning models](https://platform.openai.com/docs/guides/reasoning).
+    /// Reducing reasoning effort can result in faster responses and fewer tokens used on reasoning in a response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Output types that you would like the model to generate for this request. Most models generate text, which is the default.
+    /// To generate audio with `gpt-4o-audio-preview`, request `["text", "audio"]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<ChatCompletionModalities>>,
+
+    /// Parameters for audio output. Required when audio output is requested with `modalities: ["audio"]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<ChatCompletionAudio>,
+
     /// How many chat completion choices to generate for each input message. Note that you will be charged based on the number of generated tokens across all of the choices. Keep `n` as `1` to minimize costs.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub n: Option<u8>, // min:1, max: 128, default: 1
@@ -586,6 +737,21 @@ pub struct CreateChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 
+    /// Whether or not to store the output of this chat completion request for use in model distillation or evals products.
+    ///
+    /// Stored completions can be listed and retrieved with [crate::Chat::list] and [crate::Chat::retrieve].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store: Option<bool>,
+
+    /// Developer-defined tags and values used for filtering completions in the dashboard and [crate::Chat::list].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, serde_json::Value>>,
+
+    /// This tool searches the web for relevant results to use in a response, for the search models such as `gpt-4o-search-preview`.
+    /// Learn more about the [web search tool](https://platform.openai.com/docs/guides/tools-web-search?api-mode=chat).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_search_options: Option<WebSearchOptions>,
+
     /// Deprecated in favor of `tool_choice`.
     ///
     /// Controls which (if any) function is called by the model.
@@ -606,6 +772,39 @@ pub struct CreateChatCompletionRequest {
     pub functions: Option<Vec<ChatCompletionFunctions>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatCompletionModalities {
+    Text,
+    Audio,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChatCompletionAudio {
+    /// The voice the model uses to respond, e.g. `alloy`, `ash`, `ballad`, `coral`, `echo`, `sage`, `shimmer` or `verse`.
+    pub voice: String,
+    /// The output audio format.
+    pub format: ChatCompletionAudioFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatCompletionAudioFormat {
+    Wav,
+    Mp3,
+    Flac,
+    Opus,
+    Pcm16,
+}
+
 /// Options for streaming response. Only set this when you set `stream: true`.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct ChatCompletionStreamOptions {
@@ -723,6 +922,9 @@ pub struct ChatCompletionStreamResponseDelta {
     pub function_call: Option<FunctionCallStream>,
 
     pub tool_calls: Option<Vec<ChatCompletionMessageToolCallChunk>>,
+    /// Annotations for the message, sent with the chunk completing the annotated content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<ChatCompletionResponseMessageAnnotation>>,
     /// The role of the author of this message.
     pub role: Option<Role>,
     /// The refusal message generated by the model.
@@ -763,3 +965,144 @@ pub struct CreateChatCompletionStreamResponse {
     /// When present, it contains a null value except for the last chunk which contains the token usage statistics for the entire request.
     pub usage: Option<CompletionUsage>,
 }
+
+/// Sort order of paginated list endpoints, by the `created` timestamp of the objects.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query parameters of [crate::Chat::list].
+///
+/// `metadata` filters are sent as `metadata[key]=value` pairs.
+#[derive(Clone, Default, Debug, Deserialize, Builder, PartialEq)]
+#[builder(name = "ListChatCompletionsQueryArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ListChatCompletionsQuery {
+    /// The model used to generate the chat completions.
+    pub model: Option<String>,
+    /// Only return chat completions with all of these metadata key-value pairs.
+    pub metadata: Option<HashMap<String, String>>,
+    /// Identifier for the last chat completion from the previous pagination request.
+    pub after: Option<String>,
+    /// Number of chat completions to retrieve. Defaults to 20.
+    pub limit: Option<u32>,
+    /// Sort order for chat completions by timestamp. Defaults to `asc`.
+    pub order: Option<ListOrder>,
+}
+
+impl Serialize for ListChatCompletionsQuery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(model) = &self.model {
+            map.serialize_entry("model", model)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            // sorted so that the query string is deterministic
+            let mut entries: Vec<_> = metadata.iter().collect();
+            entries.sort();
+            for (key, value) in entries {
+                map.serialize_entry(&format!("metadata[{key}]"), value)?;
+            }
+        }
+        if let Some(after) = &self.after {
+            map.serialize_entry("after", after)?;
+        }
+        if let Some(limit) = &self.limit {
+            map.serialize_entry("limit", limit)?;
+        }
+        if let Some(order) = &self.order {
+            map.serialize_entry("order", order)?;
+        }
+        map.end()
+    }
+}
+
+/// Query parameters of [crate::Chat::list_messages].
+#[derive(Clone, Serialize, Default, Debug, Deserialize, Builder, PartialEq)]
+#[builder(name = "ListChatCompletionMessagesQueryArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ListChatCompletionMessagesQuery {
+    /// Identifier for the last message from the previous pagination request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Number of messages to retrieve. Defaults to 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Sort order for messages by timestamp. Defaults to `asc`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<ListOrder>,
+}
+
+/// A list of stored chat completions, created with `store` set to `true`.
+#[derive(Clone, Serialize, Debug, Deserialize, PartialEq)]
+pub struct ListChatCompletionsResponse {
+    /// The object type, which is always `list`.
+    pub object: String,
+    pub data: Vec<CreateChatCompletionResponse>,
+    pub first_id: Option<String>,
+    pub last_id: Option<String>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Serialize, Default, Debug, Deserialize, Builder, PartialEq)]
+#[builder(name = "UpdateChatCompletionRequestArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct UpdateChatCompletionRequest {
+    /// Set of 16 key-value pairs that can be attached to the stored chat completion. This replaces the existing metadata.
+    pub metadata: Option<HashMap<String, serde_json::Value>>,
+}
+
+#[derive(Clone, Serialize, Default, Debug, Deserialize, PartialEq)]
+pub struct DeleteChatCompletionResponse {
+    pub id: String,
+    pub deleted: bool,
+    /// The object type, which is always `chat.completion.deleted`.
+    pub object: String,
+}
+
+/// A message of a stored chat completion, either from the request or the response.
+#[derive(Clone, Serialize, Debug, Deserialize, PartialEq)]
+pub struct ChatCompletionStoreMessage {
+    /// The identifier of the chat message.
+    pub id: String,
+    /// The role of the author of this message.
+    pub role: Role,
+    /// The text contents of the message.
+    pub content: Option<String>,
+    /// The name of the participant, if one was given in the request.
+    pub name: Option<String>,
+    /// The refusal message generated by the model.
+    pub refusal: Option<String>,
+    /// The tool calls generated by the model.
+    pub tool_calls: Option<Vec<ChatCompletionMessageToolCall>>,
+    /// The text and image parts of the message, if the request used an array of content parts.
+    pub content_parts: Option<Vec<ChatCompletionRequestUserMessageContentPart>>,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize, PartialEq)]
+pub struct ListChatCompletionMessagesResponse {
+    /// The object type, which is always `list`.
+    pub object: String,
+    pub data: Vec<ChatCompletionStoreMessage>,
+    pub first_id: Option<String>,
+    pub last_id: Option<String>,
+    pub has_more: bool,
+}
@@ -0,0 +1,113 @@
+//! Rendering of the URL citations returned by the web search models.
+use serde::{Deserialize, Serialize};
+
+use super::{ChatCompletionResponseMessage, ChatCompletionResponseMessageAnnotation, UrlCitation};
+
+/// Where the numbered markers of [ChatCompletionResponseMessage::render_citations] are placed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CitationStyle {
+    /// Insert the marker after the cited text, keeping the text itself.
+    #[default]
+    Append,
+    /// Replace the cited text, usually an inline markdown link, with the marker.
+    Replace,
+}
+
+/// A unique web resource cited in a rendered message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CitationSource {
+    /// The number of the `[n]` markers referring to this resource, starting at 1.
+    pub number: usize,
+    pub url: String,
+    pub title: String,
+}
+
+/// The content of a message with inline `[n]` citation markers, see [ChatCompletionResponseMessage::render_citations].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CitedContent {
+    pub text: String,
+    /// The cited resources in the order of their numbers.
+    pub sources: Vec<CitationSource>,
+}
+
+impl CitedContent {
+    /// A markdown list of the sources, one `[n] [title](url)` line each.
+    pub fn references(&self) -> String {
+        self.sources
+            .iter()
+            .map(|source| format!("[{}] [{}]({})\n", source.number, source.title, source.url))
+            .collect()
+    }
+}
+
+impl ChatCompletionResponseMessage {
+    /// The URL citations among the annotations of the message.
+    pub fn url_citations(&self) -> impl Iterator<Item = &UrlCitation> {
+        self.annotations
+            .iter()
+            .flatten()
+            .map(|annotation| match annotation {
+                ChatCompletionResponseMessageAnnotation::UrlCitation { url_citation } => {
+                    url_citation
+                }
+            })
+    }
+
+    /// The content of the message with a numbered `[n]` marker for every URL citation.
+    ///
+    /// Citation indices are character offsets into the content. Sources are numbered in order of appearance, and
+    /// citations of the same URL share a number. Citations outside of the content are ignored, as are citations
+    /// overlapping an earlier one with [CitationStyle::Replace].
+    pub fn render_citations(&self, style: CitationStyle) -> CitedContent {
+        let content = self.content.as_deref().unwrap_or_default();
+        let mut offsets: Vec<usize> = content.char_indices().map(|(offset, _)| offset).collect();
+        offsets.push(content.len());
+
+        let mut citations: Vec<&UrlCitation> = self
+            .url_citations()
+            .filter(|citation| {
+                citation.start_index <= citation.end_index
+                    && (citation.end_index as usize) < offsets.len()
+            })
+            .collect();
+        citations.sort_by_key(|citation| match style {
+            CitationStyle::Append => (citation.end_index, citation.start_index),
+            CitationStyle::Replace => (citation.start_index, citation.end_index),
+        });
+
+        let mut sources: Vec<CitationSource> = vec![];
+        let mut text = String::with_capacity(content.len());
+        let mut position = 0;
+        for citation in citations {
+            let start = offsets[citation.start_index as usize];
+            let end = offsets[citation.end_index as usize];
+            let at = match style {
+                CitationStyle::Append => end,
+                CitationStyle::Replace if start < position => continue,
+                CitationStyle::Replace => start,
+            };
+
+            let number = match sources.iter().find(|source| source.url == citation.url) {
+                Some(source) => source.number,
+                None => {
+                    sources.push(CitationSource {
+                        number: sources.len() + 1,
+                        url: citation.url.clone(),
+                        title: citation.title.clone(),
+                    });
+                    sources.len()
+                }
+            };
+
+            text.push_str(&content[position..at]);
+            text.push_str(&format!("[{number}]"));
+            position = match style {
+                CitationStyle::Append => at,
+                CitationStyle::Replace => end,
+            };
+        }
+        text.push_str(&content[position..]);
+
+        CitedContent { text, sources }
+    }
+}
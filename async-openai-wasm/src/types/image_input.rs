@@ -0,0 +1,331 @@
+use base64::engine::{general_purpose, Engine};
+use bytes::Bytes;
+
+use crate::error::OpenAIError;
+
+use super::{
+    CreateFileRequest, FileInput, FilePurpose, ImageDetail, ImageFile, ImageUrl, InputSource,
+    MessageContentImageFileObject, MessageContentImageUrlObject, MessageContentInput,
+};
+
+/// Image formats accepted as image inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageMimeType {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+}
+
+impl ImageMimeType {
+    /// Detects the format from the magic bytes at the start of an image file.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(Self::Webp)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// Format and size of an image, read from its header without decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageMetadata {
+    pub mime_type: ImageMimeType,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageMetadata {
+    /// Reads the format and size of a PNG, JPEG, GIF or WebP image.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OpenAIError> {
+        let mime_type = ImageMimeType::sniff(bytes).ok_or_else(|| {
+            OpenAIError::InvalidArgument(
+                "unsupported image format, expected PNG, JPEG, GIF or WebP".into(),
+            )
+        })?;
+        let (width, height) = match mime_type {
+            ImageMimeType::Png => png_size(bytes),
+            ImageMimeType::Jpeg => jpeg_size(bytes),
+            ImageMimeType::Gif => gif_size(bytes),
+            ImageMimeType::Webp => webp_size(bytes),
+        }
+        .ok_or_else(|| {
+            OpenAIError::InvalidArgument(format!(
+                "truncated or malformed {} image",
+                mime_type.as_str()
+            ))
+        })?;
+        Ok(Self {
+            mime_type,
+            width,
+            height,
+        })
+    }
+
+    /// Estimated number of prompt tokens of this image, see [ImageDetail::image_tokens].
+    pub fn tokens(&self, detail: &ImageDetail) -> usize {
+        detail.image_tokens(self.width, self.height)
+    }
+}
+
+fn be_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le_u24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+fn png_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    // the IHDR chunk always comes first
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+fn gif_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    Some((le_u16(bytes, 6)?, le_u16(bytes, 8)?))
+}
+
+fn jpeg_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        // markers may be padded with any number of 0xFF bytes
+        while *bytes.get(at)? == 0xFF && *bytes.get(at + 1)? == 0xFF {
+            at += 1;
+        }
+        if *bytes.get(at)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(at + 1)?;
+        match marker {
+            // start of frame, except DHT, JPG and DAC
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                return Some((be_u16(bytes, at + 7)?, be_u16(bytes, at + 5)?));
+            }
+            // markers without a length
+            0x01 | 0xD0..=0xD8 => at += 2,
+            _ => at += 2 + be_u16(bytes, at + 2)? as usize,
+        }
+    }
+}
+
+fn webp_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        b"VP8 " => {
+            if bytes.get(23..26)? != [0x9D, 0x01, 0x2A] {
+                return None;
+            }
+            Some((le_u16(bytes, 26)? & 0x3FFF, le_u16(bytes, 28)? & 0x3FFF))
+        }
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+        }
+        b"VP8X" => Some((le_u24(bytes, 24)? + 1, le_u24(bytes, 27)? + 1)),
+        _ => None,
+    }
+}
+
+impl ImageDetail {
+    /// The size an image of the given size is resized to by the API before it is tokenized.
+    ///
+    /// `low` detail images are scaled to fit into 512x512. Otherwise images are scaled to fit into 2048x2048,
+    /// then their shortest side is scaled to 768 pixels. Images are never scaled up.
+    pub fn scaled_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (mut w, mut h) = (width as f64, height as f64);
+        if width == 0 || height == 0 {
+            return (width, height);
+        }
+        let max_side = if matches!(self, ImageDetail::Low) {
+            512.0
+        } else {
+            2048.0
+        };
+        let scale = max_side / w.max(h);
+        if scale < 1.0 {
+            w *= scale;
+            h *= scale;
+        }
+        if !matches!(self, ImageDetail::Low) {
+            let scale = 768.0 / w.min(h);
+            if scale < 1.0 {
+                w *= scale;
+                h *= scale;
+            }
+        }
+        ((w.round() as u32).max(1), (h.round() as u32).max(1))
+    }
+
+    /// Number of tokens of an image input of the given size, as billed for `gpt-4o` and `gpt-4.1`.
+    ///
+    /// `low` detail images cost a fixed 85 tokens. Otherwise every 512x512 tile of the [ImageDetail::scaled_size]
+    /// costs 170 tokens on top of the base 85 tokens.
+    pub fn image_tokens(&self, width: u32, height: u32) -> usize {
+        const BASE_TOKENS: usize = 85;
+        const TILE_TOKENS: usize = 170;
+        if matches!(self, ImageDetail::Low) || width == 0 || height == 0 {
+            return BASE_TOKENS;
+        }
+
+        let (width, height) = self.scaled_size(width, height);
+        let tiles = ((width as usize + 511) / 512) * ((height as usize + 511) / 512);
+        BASE_TOKENS + TILE_TOKENS * tiles
+    }
+}
+
+fn source_bytes(source: &InputSource) -> &[u8] {
+    match source {
+        InputSource::Bytes { bytes, .. } => bytes,
+        InputSource::VecU8 { vec, .. } => vec,
+    }
+}
+
+impl ImageUrl {
+    /// A base64 data URL of a PNG, JPEG, GIF or WebP image, its MIME type is detected from the image bytes.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>, detail: ImageDetail) -> Result<Self, OpenAIError> {
+        let bytes = bytes.as_ref();
+        let metadata = ImageMetadata::from_bytes(bytes)?;
+        Ok(Self {
+            url: format!(
+                "data:{};base64,{}",
+                metadata.mime_type.as_str(),
+                general_purpose::STANDARD.encode(bytes)
+            ),
+            detail: Some(detail),
+        })
+    }
+
+    /// Same as [ImageUrl::from_bytes], from the contents of an [InputSource].
+    pub fn from_input_source(
+        source: &InputSource,
+        detail: ImageDetail,
+    ) -> Result<Self, OpenAIError> {
+        Self::from_bytes(source_bytes(source), detail)
+    }
+
+    /// Same as [ImageUrl::from_bytes], first downscaling the image to the size the API would resize it to
+    /// for the given detail, see [ImageDetail::scaled_size]. This only saves bandwidth, the token cost is the same.
+    ///
+    /// Images that are small enough are used as is. Downscaled JPEG images are re-encoded as JPEG, other formats as PNG.
+    #[cfg(feature = "image-resize")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "image-resize")))]
+    pub fn from_bytes_downscaled(
+        bytes: impl AsRef<[u8]>,
+        detail: ImageDetail,
+    ) -> Result<Self, OpenAIError> {
+        let bytes = bytes.as_ref();
+        let metadata = ImageMetadata::from_bytes(bytes)?;
+        let (width, height) = detail.scaled_size(metadata.width, metadata.height);
+        if (width, height) == (metadata.width, metadata.height) {
+            return Self::from_bytes(bytes, detail);
+        }
+
+        let to_error = |e: image::ImageError| OpenAIError::InvalidArgument(e.to_string());
+        let image = image::load_from_memory(bytes)
+            .map_err(to_error)?
+            .resize_exact(width, height, image::imageops::FilterType::Triangle);
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        if metadata.mime_type == ImageMimeType::Jpeg {
+            image
+                .into_rgb8()
+                .write_to(&mut encoded, image::ImageFormat::Jpeg)
+        } else {
+            image.write_to(&mut encoded, image::ImageFormat::Png)
+        }
+        .map_err(to_error)?;
+        Self::from_bytes(encoded.into_inner(), detail)
+    }
+
+    /// Format and size of the image of a base64 data URL. `None` for other URLs, which are fetched by the API.
+    pub fn metadata(&self) -> Option<ImageMetadata> {
+        let (_, data) = self.url.strip_prefix("data:")?.split_once(";base64,")?;
+        let bytes = general_purpose::STANDARD.decode(data).ok()?;
+        ImageMetadata::from_bytes(&bytes).ok()
+    }
+
+    /// Estimated number of prompt tokens of the image of a base64 data URL, see [ImageDetail::image_tokens].
+    /// `None` for other URLs.
+    pub fn estimated_tokens(&self) -> Option<usize> {
+        let metadata = self.metadata()?;
+        Some(metadata.tokens(&self.detail.clone().unwrap_or_default()))
+    }
+}
+
+impl MessageContentInput {
+    /// An image URL content part with a base64 data URL of the image, see [ImageUrl::from_bytes].
+    pub fn image_from_bytes(
+        bytes: impl AsRef<[u8]>,
+        detail: ImageDetail,
+    ) -> Result<Self, OpenAIError> {
+        Ok(Self::ImageUrl(MessageContentImageUrlObject {
+            image_url: ImageUrl::from_bytes(bytes, detail)?,
+        }))
+    }
+
+    /// An image file content part referencing an image uploaded with [CreateFileRequest::vision_image].
+    pub fn image_file(file_id: impl Into<String>, detail: ImageDetail) -> Self {
+        Self::ImageFile(MessageContentImageFileObject {
+            image_file: ImageFile {
+                file_id: file_id.into(),
+                detail: Some(detail),
+            },
+        })
+    }
+}
+
+impl CreateFileRequest {
+    /// A request to upload an image for use in Assistants messages with [FilePurpose::Vision].
+    ///
+    /// The file name extension is set from the format detected from the image bytes.
+    pub fn vision_image(bytes: impl Into<Bytes>) -> Result<Self, OpenAIError> {
+        let bytes = bytes.into();
+        let mime_type = ImageMimeType::sniff(&bytes).ok_or_else(|| {
+            OpenAIError::InvalidArgument(
+                "unsupported image format, expected PNG, JPEG, GIF or WebP".into(),
+            )
+        })?;
+        Ok(Self {
+            file: FileInput {
+                source: InputSource::Bytes {
+                    filename: format!("image.{}", mime_type.extension()),
+                    bytes,
+                },
+            },
+            purpose: FilePurpose::Vision,
+        })
+    }
+}
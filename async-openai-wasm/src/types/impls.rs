@@ -8,7 +8,7 @@ use crate::{
     util::create_file_part,
 };
 
-use super::{AudioInput, AudioResponseFormat, ChatCompletionFunctionCall, ChatCompletionFunctions, ChatCompletionNamedToolChoice, ChatCompletionRequestAssistantMessage, ChatCompletionRequestFunctionMessage, ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartText, ChatCompletionRequestMessageContentPartImage, ChatCompletionRequestSystemMessage, ChatCompletionRequestToolMessage, ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent, ChatCompletionToolChoiceOption, CreateFileRequest, CreateImageEditRequest, CreateImageVariationRequest, CreateMessageRequestContent, CreateTranscriptionRequest, CreateTranslationRequest, DallE2ImageSize, EmbeddingInput, FileInput, FilePurpose, FunctionName, ImageInput, ImageModel, ImageSize, ImageUrl, ModerationInput, Prompt, ImageResponseFormat, Role, Stop, TimestampGranularity, ChatCompletionRequestSystemMessageContent, ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestToolMessageContent, ChatCompletionRequestUserMessageContentPart};
+use super::{AudioInput, AudioResponseFormat, ChatCompletionFunctionCall, ChatCompletionFunctions, ChatCompletionNamedToolChoice, ChatCompletionRequestAssistantMessage, ChatCompletionRequestFunctionMessage, ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartText, ChatCompletionRequestMessageContentPartImage, ChatCompletionRequestSystemMessage, ChatCompletionRequestToolMessage, ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent, ChatCompletionToolChoiceOption, CreateFileRequest, CreateImageEditRequest, CreateImageVariationRequest, CreateMessageRequestContent, CreateTranscriptionRequest, CreateTranslationRequest, DallE2ImageSize, EmbeddingInput, FileInput, FilePurpose, FunctionName, ImageInput, ImageModel, ImageSize, ImageUrl, ModerationInput, Prompt, ImageResponseFormat, Role, Stop, TimestampGranularity, ChatCompletionRequestSystemMessageContent, ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestToolMessageContent, ChatCompletionRequestUserMessageContentPart, ChatCompletionResponseMessage, ChatCompletionRequestDeveloperMessage, ChatCompletionRequestDeveloperMessageContent, ChatCompletionRequestDeveloperMessageContentPart, ChatCompletionRequestSystemMessageContentPart, TranscriptionChunkingStrategy, TranscriptionInclude};
 
 /// for `impl_from!(T, Enum)`, implements
 /// - `From<T>`
@@ -219,6 +219,18 @@ impl Display for TimestampGranularity {
     }
 }
 
+impl Display for TranscriptionInclude {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TranscriptionInclude::Logprobs => "logprobs",
+            }
+        )
+    }
+}
+
 impl Display for Role {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -227,6 +239,7 @@ impl Display for Role {
             match self {
                 Role::User => "user",
                 Role::System => "system",
+                Role::Developer => "developer",
                 Role::Assistant => "assistant",
                 Role::Function => "function",
                 Role::Tool => "tool",
@@ -463,6 +476,58 @@ impl From<ChatCompletionRequestSystemMessage> for ChatCompletionRequestMessage {
     }
 }
 
+impl From<ChatCompletionRequestDeveloperMessage> for ChatCompletionRequestMessage {
+    fn from(value: ChatCompletionRequestDeveloperMessage) -> Self {
+        Self::Developer(value)
+    }
+}
+
+impl From<ChatCompletionRequestSystemMessage> for ChatCompletionRequestDeveloperMessage {
+    fn from(value: ChatCompletionRequestSystemMessage) -> Self {
+        Self {
+            content: match value.content {
+                ChatCompletionRequestSystemMessageContent::Text(text) => {
+                    ChatCompletionRequestDeveloperMessageContent::Text(text)
+                }
+                ChatCompletionRequestSystemMessageContent::Array(parts) => {
+                    ChatCompletionRequestDeveloperMessageContent::Array(
+                        parts
+                            .into_iter()
+                            .map(|ChatCompletionRequestSystemMessageContentPart::Text(part)| {
+                                ChatCompletionRequestDeveloperMessageContentPart::Text(part)
+                            })
+                            .collect(),
+                    )
+                }
+            },
+            name: value.name,
+        }
+    }
+}
+
+impl From<ChatCompletionRequestDeveloperMessage> for ChatCompletionRequestSystemMessage {
+    fn from(value: ChatCompletionRequestDeveloperMessage) -> Self {
+        Self {
+            content: match value.content {
+                ChatCompletionRequestDeveloperMessageContent::Text(text) => {
+                    ChatCompletionRequestSystemMessageContent::Text(text)
+                }
+                ChatCompletionRequestDeveloperMessageContent::Array(parts) => {
+                    ChatCompletionRequestSystemMessageContent::Array(
+                        parts
+                            .into_iter()
+                            .map(|ChatCompletionRequestDeveloperMessageContentPart::Text(part)| {
+                                ChatCompletionRequestSystemMessageContentPart::Text(part)
+                            })
+                            .collect(),
+                    )
+                }
+            },
+            name: value.name,
+        }
+    }
+}
+
 impl From<ChatCompletionRequestAssistantMessage> for ChatCompletionRequestMessage {
     fn from(value: ChatCompletionRequestAssistantMessage) -> Self {
         Self::Assistant(value)
@@ -481,6 +546,41 @@ impl From<ChatCompletionRequestToolMessage> for ChatCompletionRequestMessage {
     }
 }
 
+impl ChatCompletionRequestMessage {
+    /// The role of the author of the message.
+    pub fn role(&self) -> Role {
+        match self {
+            Self::System(_) => Role::System,
+            Self::Developer(_) => Role::Developer,
+            Self::User(_) => Role::User,
+            Self::Assistant(_) => Role::Assistant,
+            Self::Tool(_) => Role::Tool,
+            Self::Function(_) => Role::Function,
+        }
+    }
+}
+
+impl From<ChatCompletionResponseMessage> for ChatCompletionRequestAssistantMessage {
+    #[allow(deprecated)]
+    fn from(value: ChatCompletionResponseMessage) -> Self {
+        Self {
+            content: value
+                .content
+                .map(ChatCompletionRequestAssistantMessageContent::Text),
+            refusal: value.refusal,
+            name: None,
+            tool_calls: value.tool_calls,
+            function_call: value.function_call,
+        }
+    }
+}
+
+impl From<ChatCompletionResponseMessage> for ChatCompletionRequestMessage {
+    fn from(value: ChatCompletionResponseMessage) -> Self {
+        Self::Assistant(value.into())
+    }
+}
+
 impl From<ChatCompletionRequestUserMessageContent> for ChatCompletionRequestUserMessage {
     fn from(value: ChatCompletionRequestUserMessageContent) -> Self {
         Self {
@@ -490,6 +590,15 @@ impl From<ChatCompletionRequestUserMessageContent> for ChatCompletionRequestUser
     }
 }
 
+impl From<ChatCompletionRequestDeveloperMessageContent> for ChatCompletionRequestDeveloperMessage {
+    fn from(value: ChatCompletionRequestDeveloperMessageContent) -> Self {
+        Self {
+            content: value,
+            name: None,
+        }
+    }
+}
+
 impl From<ChatCompletionRequestSystemMessageContent> for ChatCompletionRequestSystemMessage {
     fn from(value: ChatCompletionRequestSystemMessageContent) -> Self {
         Self {
@@ -532,6 +641,18 @@ impl From<String> for ChatCompletionRequestSystemMessageContent {
     }
 }
 
+impl From<&str> for ChatCompletionRequestDeveloperMessageContent {
+    fn from(value: &str) -> Self {
+        ChatCompletionRequestDeveloperMessageContent::Text(value.into())
+    }
+}
+
+impl From<String> for ChatCompletionRequestDeveloperMessageContent {
+    fn from(value: String) -> Self {
+        ChatCompletionRequestDeveloperMessageContent::Text(value)
+    }
+}
+
 impl From<&str> for ChatCompletionRequestAssistantMessageContent {
     fn from(value: &str) -> Self {
         ChatCompletionRequestAssistantMessageContent::Text(value.into())
@@ -580,6 +701,18 @@ impl From<String> for ChatCompletionRequestSystemMessage {
     }
 }
 
+impl From<&str> for ChatCompletionRequestDeveloperMessage {
+    fn from(value: &str) -> Self {
+        ChatCompletionRequestDeveloperMessageContent::Text(value.into()).into()
+    }
+}
+
+impl From<String> for ChatCompletionRequestDeveloperMessage {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
+
 impl From<&str> for ChatCompletionRequestAssistantMessage {
     fn from(value: &str) -> Self {
         ChatCompletionRequestAssistantMessageContent::Text(value.into()).into()
@@ -676,6 +809,12 @@ impl Default for ChatCompletionRequestSystemMessageContent {
     }
 }
 
+impl Default for ChatCompletionRequestDeveloperMessageContent {
+    fn default() -> Self {
+        ChatCompletionRequestDeveloperMessageContent::Text("".into())
+    }
+}
+
 impl Default for ChatCompletionRequestToolMessageContent {
     fn default() -> Self {
         ChatCompletionRequestToolMessageContent::Text("".into())
@@ -717,6 +856,41 @@ impl async_convert::TryFrom<CreateTranscriptionRequest> for reqwest::multipart::
             }
         }
 
+        if let Some(stream) = request.stream {
+            form = form.text("stream", stream.to_string());
+        }
+
+        if let Some(include) = request.include {
+            for include in include {
+                form = form.text("include[]", include.to_string());
+            }
+        }
+
+        match request.chunking_strategy {
+            None => {}
+            Some(TranscriptionChunkingStrategy::Auto) => {
+                form = form.text("chunking_strategy", "auto");
+            }
+            Some(TranscriptionChunkingStrategy::ServerVad(vad)) => {
+                form = form.text("chunking_strategy[type]", "server_vad");
+                if let Some(prefix_padding_ms) = vad.prefix_padding_ms {
+                    form = form.text(
+                        "chunking_strategy[prefix_padding_ms]",
+                        prefix_padding_ms.to_string(),
+                    );
+                }
+                if let Some(silence_duration_ms) = vad.silence_duration_ms {
+                    form = form.text(
+                        "chunking_strategy[silence_duration_ms]",
+                        silence_duration_ms.to_string(),
+                    );
+                }
+                if let Some(threshold) = vad.threshold {
+                    form = form.text("chunking_strategy[threshold]", threshold.to_string());
+                }
+            }
+        }
+
         Ok(form)
     }
 }
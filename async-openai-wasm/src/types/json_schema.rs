@@ -0,0 +1,296 @@
+//! Strict-mode JSON schemas derived from Rust types implementing [schemars::JsonSchema].
+//!
+//! [Structured Outputs](https://platform.openai.com/docs/guides/structured-outputs) only accepts a subset of JSON Schema
+//! when `strict` is `true`: every object must set `additionalProperties: false`, every property must be listed in
+//! `required` (optional fields are expressed as nullable instead), and a number of keywords are not supported at all.
+//! The helpers here rewrite the schema generated by `schemars` to satisfy those rules, and report constructs
+//! that cannot be expressed before any request is sent.
+use schemars::{generate::SchemaSettings, JsonSchema};
+use serde_json::{Map, Value};
+
+use crate::error::OpenAIError;
+
+use super::{FunctionObject, ResponseFormat, ResponseFormatJsonSchema};
+
+/// Keywords which are rejected by the API in strict mode and cannot be rewritten into supported ones.
+const UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "not",
+    "if",
+    "then",
+    "else",
+    "dependentRequired",
+    "dependentSchemas",
+    "patternProperties",
+    "propertyNames",
+    "unevaluatedProperties",
+    "unevaluatedItems",
+    "prefixItems",
+    "contains",
+    "minProperties",
+    "maxProperties",
+];
+
+/// Keywords which carry no meaning for the model and are dropped from the schema.
+const STRIPPED_KEYWORDS: &[&str] = &["$schema", "default", "examples", "readOnly", "writeOnly", "deprecated"];
+
+/// String formats supported in strict mode. Any other `format` (e.g. the `uint32` or `double` annotations
+/// emitted by `schemars` for numeric types) is dropped.
+const SUPPORTED_FORMATS: &[&str] = &[
+    "date-time", "time", "date", "duration", "email", "hostname", "ipv4", "ipv6", "uuid",
+];
+
+/// Generates a strict-mode compatible JSON schema for `T`.
+///
+/// Returns [OpenAIError::InvalidArgument] if the schema of `T` uses a construct that is not supported in strict mode,
+/// such as maps with arbitrary keys, tuples or a non-object root.
+pub fn strict_json_schema<T: JsonSchema + ?Sized>() -> Result<Value, OpenAIError> {
+    let schema = SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<T>();
+    to_strict_json_schema(schema.to_value())
+}
+
+/// Rewrites an arbitrary JSON schema into one that is accepted in strict mode.
+///
+/// - objects get `additionalProperties: false` and all of their properties marked as required,
+///   properties which were optional become nullable instead.
+/// - `oneOf` is rewritten to `anyOf`, and a single-element `allOf` is inlined.
+/// - sibling keywords of `$ref`s are dropped, as they are not supported.
+/// - annotations like `default` and unsupported `format`s are removed.
+pub fn to_strict_json_schema(mut schema: Value) -> Result<Value, OpenAIError> {
+    for key in ["$defs", "definitions"] {
+        if let Some(Value::Object(defs)) = schema.get_mut(key) {
+            for (name, def) in defs.iter_mut() {
+                make_strict(def, &format!("#/{key}/{name}"))?;
+            }
+        }
+    }
+
+    make_strict(&mut schema, "#")?;
+
+    let root_type = schema.get("type").and_then(Value::as_str);
+    if root_type != Some("object") {
+        return Err(unsupported("#", "the root schema must be an object"));
+    }
+
+    Ok(schema)
+}
+
+fn unsupported(path: &str, reason: &str) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!(
+        "schema is not supported in strict mode at {path}: {reason}"
+    ))
+}
+
+fn make_strict(schema: &mut Value, path: &str) -> Result<(), OpenAIError> {
+    let object = match schema {
+        Value::Object(object) => object,
+        Value::Bool(_) => {
+            return Err(unsupported(
+                path,
+                "schemas accepting any value (e.g. serde_json::Value) are not allowed",
+            ))
+        }
+        _ => return Err(unsupported(path, "expected a schema object")),
+    };
+
+    for keyword in UNSUPPORTED_KEYWORDS {
+        if object.contains_key(*keyword) {
+            return Err(unsupported(path, &format!("`{keyword}` is not supported")));
+        }
+    }
+    for keyword in STRIPPED_KEYWORDS {
+        object.remove(*keyword);
+    }
+    if let Some(format) = object.get("format").and_then(Value::as_str) {
+        if !SUPPORTED_FORMATS.contains(&format) {
+            object.remove("format");
+        }
+    }
+
+    // siblings of `$ref` are not supported, and inlining the referenced schema would not
+    // terminate for recursive types, so drop annotations like `description` instead
+    if object.contains_key("$ref") {
+        object.retain(|key, _| key == "$ref");
+        return Ok(());
+    }
+
+    if let Some(Value::Array(all_of)) = object.remove("allOf") {
+        match <[Value; 1]>::try_from(all_of) {
+            Ok([Value::Object(single)]) => {
+                for (key, value) in single {
+                    object.entry(key).or_insert(value);
+                }
+                return make_strict(schema, path);
+            }
+            _ => return Err(unsupported(path, "`allOf` with more than one schema is not supported")),
+        }
+    }
+
+    if let Some(one_of) = object.remove("oneOf") {
+        object.insert("anyOf".into(), one_of);
+    }
+
+    if let Some(Value::Array(variants)) = object.get_mut("anyOf") {
+        for (i, variant) in variants.iter_mut().enumerate() {
+            make_strict(variant, &format!("{path}/anyOf/{i}"))?;
+        }
+    }
+
+    if let Some(items) = object.get_mut("items") {
+        make_strict(items, &format!("{path}/items"))?;
+    }
+
+    let is_object = match object.get("type") {
+        Some(Value::String(t)) => t == "object",
+        Some(Value::Array(types)) => types.iter().any(|t| t == "object"),
+        _ => object.contains_key("properties"),
+    };
+    if is_object {
+        match object.get("additionalProperties") {
+            None | Some(Value::Bool(false)) => {}
+            Some(_) => {
+                return Err(unsupported(
+                    path,
+                    "objects with arbitrary keys (e.g. maps) are not supported",
+                ))
+            }
+        }
+        object.insert("additionalProperties".into(), Value::Bool(false));
+
+        let required: Vec<String> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(|r| r.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut all_properties = Vec::new();
+        if let Some(Value::Object(properties)) = object.get_mut("properties") {
+            for (name, property) in properties.iter_mut() {
+                make_strict(property, &format!("{path}/properties/{name}"))?;
+                if !required.contains(name) {
+                    make_nullable(property);
+                }
+                all_properties.push(Value::String(name.clone()));
+            }
+        } else {
+            object.insert("properties".into(), Value::Object(Map::new()));
+        }
+        object.insert("required".into(), Value::Array(all_properties));
+    }
+
+    Ok(())
+}
+
+/// Allows `null` for a schema, unless it already does.
+fn make_nullable(schema: &mut Value) {
+    let allows_null = |s: &Value| match s.get("type") {
+        Some(Value::String(t)) => t == "null",
+        Some(Value::Array(types)) => types.iter().any(|t| t == "null"),
+        _ => false,
+    };
+    if allows_null(schema) {
+        return;
+    }
+    if let Some(Value::Array(variants)) = schema.get("anyOf") {
+        if variants.iter().any(allows_null) {
+            return;
+        }
+    }
+
+    let Value::Object(object) = schema else {
+        return;
+    };
+    let is_enum = object.contains_key("enum") || object.contains_key("const");
+    match object.get_mut("type") {
+        Some(Value::String(t)) if !is_enum => {
+            let t = std::mem::take(t);
+            object.insert("type".into(), Value::Array(vec![t.into(), "null".into()]));
+        }
+        _ => {
+            let inner = std::mem::take(object);
+            object.insert(
+                "anyOf".into(),
+                Value::Array(vec![
+                    Value::Object(inner),
+                    serde_json::json!({ "type": "null" }),
+                ]),
+            );
+        }
+    }
+}
+
+/// Turns a `schemars` schema name into a name accepted by the API: a-z, A-Z, 0-9, underscores and dashes,
+/// with a maximum length of 64.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+fn description_of(schema: &Value) -> Option<String> {
+    schema
+        .get("description")
+        .and_then(Value::as_str)
+        .map(String::from)
+}
+
+impl FunctionObject {
+    /// Creates a strict function definition whose `parameters` are derived from the type of its arguments.
+    ///
+    /// The function name is the name of the type and the description is taken from its doc comment.
+    /// Use [FunctionObject::from_type_with_name] to choose a different name.
+    pub fn from_type<T: JsonSchema + ?Sized>() -> Result<Self, OpenAIError> {
+        Self::from_type_with_name::<T>(sanitize_name(&T::schema_name()))
+    }
+
+    /// Same as [FunctionObject::from_type] with the given function name.
+    pub fn from_type_with_name<T: JsonSchema + ?Sized>(
+        name: impl Into<String>,
+    ) -> Result<Self, OpenAIError> {
+        let parameters = strict_json_schema::<T>()?;
+        Ok(Self {
+            name: name.into(),
+            description: description_of(&parameters),
+            parameters: Some(parameters),
+            strict: Some(true),
+        })
+    }
+}
+
+impl ResponseFormatJsonSchema {
+    /// Creates a strict response format schema from the type the response is parsed into.
+    ///
+    /// The name is the name of the type and the description is taken from its doc comment.
+    pub fn from_type<T: JsonSchema + ?Sized>() -> Result<Self, OpenAIError> {
+        let schema = strict_json_schema::<T>()?;
+        Ok(Self {
+            description: description_of(&schema),
+            name: sanitize_name(&T::schema_name()),
+            schema: Some(schema),
+            strict: Some(true),
+        })
+    }
+}
+
+impl ResponseFormat {
+    /// Creates a [ResponseFormat::JsonSchema] with a strict schema derived from `T`,
+    /// see [ResponseFormatJsonSchema::from_type].
+    pub fn json_schema<T: JsonSchema + ?Sized>() -> Result<Self, OpenAIError> {
+        Ok(Self::JsonSchema {
+            json_schema: ResponseFormatJsonSchema::from_type::<T>()?,
+        })
+    }
+}
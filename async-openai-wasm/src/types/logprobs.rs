@@ -0,0 +1,132 @@
+//! Helpers to score and classify model output with `logprobs` and `top_logprobs`.
+use super::{ChatChoiceLogprobs, ChatCompletionTokenLogprob, TopLogprobs};
+
+impl TopLogprobs {
+    /// The linear probability of this token, `exp(logprob)`.
+    pub fn probability(&self) -> f64 {
+        f64::from(self.logprob).exp()
+    }
+}
+
+impl ChatCompletionTokenLogprob {
+    /// The linear probability of this token, `exp(logprob)`.
+    pub fn probability(&self) -> f64 {
+        f64::from(self.logprob).exp()
+    }
+
+    /// The `k` most likely tokens at this position, most likely first.
+    ///
+    /// At most `top_logprobs` tokens are available, as requested in [crate::types::CreateChatCompletionRequest].
+    pub fn top_k(&self, k: usize) -> Vec<&TopLogprobs> {
+        let mut alternatives: Vec<&TopLogprobs> = self.top_logprobs.iter().collect();
+        alternatives.sort_by(|a, b| b.logprob.total_cmp(&a.logprob));
+        alternatives.truncate(k);
+        alternatives
+    }
+}
+
+impl ChatChoiceLogprobs {
+    /// The log probabilities of the message content, or of the refusal when there is no content.
+    pub fn tokens(&self) -> &[ChatCompletionTokenLogprob] {
+        self.content
+            .as_deref()
+            .filter(|content| !content.is_empty())
+            .or(self.refusal.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The log-likelihood of the generated sequence, the sum of the token log probabilities.
+    pub fn log_likelihood(&self) -> f64 {
+        self.tokens()
+            .iter()
+            .map(|token| f64::from(token.logprob))
+            .sum()
+    }
+
+    /// The perplexity of the generated sequence, `exp(-log_likelihood / n)`. `None` for an empty sequence.
+    pub fn perplexity(&self) -> Option<f64> {
+        let n = self.tokens().len();
+        (n > 0).then(|| (-self.log_likelihood() / n as f64).exp())
+    }
+
+    /// Each generated token with its linear probability.
+    pub fn token_probabilities(&self) -> Vec<(&str, f64)> {
+        self.tokens()
+            .iter()
+            .map(|token| (token.token.as_str(), token.probability()))
+            .collect()
+    }
+
+    /// The `k` most likely tokens at each position of the sequence, see [ChatCompletionTokenLogprob::top_k].
+    pub fn top_k(&self, k: usize) -> Vec<Vec<&TopLogprobs>> {
+        self.tokens().iter().map(|token| token.top_k(k)).collect()
+    }
+
+    /// Probabilities of the given labels, normalized to sum to 1 and in the order of `labels`.
+    ///
+    /// Labels are matched case-insensitively against the `top_logprobs` of the first token, ignoring leading whitespace.
+    /// A label spanning multiple tokens is scored by following the generated tokens while they spell out the label.
+    /// The probability of a token shared by several labels, such as `no` for `no` and `none`, is counted once: the next
+    /// generated token splits it between the labels, and it is split evenly when there is no next generated token.
+    ///
+    /// Returns `None` if none of the labels is among the most likely tokens.
+    pub fn classify<S: AsRef<str>>(&self, labels: &[S]) -> Option<Vec<f64>> {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|label| label.as_ref().trim().to_lowercase())
+            .collect();
+        let remaining: Vec<&str> = labels.iter().map(String::as_str).collect();
+
+        let scores: Vec<f64> = remaining
+            .iter()
+            .map(|label| label_probability(self.tokens(), label, &remaining, true))
+            .collect();
+
+        let total: f64 = scores.iter().sum();
+        (total > 0.0).then(|| scores.iter().map(|score| score / total).collect())
+    }
+}
+
+/// Probability of `label` being generated from `tokens` onwards. `labels` are the remaining parts of all labels that share the path so far.
+fn label_probability(
+    tokens: &[ChatCompletionTokenLogprob],
+    label: &str,
+    labels: &[&str],
+    first: bool,
+) -> f64 {
+    let Some(position) = tokens.first() else {
+        return 0.0;
+    };
+
+    let mut probability = 0.0;
+    for alternative in &position.top_logprobs {
+        let token = alternative.token.to_lowercase();
+        let token = if first { token.trim_start() } else { &token };
+        let Some(rest) = label.strip_prefix(token).filter(|_| !token.is_empty()) else {
+            continue;
+        };
+
+        let siblings: Vec<&str> = labels
+            .iter()
+            .filter_map(|other| other.strip_prefix(token))
+            .filter(|other| !other.is_empty())
+            .collect();
+        let share = if alternative.token == position.token && tokens.len() > 1 {
+            // a label ending with this token keeps what the next token does not spend on continuing as another label
+            let next = &tokens[1..];
+            if rest.is_empty() {
+                1.0 - siblings
+                    .iter()
+                    .map(|sibling| label_probability(next, sibling, &siblings, false))
+                    .sum::<f64>()
+            } else {
+                label_probability(next, rest, &siblings, false)
+            }
+        } else {
+            let ends = labels.contains(&token);
+            1.0 / (siblings.len() + usize::from(ends)) as f64
+        };
+        probability += alternative.probability() * share.max(0.0);
+    }
+    probability
+}
@@ -8,19 +8,25 @@ pub use assistant_stream::*;
 pub use audio::*;
 pub use batch::*;
 pub use chat::*;
+pub use citations::*;
 pub use common::*;
 pub use completion::*;
 pub use embedding::*;
 pub use file::*;
 pub use fine_tuning::*;
 pub use image::*;
+pub use image_input::*;
+#[cfg(feature = "json-schema")]
+pub use json_schema::*;
 pub use message::*;
 pub use message_file::*;
 pub use model::*;
 pub use moderation::*;
+pub use parsed_chat::*;
 pub use run::*;
 pub use step::*;
 pub use thread::*;
+pub use validate::*;
 pub use vector_store::*;
 
 use crate::error::OpenAIError;
@@ -32,22 +38,30 @@ mod assistant_stream;
 mod audio;
 mod batch;
 mod chat;
+mod citations;
 mod common;
 mod completion;
 mod embedding;
 mod file;
 mod fine_tuning;
 mod image;
+mod image_input;
+#[cfg_attr(docsrs, doc(cfg(feature = "json-schema")))]
+#[cfg(feature = "json-schema")]
+mod json_schema;
+mod logprobs;
 mod message;
 mod message_file;
 mod model;
 mod moderation;
+mod parsed_chat;
 #[cfg_attr(docsrs, doc(cfg(feature = "realtime")))]
 #[cfg(feature = "realtime")]
 pub mod realtime;
 mod run;
 mod step;
 mod thread;
+mod validate;
 mod vector_store;
 
 mod impls;
@@ -0,0 +1,230 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::{map_deserialization_error, OpenAIError};
+
+use super::{
+    ChatChoiceLogprobs, ChatCompletionToolType, CompletionUsage, CreateChatCompletionRequest,
+    CreateChatCompletionResponse, FinishReason, FunctionCall, ResponseFormat, Role,
+    ServiceTierResponse,
+};
+
+/// The refusal message generated by the model instead of a response matching the requested schema.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ChatCompletionRefusal(pub String);
+
+impl std::fmt::Display for ChatCompletionRefusal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ChatCompletionRefusal {}
+
+/// A tool call generated by the model, with its arguments parsed.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ParsedChatCompletionMessageToolCall<F> {
+    /// The ID of the tool call.
+    pub id: String,
+    /// The type of the tool. Currently, only `function` is supported.
+    pub r#type: ChatCompletionToolType,
+    /// The function that the model called, with its raw JSON arguments.
+    pub function: FunctionCall,
+    /// The arguments of the function call parsed into `F`.
+    ///
+    /// This is `None` if the arguments could not be parsed and the function was not declared with `strict: true`,
+    /// for strict functions a parsing failure is an error instead.
+    pub parsed_arguments: Option<F>,
+}
+
+/// A chat completion message generated by the model, with its content parsed into `T`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ParsedChatCompletionMessage<T, F = serde_json::Value> {
+    /// The raw contents of the message.
+    pub content: Option<String>,
+    /// The contents of the message parsed into `T`.
+    /// This is `None` if the model refused, or no JSON response format was requested.
+    pub parsed: Option<T>,
+    /// The refusal message generated by the model.
+    pub refusal: Option<ChatCompletionRefusal>,
+    /// The tool calls generated by the model, with their arguments parsed.
+    pub tool_calls: Option<Vec<ParsedChatCompletionMessageToolCall<F>>>,
+    /// The role of the author of this message.
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ParsedChatChoice<T, F = serde_json::Value> {
+    /// The index of the choice in the list of choices.
+    pub index: u32,
+    pub message: ParsedChatCompletionMessage<T, F>,
+    /// The reason the model stopped generating tokens. Always `stop`, `tool_calls` or `function_call` (deprecated),
+    /// as responses which were cut off are reported as [OpenAIError::IncompleteResponse].
+    pub finish_reason: Option<FinishReason>,
+    /// Log probability information for the choice.
+    pub logprobs: Option<ChatChoiceLogprobs>,
+}
+
+/// A chat completion response with message contents and tool call arguments parsed into Rust types.
+///
+/// Created by [crate::Chat::create_parsed] or [crate::Chat::create_parsed_with_tools].
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ParsedChatCompletion<T, F = serde_json::Value> {
+    /// A unique identifier for the chat completion.
+    pub id: String,
+    /// A list of chat completion choices. Can be more than one if `n` is greater than 1.
+    pub choices: Vec<ParsedChatChoice<T, F>>,
+    /// The Unix timestamp (in seconds) of when the chat completion was created.
+    pub created: u32,
+    /// The model used for the chat completion.
+    pub model: String,
+    /// The service tier used for processing the request. This field is only included if the `service_tier` parameter is specified in the request.
+    pub service_tier: Option<ServiceTierResponse>,
+    /// This fingerprint represents the backend configuration that the model runs with.
+    pub system_fingerprint: Option<String>,
+    /// The object type, which is always `chat.completion`.
+    pub object: String,
+    pub usage: Option<CompletionUsage>,
+}
+
+/// Options of the request that decide how a response is parsed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Whether message contents are parsed, which is the case when the request set a JSON response format.
+    pub parse_content: bool,
+    /// Names of the functions declared with `strict: true`, parsing failures of their arguments are errors.
+    pub strict_functions: Vec<String>,
+}
+
+impl ParseOptions {
+    /// Derives the parse options from the request the response was generated for.
+    pub fn from_request(request: &CreateChatCompletionRequest) -> Self {
+        Self {
+            parse_content: matches!(
+                request.response_format,
+                Some(ResponseFormat::JsonSchema { .. } | ResponseFormat::JsonObject)
+            ),
+            strict_functions: request
+                .tools
+                .iter()
+                .flatten()
+                .filter(|tool| tool.function.strict == Some(true))
+                .map(|tool| tool.function.name.clone())
+                .collect(),
+        }
+    }
+}
+
+impl<T: DeserializeOwned> ParsedChatCompletion<T> {
+    /// Parses message contents into `T` and tool call arguments into [serde_json::Value].
+    pub fn parse(
+        response: CreateChatCompletionResponse,
+        options: &ParseOptions,
+    ) -> Result<Self, OpenAIError> {
+        Self::parse_with(response, options, |_, arguments| {
+            serde_json::from_str(arguments)
+        })
+    }
+}
+
+impl<T: DeserializeOwned, F: DeserializeOwned> ParsedChatCompletion<T, F> {
+    /// Parses message contents into `T` and tool call arguments into `F`.
+    ///
+    /// `F` is the set of registered tools, deserialized from `{"name": <function name>, "arguments": <arguments>}`.
+    /// The natural fit is an adjacently tagged enum with one variant per function:
+    ///
+    /// ```
+    /// #[derive(serde::Deserialize)]
+    /// #[serde(tag = "name", content = "arguments", rename_all = "snake_case")]
+    /// enum Tools {
+    ///     GetWeather { location: String },
+    ///     GetTime { timezone: String },
+    /// }
+    /// ```
+    pub fn parse_with_tools(
+        response: CreateChatCompletionResponse,
+        options: &ParseOptions,
+    ) -> Result<Self, OpenAIError> {
+        Self::parse_with(response, options, |name, arguments| {
+            let arguments: serde_json::Value = serde_json::from_str(arguments)?;
+            serde_json::from_value(serde_json::json!({ "name": name, "arguments": arguments }))
+        })
+    }
+}
+
+impl<T: DeserializeOwned, F> ParsedChatCompletion<T, F> {
+    fn parse_with(
+        response: CreateChatCompletionResponse,
+        options: &ParseOptions,
+        parse_arguments: impl Fn(&str, &str) -> Result<F, serde_json::Error>,
+    ) -> Result<Self, OpenAIError> {
+        let mut choices = Vec::with_capacity(response.choices.len());
+        for choice in response.choices {
+            if let Some(reason @ (FinishReason::Length | FinishReason::ContentFilter)) =
+                choice.finish_reason
+            {
+                return Err(OpenAIError::IncompleteResponse(reason));
+            }
+
+            let message = choice.message;
+            let parsed = match (&message.content, &message.refusal) {
+                (Some(content), None) if options.parse_content => Some(
+                    serde_json::from_str::<T>(content)
+                        .map_err(|e| map_deserialization_error(e, content.as_bytes()))?,
+                ),
+                _ => None,
+            };
+
+            let tool_calls = match message.tool_calls {
+                None => None,
+                Some(tool_calls) => {
+                    let mut parsed_calls = Vec::with_capacity(tool_calls.len());
+                    for tool_call in tool_calls {
+                        let function = tool_call.function;
+                        let parsed_arguments =
+                            match parse_arguments(&function.name, &function.arguments) {
+                                Ok(arguments) => Some(arguments),
+                                Err(e) if options.strict_functions.contains(&function.name) => {
+                                    return Err(map_deserialization_error(
+                                        e,
+                                        function.arguments.as_bytes(),
+                                    ))
+                                }
+                                Err(_) => None,
+                            };
+                        parsed_calls.push(ParsedChatCompletionMessageToolCall {
+                            id: tool_call.id,
+                            r#type: tool_call.r#type,
+                            function,
+                            parsed_arguments,
+                        });
+                    }
+                    Some(parsed_calls)
+                }
+            };
+
+            choices.push(ParsedChatChoice {
+                index: choice.index,
+                message: ParsedChatCompletionMessage {
+                    content: message.content,
+                    parsed,
+                    refusal: message.refusal.map(ChatCompletionRefusal),
+                    tool_calls,
+                    role: message.role,
+                },
+                finish_reason: choice.finish_reason,
+                logprobs: choice.logprobs,
+            });
+        }
+
+        Ok(Self {
+            id: response.id,
+            choices,
+            created: response.created,
+            model: response.model,
+            service_tier: response.service_tier,
+            system_fingerprint: response.system_fingerprint,
+            object: response.object,
+            usage: response.usage,
+        })
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+
+use crate::error::{InvalidArgumentDetail, OpenAIError};
+
+use super::{
+    AudioResponseFormat, BatchSize, ChatCompletionModalities, ChatCompletionToolChoiceOption,
+    CreateChatCompletionRequest, CreateCompletionRequest, CreateEmbeddingRequest,
+    CreateFineTuningJobRequest, CreateImageRequest, CreateRunRequest, CreateSpeechRequest,
+    CreateTranscriptionRequest, EmbeddingInput, ImageModel, ImageQuality, ImageSize,
+    LearningRateMultiplier, NEpochs, Prompt, SpeechModel, SpeechStreamFormat, Stop,
+    TruncationObjectType,
+};
+
+/// Client side validation of the documented constraints of a request, such as parameter ranges
+/// and parameters which are only valid in combination with others.
+///
+/// Validation is opt-in: call [Validate::validate] directly, or enable it for every request
+/// with [crate::Client::with_request_validation].
+pub trait Validate {
+    /// Every parameter violating a constraint, empty if the request is valid.
+    fn violations(&self) -> Vec<InvalidArgumentDetail>;
+
+    /// Fails with [OpenAIError::InvalidArguments] listing every violation.
+    fn validate(&self) -> Result<(), OpenAIError> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(OpenAIError::InvalidArguments(violations))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Violations(Vec<InvalidArgumentDetail>);
+
+impl Violations {
+    fn add(&mut self, param: &str, message: impl Into<String>) {
+        self.0.push(InvalidArgumentDetail {
+            param: param.into(),
+            message: message.into(),
+        });
+    }
+
+    fn check(&mut self, valid: bool, param: &str, message: impl Into<String>) {
+        if !valid {
+            self.add(param, message);
+        }
+    }
+
+    fn range<T: PartialOrd + std::fmt::Display + Copy>(
+        &mut self,
+        param: &str,
+        value: Option<T>,
+        min: T,
+        max: T,
+    ) {
+        if let Some(value) = value {
+            // written to also reject NaN
+            if !(value >= min && value <= max) {
+                self.add(
+                    param,
+                    format!("must be between {min} and {max}, got {value}"),
+                );
+            }
+        }
+    }
+
+    fn min<T: PartialOrd + std::fmt::Display + Copy>(
+        &mut self,
+        param: &str,
+        value: Option<T>,
+        min: T,
+    ) {
+        if let Some(value) = value {
+            if value.partial_cmp(&min).map_or(true, |order| order.is_lt()) {
+                self.add(param, format!("must be at least {min}, got {value}"));
+            }
+        }
+    }
+
+    fn max_chars(&mut self, param: &str, value: &str, max: usize) {
+        let length = value.chars().count();
+        if length > max {
+            self.add(
+                param,
+                format!("must be at most {max} characters, got {length}"),
+            );
+        }
+    }
+
+    fn stop(&mut self, stop: &Option<Stop>) {
+        if let Some(Stop::StringArray(sequences)) = stop {
+            self.check(
+                (1..=4).contains(&sequences.len()),
+                "stop",
+                format!("must contain 1 to 4 sequences, got {}", sequences.len()),
+            );
+        }
+    }
+
+    fn logit_bias(&mut self, logit_bias: &Option<HashMap<String, serde_json::Value>>) {
+        for (token, bias) in logit_bias.iter().flatten() {
+            let param = format!("logit_bias.{token}");
+            if token.parse::<u32>().is_err() {
+                self.add(&param, "keys must be token ids");
+            }
+            match bias.as_f64() {
+                Some(bias) => self.range(&param, Some(bias), -100.0, 100.0),
+                None => self.add(&param, "must be a number"),
+            }
+        }
+    }
+
+    fn function_name(&mut self, param: &str, name: &str) {
+        let valid = !name.is_empty()
+            && name.len() <= 64
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        self.check(
+            valid,
+            param,
+            "must be 1 to 64 characters of a-z, A-Z, 0-9, underscores and dashes",
+        );
+    }
+
+    fn metadata(&mut self, metadata: &Option<HashMap<String, serde_json::Value>>) {
+        let Some(metadata) = metadata else {
+            return;
+        };
+        self.check(
+            metadata.len() <= 16,
+            "metadata",
+            format!("must have at most 16 pairs, got {}", metadata.len()),
+        );
+        for (key, value) in metadata {
+            let param = format!("metadata.{key}");
+            self.max_chars(&param, key, 64);
+            match value.as_str() {
+                Some(value) => self.max_chars(&param, value, 512),
+                None => self.add(&param, "must be a string"),
+            }
+        }
+    }
+
+    fn into_vec(self) -> Vec<InvalidArgumentDetail> {
+        self.0
+    }
+}
+
+impl Validate for CreateChatCompletionRequest {
+    #[allow(deprecated)]
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(
+            !self.messages.is_empty(),
+            "messages",
+            "must contain at least one message",
+        );
+        v.check(!self.model.is_empty(), "model", "must not be empty");
+        v.range("frequency_penalty", self.frequency_penalty, -2.0, 2.0);
+        v.range("presence_penalty", self.presence_penalty, -2.0, 2.0);
+        v.range("temperature", self.temperature, 0.0, 2.0);
+        v.range("top_p", self.top_p, 0.0, 1.0);
+        v.range("n", self.n, 1, 128);
+        v.range("top_logprobs", self.top_logprobs, 0, 20);
+        v.check(
+            self.top_logprobs.is_none() || self.logprobs == Some(true),
+            "top_logprobs",
+            "requires `logprobs` to be true",
+        );
+        v.min("max_tokens", self.max_tokens, 1);
+        v.min("max_completion_tokens", self.max_completion_tokens, 1);
+        v.logit_bias(&self.logit_bias);
+        v.stop(&self.stop);
+        v.check(
+            self.stream_options.is_none() || self.stream == Some(true),
+            "stream_options",
+            "requires `stream` to be true",
+        );
+        let audio_output = self
+            .modalities
+            .iter()
+            .flatten()
+            .any(|modality| *modality == ChatCompletionModalities::Audio);
+        v.check(
+            !audio_output || self.audio.is_some(),
+            "audio",
+            "is required when `modalities` contains audio",
+        );
+
+        let tools = self.tools.as_deref().unwrap_or_default();
+        v.check(
+            tools.len() <= 128,
+            "tools",
+            format!("must contain at most 128 tools, got {}", tools.len()),
+        );
+        for (index, tool) in tools.iter().enumerate() {
+            v.function_name(
+                &format!("tools[{index}].function.name"),
+                &tool.function.name,
+            );
+        }
+        v.check(
+            !tools.is_empty()
+                || !matches!(
+                    self.tool_choice,
+                    Some(ChatCompletionToolChoiceOption::Required)
+                        | Some(ChatCompletionToolChoiceOption::Named(_))
+                ),
+            "tool_choice",
+            "requires `tools`",
+        );
+        v.check(
+            !tools.is_empty() || self.parallel_tool_calls.is_none(),
+            "parallel_tool_calls",
+            "requires `tools`",
+        );
+
+        let functions = self.functions.as_deref().unwrap_or_default();
+        v.check(
+            functions.len() <= 128,
+            "functions",
+            format!(
+                "must contain at most 128 functions, got {}",
+                functions.len()
+            ),
+        );
+        for (index, function) in functions.iter().enumerate() {
+            v.function_name(&format!("functions[{index}].name"), &function.name);
+        }
+        v.check(
+            !functions.is_empty() || self.function_call.is_none(),
+            "function_call",
+            "requires `functions`",
+        );
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateCompletionRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(!self.model.is_empty(), "model", "must not be empty");
+        let empty_prompt = match &self.prompt {
+            Prompt::StringArray(prompts) => prompts.is_empty(),
+            Prompt::IntegerArray(tokens) => tokens.is_empty(),
+            Prompt::ArrayOfIntegerArray(prompts) => prompts.is_empty(),
+            Prompt::String(_) => false,
+        };
+        v.check(!empty_prompt, "prompt", "must not be an empty array");
+        v.range("frequency_penalty", self.frequency_penalty, -2.0, 2.0);
+        v.range("presence_penalty", self.presence_penalty, -2.0, 2.0);
+        v.range("temperature", self.temperature, 0.0, 2.0);
+        v.range("top_p", self.top_p, 0.0, 1.0);
+        v.range("n", self.n, 1, 128);
+        v.range("logprobs", self.logprobs, 0, 5);
+        v.range("best_of", self.best_of, 0, 20);
+        if let Some(best_of) = self.best_of {
+            v.check(
+                best_of >= self.n.unwrap_or(1),
+                "best_of",
+                "must be greater than or equal to `n`",
+            );
+            v.check(
+                self.stream != Some(true),
+                "best_of",
+                "cannot be used with `stream`",
+            );
+        }
+        v.min("max_tokens", self.max_tokens, 1);
+        v.logit_bias(&self.logit_bias);
+        v.stop(&self.stop);
+        v.check(
+            self.stream_options.is_none() || self.stream == Some(true),
+            "stream_options",
+            "requires `stream` to be true",
+        );
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateEmbeddingRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(!self.model.is_empty(), "model", "must not be empty");
+        let inputs = match &self.input {
+            EmbeddingInput::String(input) => {
+                v.check(!input.is_empty(), "input", "must not be empty");
+                1
+            }
+            EmbeddingInput::StringArray(inputs) => {
+                for (index, input) in inputs.iter().enumerate() {
+                    v.check(
+                        !input.is_empty(),
+                        &format!("input[{index}]"),
+                        "must not be empty",
+                    );
+                }
+                inputs.len()
+            }
+            EmbeddingInput::IntegerArray(tokens) => {
+                v.check(!tokens.is_empty(), "input", "must not be empty");
+                1
+            }
+            EmbeddingInput::ArrayOfIntegerArray(inputs) => {
+                for (index, tokens) in inputs.iter().enumerate() {
+                    v.check(
+                        !tokens.is_empty(),
+                        &format!("input[{index}]"),
+                        "must not be empty",
+                    );
+                }
+                inputs.len()
+            }
+        };
+        v.range("input", Some(inputs), 1, 2048);
+        v.min("dimensions", self.dimensions, 1);
+        v.check(
+            self.dimensions.is_none() || !self.model.starts_with("text-embedding-ada-002"),
+            "dimensions",
+            "is only supported by `text-embedding-3` and later models",
+        );
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateImageRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        let model = self.model.clone().unwrap_or_default();
+        v.check(!self.prompt.is_empty(), "prompt", "must not be empty");
+        v.range("n", self.n, 1, 10);
+        match model {
+            ImageModel::DallE2 => {
+                v.max_chars("prompt", &self.prompt, 1000);
+                v.check(
+                    !matches!(
+                        self.size,
+                        Some(ImageSize::S1792x1024 | ImageSize::S1024x1792)
+                    ),
+                    "size",
+                    "1792x1024 and 1024x1792 are only supported by dall-e-3",
+                );
+                v.check(
+                    !matches!(self.quality, Some(ImageQuality::HD)),
+                    "quality",
+                    "hd is only supported by dall-e-3",
+                );
+                v.check(
+                    self.style.is_none(),
+                    "style",
+                    "is only supported by dall-e-3",
+                );
+            }
+            ImageModel::DallE3 => {
+                v.max_chars("prompt", &self.prompt, 4000);
+                v.check(
+                    self.n.unwrap_or(1) == 1,
+                    "n",
+                    "dall-e-3 only supports generating one image",
+                );
+                v.check(
+                    !matches!(self.size, Some(ImageSize::S256x256 | ImageSize::S512x512)),
+                    "size",
+                    "256x256 and 512x512 are only supported by dall-e-2",
+                );
+            }
+            ImageModel::Other(_) => {}
+        }
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateSpeechRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(!self.input.is_empty(), "input", "must not be empty");
+        v.max_chars("input", &self.input, 4096);
+        v.range("speed", self.speed, 0.25, 4.0);
+        let tts1 = matches!(self.model, SpeechModel::Tts1 | SpeechModel::Tts1Hd);
+        v.check(
+            !(tts1 && self.instructions.is_some()),
+            "instructions",
+            "not supported by tts-1 and tts-1-hd",
+        );
+        v.check(
+            !(tts1 && self.stream_format == Some(SpeechStreamFormat::Sse)),
+            "stream_format",
+            "sse is not supported by tts-1 and tts-1-hd",
+        );
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateTranscriptionRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(!self.model.is_empty(), "model", "must not be empty");
+        v.range("temperature", self.temperature, 0.0, 1.0);
+        v.check(
+            self.timestamp_granularities.is_none()
+                || self.response_format == Some(AudioResponseFormat::VerboseJson),
+            "timestamp_granularities",
+            "requires `response_format` to be verbose_json",
+        );
+        let whisper = self.model == "whisper-1";
+        v.check(
+            !(whisper && self.stream == Some(true)),
+            "stream",
+            "not supported by whisper-1",
+        );
+        v.check(
+            self.include.as_ref().map_or(true, Vec::is_empty)
+                || (!whisper
+                    && matches!(self.response_format, None | Some(AudioResponseFormat::Json))),
+            "include",
+            "requires `response_format` to be json and is not supported by whisper-1",
+        );
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateFineTuningJobRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(!self.model.is_empty(), "model", "must not be empty");
+        v.check(
+            !self.training_file.is_empty(),
+            "training_file",
+            "must not be empty",
+        );
+        if let Some(suffix) = &self.suffix {
+            v.range("suffix", Some(suffix.chars().count()), 1, 40);
+        }
+        v.range("seed", self.seed, 0, 2147483647);
+        if let Some(hyperparameters) = &self.hyperparameters {
+            if let NEpochs::NEpochs(n_epochs) = hyperparameters.n_epochs {
+                v.range("hyperparameters.n_epochs", Some(n_epochs), 1, 50);
+            }
+            if let BatchSize::BatchSize(batch_size) = hyperparameters.batch_size {
+                v.range("hyperparameters.batch_size", Some(batch_size), 1, 256);
+            }
+            if let LearningRateMultiplier::LearningRateMultiplier(multiplier) =
+                hyperparameters.learning_rate_multiplier
+            {
+                v.check(
+                    multiplier > 0.0,
+                    "hyperparameters.learning_rate_multiplier",
+                    format!("must be greater than 0, got {multiplier}"),
+                );
+            }
+        }
+        if let Some(integrations) = &self.integrations {
+            v.check(
+                integrations.len() <= 5,
+                "integrations",
+                format!(
+                    "must contain at most 5 integrations, got {}",
+                    integrations.len()
+                ),
+            );
+        }
+        v.into_vec()
+    }
+}
+
+impl Validate for CreateRunRequest {
+    fn violations(&self) -> Vec<InvalidArgumentDetail> {
+        let mut v = Violations::default();
+        v.check(
+            !self.assistant_id.is_empty(),
+            "assistant_id",
+            "must not be empty",
+        );
+        v.range("temperature", self.temperature, 0.0, 2.0);
+        v.range("top_p", self.top_p, 0.0, 1.0);
+        v.min("max_prompt_tokens", self.max_prompt_tokens, 256);
+        v.min("max_completion_tokens", self.max_completion_tokens, 256);
+        if let Some(truncation_strategy) = &self.truncation_strategy {
+            match truncation_strategy.r#type {
+                TruncationObjectType::Auto => v.check(
+                    truncation_strategy.last_messages.is_none(),
+                    "truncation_strategy.last_messages",
+                    "requires `type` to be last_messages",
+                ),
+                TruncationObjectType::LastMessages => {
+                    v.check(
+                        truncation_strategy.last_messages.is_some(),
+                        "truncation_strategy.last_messages",
+                        "is required when `type` is last_messages",
+                    );
+                    v.min(
+                        "truncation_strategy.last_messages",
+                        truncation_strategy.last_messages,
+                        1,
+                    );
+                }
+            }
+        }
+        v.metadata(&self.metadata);
+        v.into_vec()
+    }
+}
@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use futures_timer::Delay;
 use reqwest::Body;
 
 use crate::error::OpenAIError;
@@ -24,3 +27,91 @@ pub(crate) async fn create_file_part(
     Ok(file_part)
 }
 
+
+/// Concurrency and retry policy of the requests of [crate::Audio::speech_long], [crate::Audio::transcribe_long]
+/// and [crate::Embeddings::create_many].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRequestOptions {
+    /// The maximum number of requests in flight, 4 by default.
+    pub concurrency: usize,
+    /// How often a request is retried after a rate limit, server or connection error, 3 times by default.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for every following retry. 1 second by default.
+    pub retry_delay: Duration,
+}
+
+impl Default for BatchRequestOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            max_retries: 3,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl BatchRequestOptions {
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Runs `request`, retrying errors that may succeed later according to the options.
+    pub(crate) async fn retry<T, F, Fut>(&self, request: F) -> Result<T, OpenAIError>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T, OpenAIError>>,
+    {
+        retry(self.max_retries, self.retry_delay, request).await
+    }
+}
+
+/// Runs `request`, retrying errors that may succeed later up to `max_retries` times, with a delay doubling from
+/// `delay` between attempts.
+pub(crate) async fn retry<T, F, Fut>(
+    max_retries: u32,
+    mut delay: Duration,
+    request: F,
+) -> Result<T, OpenAIError>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<T, OpenAIError>>,
+{
+    let mut retries = 0;
+    loop {
+        match request().await {
+            Err(e) if retries < max_retries && is_retryable(&e) => {
+                tracing::warn!("Retrying request after error: {e}");
+                Delay::new(delay).await;
+                delay *= 2;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Rate limits, server errors and connection errors, but not exceeded quotas or invalid requests.
+fn is_retryable(error: &OpenAIError) -> bool {
+    match error {
+        OpenAIError::Reqwest(_) => true,
+        OpenAIError::ApiError(error) => {
+            error.code.as_deref() == Some("rate_limit_exceeded")
+                || matches!(
+                    error.r#type.as_deref(),
+                    Some("server_error" | "requests" | "tokens")
+                )
+        }
+        _ => false,
+    }
+}
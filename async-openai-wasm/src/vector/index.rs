@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::VectorExt;
+use crate::error::OpenAIError;
+use crate::types::CreateEmbeddingResponse;
+
+const MAGIC: &[u8; 4] = b"OAVI";
+const VERSION: u8 = 1;
+
+/// A match of [VectorIndex::search], ordered by descending score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit<'a> {
+    pub id: &'a str,
+    /// The cosine similarity of the query and the stored vector.
+    pub score: f32,
+    pub metadata: &'a Value,
+}
+
+/// A brute-force in-memory vector index with cosine similarity search.
+///
+/// Vectors are stored normalized in one contiguous buffer, so every search is a scan of dot products without any
+/// runtime or platform specific dependency. This is fast enough for tens of thousands of vectors, such as the
+/// chunks of a few documents searched in the browser.
+///
+/// All vectors of an index have the dimensions of the first one added. Metadata can be any JSON value, and is
+/// usually an object used by [VectorIndex::search_filtered] and to find the original text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VectorIndex {
+    dimensions: usize,
+    ids: Vec<String>,
+    metadata: Vec<Value>,
+    vectors: Vec<f32>,
+    positions: HashMap<String, usize>,
+}
+
+impl VectorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The dimensions of the vectors in the index, 0 while it is empty.
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Adds a vector, replacing the vector and metadata of an existing entry with the same id.
+    ///
+    /// Fails if the vector is empty or its dimensions differ from those of the index.
+    pub fn add(
+        &mut self,
+        id: impl Into<String>,
+        vector: impl AsRef<[f32]>,
+        metadata: Value,
+    ) -> Result<(), OpenAIError> {
+        let vector = vector.as_ref();
+        if vector.is_empty() {
+            return Err(OpenAIError::InvalidArgument(
+                "vector must not be empty".into(),
+            ));
+        }
+        if self.is_empty() {
+            self.dimensions = vector.len();
+        } else if vector.len() != self.dimensions {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "vector has {} dimensions, the index has {}",
+                vector.len(),
+                self.dimensions
+            )));
+        }
+
+        let id = id.into();
+        let vector = vector.normalized();
+        match self.positions.get(&id) {
+            Some(&position) => {
+                self.row_mut(position).copy_from_slice(&vector);
+                self.metadata[position] = metadata;
+            }
+            None => {
+                self.positions.insert(id.clone(), self.ids.len());
+                self.ids.push(id);
+                self.metadata.push(metadata);
+                self.vectors.extend_from_slice(&vector);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the embeddings of a response, with the id and metadata of the input at the same index.
+    ///
+    /// Embeddings without a matching entry in `inputs` are ignored.
+    pub fn add_response<I, S>(
+        &mut self,
+        response: CreateEmbeddingResponse,
+        inputs: I,
+    ) -> Result<(), OpenAIError>
+    where
+        I: IntoIterator<Item = (S, Value)>,
+        S: Into<String>,
+    {
+        let mut inputs: Vec<Option<(S, Value)>> = inputs.into_iter().map(Some).collect();
+        for embedding in response.data {
+            let input = inputs
+                .get_mut(embedding.index as usize)
+                .and_then(Option::take);
+            if let Some((id, metadata)) = input {
+                self.add(id, embedding.embedding, metadata)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes an entry, returning its metadata.
+    pub fn remove(&mut self, id: &str) -> Option<Value> {
+        let position = self.positions.remove(id)?;
+        let last = self.ids.len() - 1;
+        if position != last {
+            let (head, tail) = self.vectors.split_at_mut(last * self.dimensions);
+            head[position * self.dimensions..][..self.dimensions].copy_from_slice(tail);
+            self.positions.insert(self.ids[last].clone(), position);
+        }
+        self.vectors.truncate(last * self.dimensions);
+        self.ids.swap_remove(position);
+        let metadata = self.metadata.swap_remove(position);
+        if self.is_empty() {
+            self.dimensions = 0;
+        }
+        Some(metadata)
+    }
+
+    /// The normalized vector and the metadata of an entry.
+    pub fn get(&self, id: &str) -> Option<(&[f32], &Value)> {
+        let position = *self.positions.get(id)?;
+        Some((self.row(position), &self.metadata[position]))
+    }
+
+    /// The ids of all entries, in no particular order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.iter().map(String::as_str)
+    }
+
+    /// The `k` entries most similar to the query.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<SearchHit<'_>> {
+        self.search_filtered(query, k, |_| true)
+    }
+
+    /// The `k` entries most similar to the query among those whose metadata matches `filter`.
+    ///
+    /// A query with other dimensions than the index matches nothing.
+    pub fn search_filtered(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter: impl Fn(&Value) -> bool,
+    ) -> Vec<SearchHit<'_>> {
+        if query.len() != self.dimensions || k == 0 {
+            return vec![];
+        }
+        let query = query.normalized();
+
+        let mut hits: Vec<SearchHit> = (0..self.len())
+            .filter(|&position| filter(&self.metadata[position]))
+            .map(|position| SearchHit {
+                id: &self.ids[position],
+                score: self.row(position).dot(&query),
+                metadata: &self.metadata[position],
+            })
+            .collect();
+
+        let by_score = |a: &SearchHit, b: &SearchHit| b.score.total_cmp(&a.score);
+        if hits.len() > k {
+            hits.select_nth_unstable_by(k - 1, by_score);
+            hits.truncate(k);
+        }
+        hits.sort_by(by_score);
+        hits
+    }
+
+    /// Serializes the index into a compact binary format, read by [VectorIndex::from_bytes].
+    ///
+    /// Vectors are stored as little-endian `f32`, metadata as JSON.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13 + self.vectors.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.dimensions as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        for (id, metadata) in self.ids.iter().zip(&self.metadata) {
+            let metadata = metadata.to_string();
+            for field in [id.as_bytes(), metadata.as_bytes()] {
+                bytes.extend_from_slice(&(field.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(field);
+            }
+        }
+        for value in &self.vectors {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Reads an index serialized with [VectorIndex::to_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OpenAIError> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not a vector index"));
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported version {version}")));
+        }
+        let dimensions = reader.u32()? as usize;
+        let len = reader.u32()? as usize;
+
+        let mut index = Self {
+            dimensions,
+            ..Self::default()
+        };
+        for position in 0..len {
+            let id =
+                String::from_utf8(reader.field()?.to_vec()).map_err(|e| invalid(&e.to_string()))?;
+            let metadata =
+                serde_json::from_slice(reader.field()?).map_err(|e| invalid(&e.to_string()))?;
+            index.positions.insert(id.clone(), position);
+            index.ids.push(id);
+            index.metadata.push(metadata);
+        }
+        let size = len
+            .checked_mul(dimensions)
+            .and_then(|values| values.checked_mul(4))
+            .ok_or_else(|| invalid("too many vectors"))?;
+        let vectors = reader.take(size)?;
+        index.vectors = vectors
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        if !reader.0.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+        if index.positions.len() != len {
+            return Err(invalid("duplicate ids"));
+        }
+        Ok(index)
+    }
+
+    fn row(&self, position: usize) -> &[f32] {
+        &self.vectors[position * self.dimensions..][..self.dimensions]
+    }
+
+    fn row_mut(&mut self, position: usize) -> &mut [f32] {
+        &mut self.vectors[position * self.dimensions..][..self.dimensions]
+    }
+}
+
+impl TryFrom<CreateEmbeddingResponse> for VectorIndex {
+    type Error = OpenAIError;
+
+    /// An index of the embeddings of a response, with the index of each input as its id and no metadata.
+    fn try_from(response: CreateEmbeddingResponse) -> Result<Self, Self::Error> {
+        let mut index = Self::new();
+        for embedding in response.data {
+            index.add(
+                embedding.index.to_string(),
+                embedding.embedding,
+                Value::Null,
+            )?;
+        }
+        Ok(index)
+    }
+}
+
+fn invalid(message: &str) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!("invalid vector index: {message}"))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], OpenAIError> {
+        if self.0.len() < n {
+            return Err(invalid("unexpected end of data"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, OpenAIError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], OpenAIError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
@@ -0,0 +1,410 @@
+use base64::engine::{general_purpose, Engine};
+use half::f16;
+
+use crate::error::OpenAIError;
+use crate::types::{Base64EmbeddingVector, CreateBase64EmbeddingResponse, CreateEmbeddingResponse};
+
+const MAGIC: &[u8; 4] = b"OAEM";
+const VERSION: u8 = 1;
+
+/// The embeddings of a response as rows of one contiguous `f32` buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingMatrix {
+    model: String,
+    dimensions: usize,
+    values: Vec<f32>,
+}
+
+impl EmbeddingMatrix {
+    /// A matrix of `values.len() / dimensions` rows. Fails if the values do not fill the last row.
+    pub fn new(
+        model: impl Into<String>,
+        dimensions: usize,
+        values: Vec<f32>,
+    ) -> Result<Self, OpenAIError> {
+        if dimensions == 0 || values.len() % dimensions != 0 {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{} values are no rows of {dimensions} dimensions",
+                values.len()
+            )));
+        }
+        Ok(Self {
+            model: model.into(),
+            dimensions,
+            values,
+        })
+    }
+
+    /// Decodes all embeddings of a base64 response into one buffer, in the order of their index.
+    ///
+    /// Unlike converting each [Base64EmbeddingVector] into a `Vec<f32>`, this allocates once for the whole response.
+    pub fn from_base64_response(
+        response: &CreateBase64EmbeddingResponse,
+    ) -> Result<Self, OpenAIError> {
+        let mut data: Vec<_> = response.data.iter().collect();
+        data.sort_by_key(|embedding| embedding.index);
+
+        let mut dimensions = 0;
+        let mut values = vec![];
+        let mut bytes = vec![];
+        for embedding in data {
+            let len = embedding.embedding.decode_into(&mut bytes, &mut values)?;
+            if dimensions == 0 {
+                dimensions = len;
+                values.reserve(dimensions * (response.data.len() - 1));
+            } else if len != dimensions {
+                return Err(mismatched_dimensions(embedding.index, len, dimensions));
+            }
+        }
+        Self::new(response.model.clone(), dimensions.max(1), values)
+    }
+
+    /// Copies all embeddings of a response into one buffer, in the order of their index.
+    pub fn from_response(response: &CreateEmbeddingResponse) -> Result<Self, OpenAIError> {
+        let mut data: Vec<_> = response.data.iter().collect();
+        data.sort_by_key(|embedding| embedding.index);
+
+        let dimensions = data
+            .first()
+            .map_or(1, |embedding| embedding.embedding.len());
+        let mut values = Vec::with_capacity(dimensions * data.len());
+        for embedding in data {
+            if embedding.embedding.len() != dimensions {
+                return Err(mismatched_dimensions(
+                    embedding.index,
+                    embedding.embedding.len(),
+                    dimensions,
+                ));
+            }
+            values.extend_from_slice(&embedding.embedding);
+        }
+        Self::new(response.model.clone(), dimensions, values)
+    }
+
+    /// The model which generated the embeddings.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// The number of rows.
+    pub fn len(&self) -> usize {
+        self.values.len() / self.dimensions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The embedding of the input at `index`.
+    pub fn row(&self, index: usize) -> Option<&[f32]> {
+        self.values.chunks_exact(self.dimensions).nth(index)
+    }
+
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &[f32]> {
+        self.values.chunks_exact(self.dimensions)
+    }
+
+    /// All values, row after row.
+    pub fn as_slice(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn into_vec(self) -> Vec<f32> {
+        self.values
+    }
+
+    /// A copy of the matrix with values of lower precision, see [Quantization].
+    pub fn quantize(&self, quantization: Quantization) -> QuantizedMatrix {
+        let values = match quantization {
+            Quantization::F32 => QuantizedValues::F32(self.values.clone()),
+            Quantization::F16 => {
+                QuantizedValues::F16(self.values.iter().copied().map(f16::from_f32).collect())
+            }
+            Quantization::Int8 => {
+                let mut scales = Vec::with_capacity(self.len());
+                let mut values = Vec::with_capacity(self.values.len());
+                for row in self.rows() {
+                    let max = row.iter().fold(0f32, |max, value| max.max(value.abs()));
+                    let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+                    scales.push(scale);
+                    values.extend(row.iter().map(|value| (value / scale).round() as i8));
+                }
+                QuantizedValues::Int8 { scales, values }
+            }
+        };
+        QuantizedMatrix {
+            model: self.model.clone(),
+            dimensions: self.dimensions,
+            values,
+        }
+    }
+
+    /// Serializes the matrix in the binary format of [QuantizedMatrix::to_bytes], at full precision.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.quantize(Quantization::F32).to_bytes()
+    }
+
+    /// Reads a matrix serialized with [EmbeddingMatrix::to_bytes] or [QuantizedMatrix::to_bytes] of any precision.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OpenAIError> {
+        QuantizedMatrix::from_bytes(bytes).map(|matrix| matrix.dequantize())
+    }
+}
+
+impl TryFrom<&CreateBase64EmbeddingResponse> for EmbeddingMatrix {
+    type Error = OpenAIError;
+
+    fn try_from(response: &CreateBase64EmbeddingResponse) -> Result<Self, Self::Error> {
+        Self::from_base64_response(response)
+    }
+}
+
+impl TryFrom<&CreateEmbeddingResponse> for EmbeddingMatrix {
+    type Error = OpenAIError;
+
+    fn try_from(response: &CreateEmbeddingResponse) -> Result<Self, Self::Error> {
+        Self::from_response(response)
+    }
+}
+
+impl Base64EmbeddingVector {
+    /// Decodes the vector and appends it to `values`, returning its dimensions.
+    ///
+    /// `buffer` holds the decoded bytes and can be reused to decode many vectors without further allocations.
+    pub fn decode_into(
+        &self,
+        buffer: &mut Vec<u8>,
+        values: &mut Vec<f32>,
+    ) -> Result<usize, OpenAIError> {
+        buffer.clear();
+        general_purpose::STANDARD
+            .decode_vec(&self.0, buffer)
+            .map_err(|e| OpenAIError::InvalidArgument(format!("invalid base64 embedding: {e}")))?;
+        if buffer.len() % 4 != 0 {
+            return Err(OpenAIError::InvalidArgument(
+                "base64 embedding is not a list of f32".into(),
+            ));
+        }
+        values.extend(
+            buffer
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+        );
+        Ok(buffer.len() / 4)
+    }
+}
+
+fn mismatched_dimensions(index: u32, len: usize, dimensions: usize) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!(
+        "embedding {index} has {len} dimensions, the first one has {dimensions}"
+    ))
+}
+
+/// The precision of the values of a [QuantizedMatrix].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantization {
+    /// 4 bytes per value, lossless.
+    F32,
+    /// 2 bytes per value, half precision floats. The error is negligible for similarity search.
+    F16,
+    /// 1 byte per value, scaled per row so that the largest magnitude of each row maps to 127.
+    Int8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum QuantizedValues {
+    F32(Vec<f32>),
+    F16(Vec<f16>),
+    Int8 { scales: Vec<f32>, values: Vec<i8> },
+}
+
+/// An [EmbeddingMatrix] stored with less precision to save memory, created by [EmbeddingMatrix::quantize].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizedMatrix {
+    model: String,
+    dimensions: usize,
+    values: QuantizedValues,
+}
+
+impl QuantizedMatrix {
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    pub fn quantization(&self) -> Quantization {
+        match self.values {
+            QuantizedValues::F32(_) => Quantization::F32,
+            QuantizedValues::F16(_) => Quantization::F16,
+            QuantizedValues::Int8 { .. } => Quantization::Int8,
+        }
+    }
+
+    /// The number of rows.
+    pub fn len(&self) -> usize {
+        let values = match &self.values {
+            QuantizedValues::F32(values) => values.len(),
+            QuantizedValues::F16(values) => values.len(),
+            QuantizedValues::Int8 { values, .. } => values.len(),
+        };
+        values / self.dimensions
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The dequantized embedding of the input at `index`.
+    pub fn row(&self, index: usize) -> Option<Vec<f32>> {
+        if index >= self.len() {
+            return None;
+        }
+        let range = index * self.dimensions..(index + 1) * self.dimensions;
+        Some(match &self.values {
+            QuantizedValues::F32(values) => values[range].to_vec(),
+            QuantizedValues::F16(values) => values[range].iter().map(|v| v.to_f32()).collect(),
+            QuantizedValues::Int8 { scales, values } => values[range]
+                .iter()
+                .map(|&value| f32::from(value) * scales[index])
+                .collect(),
+        })
+    }
+
+    /// Converts the values back to `f32`.
+    pub fn dequantize(&self) -> EmbeddingMatrix {
+        let values = match &self.values {
+            QuantizedValues::F32(values) => values.clone(),
+            QuantizedValues::F16(values) => values.iter().map(|value| value.to_f32()).collect(),
+            QuantizedValues::Int8 { scales, values } => values
+                .chunks_exact(self.dimensions)
+                .zip(scales)
+                .flat_map(|(row, scale)| row.iter().map(move |&value| f32::from(value) * scale))
+                .collect(),
+        };
+        EmbeddingMatrix {
+            model: self.model.clone(),
+            dimensions: self.dimensions,
+            values,
+        }
+    }
+
+    /// Serializes the matrix into a compact binary format, e.g. to persist it in a key-value store.
+    ///
+    /// The format is a header with the magic bytes `OAEM`, a format version, the [Quantization], the dimensions, the
+    /// number of rows and the model name, followed by the values in little-endian byte order. Int8 values are
+    /// preceded by the `f32` scale of every row.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (quantization, width) = match self.quantization() {
+            Quantization::F32 => (0u8, 4),
+            Quantization::F16 => (1, 2),
+            Quantization::Int8 => (2, 1),
+        };
+        let mut bytes =
+            Vec::with_capacity(18 + self.model.len() + self.len() * (self.dimensions * width + 4));
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(quantization);
+        bytes.extend_from_slice(&(self.dimensions as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.model.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(self.model.as_bytes());
+        match &self.values {
+            QuantizedValues::F32(values) => values
+                .iter()
+                .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes())),
+            QuantizedValues::F16(values) => values
+                .iter()
+                .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes())),
+            QuantizedValues::Int8 { scales, values } => {
+                scales
+                    .iter()
+                    .for_each(|scale| bytes.extend_from_slice(&scale.to_le_bytes()));
+                bytes.extend(values.iter().map(|&value| value as u8));
+            }
+        }
+        bytes
+    }
+
+    /// Reads a matrix serialized with [QuantizedMatrix::to_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OpenAIError> {
+        let invalid = |message: &str| {
+            OpenAIError::InvalidArgument(format!("invalid embedding matrix: {message}"))
+        };
+        let mut rest = bytes;
+        let mut take = |n: usize| {
+            if rest.len() < n {
+                return Err(invalid("unexpected end of data"));
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(head)
+        };
+        let u32_at = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        if take(4)? != MAGIC {
+            return Err(invalid("not an embedding matrix"));
+        }
+        let header = take(2)?;
+        if header[0] != VERSION {
+            return Err(invalid(&format!("unsupported version {}", header[0])));
+        }
+        let dimensions = u32_at(take(4)?) as usize;
+        let rows = u32_at(take(4)?) as usize;
+        let model_len = u32_at(take(4)?) as usize;
+        let model =
+            String::from_utf8(take(model_len)?.to_vec()).map_err(|e| invalid(&e.to_string()))?;
+        if dimensions == 0 {
+            return Err(invalid("zero dimensions"));
+        }
+        let count = rows
+            .checked_mul(dimensions)
+            .ok_or_else(|| invalid("too many values"))?;
+        let size = |width: usize| {
+            count
+                .checked_mul(width)
+                .ok_or_else(|| invalid("too many values"))
+        };
+
+        let values = match header[1] {
+            0 => QuantizedValues::F32(
+                take(size(4)?)?
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect(),
+            ),
+            1 => QuantizedValues::F16(
+                take(size(2)?)?
+                    .chunks_exact(2)
+                    .map(|chunk| f16::from_le_bytes([chunk[0], chunk[1]]))
+                    .collect(),
+            ),
+            2 => {
+                let scales = take(
+                    rows.checked_mul(4)
+                        .ok_or_else(|| invalid("too many rows"))?,
+                )?
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect();
+                let values = take(size(1)?)?.iter().map(|&value| value as i8).collect();
+                QuantizedValues::Int8 { scales, values }
+            }
+            quantization => return Err(invalid(&format!("unknown quantization {quantization}"))),
+        };
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+
+        Ok(Self {
+            model,
+            dimensions,
+            values,
+        })
+    }
+}
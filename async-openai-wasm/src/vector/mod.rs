@@ -0,0 +1,114 @@
+//! Vector math on embeddings and a small in-memory similarity index, see [VectorExt] and [VectorIndex].
+//! Compact storage of many embeddings in one buffer, optionally quantized, see [EmbeddingMatrix].
+//!
+//! ```
+//! use async_openai_wasm::vector::{VectorExt, VectorIndex};
+//! use serde_json::json;
+//!
+//! let mut index = VectorIndex::new();
+//! index.add("rust", vec![1.0, 0.0], json!({"lang": "en"})).unwrap();
+//! index.add("rost", vec![0.8, 0.6], json!({"lang": "de"})).unwrap();
+//!
+//! let hits = index.search(&[1.0, 0.1], 1);
+//! assert_eq!(hits[0].id, "rust");
+//!
+//! let hits = index.search_filtered(&[1.0, 0.1], 1, |metadata| metadata["lang"] == "de");
+//! assert_eq!(hits[0].id, "rost");
+//! assert!((hits[0].score - [0.8, 0.6].cosine_similarity(&[1.0, 0.1])).abs() < 1e-6);
+//! ```
+use crate::types::Embedding;
+
+pub use index::*;
+pub use matrix::*;
+
+mod index;
+mod matrix;
+
+/// Vector math on embedding vectors, implemented for `[f32]` and thereby for `Vec<f32>`.
+pub trait VectorExt {
+    /// The dot product, or `0.0` if the lengths differ.
+    fn dot(&self, other: &[f32]) -> f32;
+
+    /// The euclidean length.
+    fn norm(&self) -> f32;
+
+    /// The cosine of the angle between both vectors, or `0.0` if the lengths differ or either is zero.
+    ///
+    /// Embeddings of OpenAI models are normalized, for which this equals the [VectorExt::dot] product.
+    fn cosine_similarity(&self, other: &[f32]) -> f32;
+
+    /// Scales the vector in place to a length of 1. A zero vector is left unchanged.
+    fn normalize(&mut self);
+
+    /// A copy of the vector scaled to a length of 1.
+    fn normalized(&self) -> Vec<f32>;
+
+    /// The first `dimensions` components, normalized again.
+    ///
+    /// The `text-embedding-3` models are trained so that such a prefix is itself an embedding (Matryoshka
+    /// representation learning), which is what the API returns when `dimensions` is set on the request.
+    fn truncated(&self, dimensions: usize) -> Vec<f32>;
+}
+
+impl VectorExt for [f32] {
+    fn dot(&self, other: &[f32]) -> f32 {
+        if self.len() != other.len() {
+            return 0.0;
+        }
+        self.iter().zip(other).map(|(a, b)| a * b).sum()
+    }
+
+    fn norm(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    fn cosine_similarity(&self, other: &[f32]) -> f32 {
+        let norms = self.norm() * other.norm();
+        if norms == 0.0 {
+            0.0
+        } else {
+            self.dot(other) / norms
+        }
+    }
+
+    fn normalize(&mut self) {
+        let norm = self.norm();
+        if norm > 0.0 {
+            self.iter_mut().for_each(|value| *value /= norm);
+        }
+    }
+
+    fn normalized(&self) -> Vec<f32> {
+        let mut vector = self.to_vec();
+        vector.normalize();
+        vector
+    }
+
+    fn truncated(&self, dimensions: usize) -> Vec<f32> {
+        self[..dimensions.min(self.len())].normalized()
+    }
+}
+
+impl Embedding {
+    /// The cosine similarity of both embeddings, see [VectorExt::cosine_similarity].
+    pub fn cosine_similarity(&self, other: &Embedding) -> f32 {
+        self.embedding.cosine_similarity(&other.embedding)
+    }
+
+    /// The dot product of both embeddings, see [VectorExt::dot].
+    pub fn dot(&self, other: &Embedding) -> f32 {
+        self.embedding.dot(&other.embedding)
+    }
+
+    /// Scales the embedding in place to a length of 1, see [VectorExt::normalize].
+    pub fn normalize(&mut self) {
+        self.embedding.normalize();
+    }
+
+    /// Shortens the embedding in place to its first `dimensions` components and normalizes it again,
+    /// see [VectorExt::truncated].
+    pub fn truncate(&mut self, dimensions: usize) {
+        self.embedding.truncate(dimensions);
+        self.embedding.normalize();
+    }
+}
@@ -0,0 +1,96 @@
+//! Checking and rewriting chat requests with the model capability registry.
+use async_openai_wasm::capabilities::{
+    ModelCapabilities, ModelParameter, ModelRegistry, UnsupportedParameters,
+};
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
+    ChatCompletionRequestUserMessage, CreateChatCompletionRequest, CreateChatCompletionRequestArgs,
+};
+use async_openai_wasm::Client;
+
+fn request(model: &str) -> CreateChatCompletionRequest {
+    CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages([
+            ChatCompletionRequestSystemMessage::from("Be brief.").into(),
+            ChatCompletionRequestUserMessage::from("Hi").into(),
+        ])
+        .temperature(0.2)
+        .max_tokens(100u32)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn lookup_by_longest_prefix() {
+    let registry = ModelRegistry::default();
+    assert_eq!(
+        registry
+            .get("gpt-4o-mini-2024-07-18")
+            .unwrap()
+            .context_window,
+        128000
+    );
+    assert_eq!(registry.get("gpt-4-0613").unwrap().context_window, 8192);
+    assert!(registry
+        .get("ft:gpt-4o-2024-08-06:org::id")
+        .unwrap()
+        .supports(ModelParameter::Temperature));
+    assert!(!registry
+        .get("gpt-5-2025-08-07")
+        .unwrap()
+        .supports(ModelParameter::Temperature));
+    assert!(registry
+        .get("gpt-5-chat-latest")
+        .unwrap()
+        .supports(ModelParameter::Temperature));
+    assert!(registry.get("my-model").is_none());
+
+    let mut registry = registry;
+    registry.insert("my-model", ModelCapabilities::reasoning(32000, 4000));
+    assert_eq!(
+        registry.get("my-model-v2").unwrap().max_output_tokens,
+        Some(4000)
+    );
+}
+
+#[test]
+fn reject_reasoning_model_parameters() {
+    let registry = ModelRegistry::default();
+    assert!(registry.check_chat_request(&request("gpt-4o")).is_empty());
+
+    let params: Vec<_> = registry
+        .check_chat_request(&request("o3-mini"))
+        .into_iter()
+        .map(|detail| detail.param)
+        .collect();
+    assert_eq!(params, ["temperature", "max_tokens", "messages"]);
+}
+
+#[test]
+fn rewrite_for_reasoning_model() {
+    let registry = ModelRegistry::default();
+    let mut request = request("o3-mini");
+    registry.adapt_chat_request(&mut request).unwrap();
+    assert_eq!(request.temperature, None);
+    assert_eq!(request.max_tokens, None);
+    assert_eq!(request.max_completion_tokens, Some(100));
+    assert!(matches!(
+        request.messages[0],
+        ChatCompletionRequestMessage::Developer(_)
+    ));
+
+    // o1-mini supports neither system nor developer messages
+    let mut request = self::request("o1-mini");
+    let error = registry.adapt_chat_request(&mut request).unwrap_err();
+    assert!(matches!(error, OpenAIError::InvalidArguments(details) if details.len() == 1));
+}
+
+#[tokio::test]
+async fn client_rejects_before_sending() {
+    let client =
+        Client::new().with_model_registry(ModelRegistry::default(), UnsupportedParameters::Reject);
+    let error = client.chat().create(request("o1")).await.unwrap_err();
+    assert!(matches!(error, OpenAIError::InvalidArguments(_)));
+}
@@ -0,0 +1,111 @@
+//! Splitting streams of requests with `n > 1` by choice.
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::stream::ChatCompletionStreamExt;
+use async_openai_wasm::types::{CreateChatCompletionStreamResponse, FinishReason};
+use futures::{stream, StreamExt};
+use serde_json::json;
+
+fn chunk(
+    index: u32,
+    content: &str,
+    finish: bool,
+) -> Result<CreateChatCompletionStreamResponse, OpenAIError> {
+    Ok(serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": index,
+            "delta": {"content": content},
+            "finish_reason": if finish { Some("stop") } else { None },
+            "logprobs": null
+        }]
+    }))
+    .unwrap())
+}
+
+fn chunks() -> Vec<Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+    vec![
+        chunk(0, "a", false),
+        chunk(1, "x", false),
+        chunk(1, "y", true),
+        chunk(0, "b", false),
+        chunk(0, "c", true),
+    ]
+}
+
+#[tokio::test]
+async fn keyed_choices() {
+    let keyed: Vec<(u32, String)> = stream::iter(chunks())
+        .keyed_choices()
+        .map(|item| {
+            let (index, choice) = item.unwrap();
+            (index, choice.delta.content.unwrap())
+        })
+        .collect()
+        .await;
+    assert_eq!(
+        keyed,
+        [(0, "a"), (1, "x"), (1, "y"), (0, "b"), (0, "c")].map(|(i, s)| (i, s.to_string()))
+    );
+}
+
+#[tokio::test]
+async fn split_choices_end_on_their_finish_reason() {
+    let mut streams = stream::iter(chunks())
+        // the inner stream never ends, every choice stream has to end on its own
+        .chain(stream::pending())
+        .split_choices(2);
+    let second = streams.pop().unwrap();
+    let first = streams.pop().unwrap();
+    assert_eq!(second.index(), 1);
+
+    // drain the second choice first, chunks of the first are buffered meanwhile
+    let second: Vec<_> = second.map(Result::unwrap).collect().await;
+    assert_eq!(second.len(), 2);
+    assert_eq!(second[1].finish_reason, Some(FinishReason::Stop));
+
+    let first: String = first
+        .map(|choice| choice.unwrap().delta.content.unwrap())
+        .collect()
+        .await;
+    assert_eq!(first, "abc");
+}
+
+#[tokio::test]
+async fn split_choices_concurrently_and_propagate_errors() {
+    let streams = stream::iter(vec![
+        chunk(0, "a", false),
+        chunk(1, "x", false),
+        Err(OpenAIError::StreamError("connection reset".into())),
+    ])
+    .split_choices(2);
+
+    let results =
+        futures::future::join_all(streams.into_iter().map(|stream| stream.collect::<Vec<_>>()))
+            .await;
+    for result in results {
+        assert_eq!(result.len(), 2);
+        assert!(result[0].is_ok());
+        assert!(
+            matches!(&result[1], Err(OpenAIError::StreamError(message)) if message == "connection reset")
+        );
+    }
+}
+
+#[tokio::test]
+async fn split_choices_wake_waiting_streams() {
+    let (sender, receiver) = futures::channel::mpsc::unbounded();
+    let mut streams = receiver.split_choices(2).into_iter();
+    let (first, second) = (streams.next().unwrap(), streams.next().unwrap());
+
+    let send = async move {
+        for chunk in chunks() {
+            tokio::task::yield_now().await;
+            sender.unbounded_send(chunk).unwrap();
+        }
+    };
+    let (_, first, second) = tokio::join!(send, first.count(), second.count());
+    assert_eq!((first, second), (3, 2));
+}
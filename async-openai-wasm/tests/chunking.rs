@@ -0,0 +1,214 @@
+//! Token-aware splitting of documents into chunks.
+use async_openai_wasm::chunking::{Chunk, ChunkingMode, TextChunker};
+use async_openai_wasm::types::StaticChunkingStrategy;
+use serde_json::{json, Value};
+
+fn words(n: usize) -> String {
+    (0..n)
+        .map(|i| format!("word{i}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Chunks are slices of the text at their offsets, in order and numbered, and none exceeds the limit by much.
+fn check_chunks(text: &str, chunks: &[Chunk], max: usize, slack: usize) {
+    assert!(!chunks.is_empty());
+    for (index, chunk) in chunks.iter().enumerate() {
+        assert_eq!(chunk.index, index);
+        assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        assert!(chunk.tokens <= max + slack, "{} > {max}", chunk.tokens);
+    }
+    assert!(chunks.windows(2).all(|pair| pair[0].start < pair[1].start));
+    assert!(chunks.windows(2).all(|pair| pair[0].end < pair[1].end));
+}
+
+#[test]
+fn defaults_match_vector_store_chunking() {
+    let chunker = TextChunker::default();
+    assert_eq!(chunker.max_chunk_size_tokens, 800);
+    assert_eq!(chunker.chunk_overlap_tokens, 400);
+    assert_eq!(chunker.mode, ChunkingMode::Fixed);
+
+    let strategy = StaticChunkingStrategy::from(&chunker.clone().with_max_chunk_size_tokens(1000));
+    assert_eq!(
+        serde_json::to_value(&strategy).unwrap(),
+        json!({"max_chunk_size_tokens": 1000, "chunk_overlap_tokens": 400})
+    );
+    assert_eq!(TextChunker::from(&strategy).max_chunk_size_tokens, 1000);
+}
+
+#[test]
+fn fixed_windows_overlap() {
+    let text = words(500);
+    let chunker = TextChunker::default()
+        .with_max_chunk_size_tokens(100)
+        .with_chunk_overlap_tokens(40);
+    let chunks = chunker.split_with_metadata(&text, json!({"source": "words.txt"}));
+    check_chunks(&text, &chunks, 100, 0);
+    assert!(chunks.len() > 2);
+    assert_eq!(chunks[0].start, 0);
+    assert_eq!(chunks.last().unwrap().end, text.len());
+    assert!(chunks
+        .iter()
+        .all(|chunk| chunk.metadata["source"] == "words.txt"));
+
+    for pair in chunks.windows(2) {
+        // the next window starts 40 tokens before the end of the previous one
+        assert!(pair[1].start < pair[0].end);
+        assert_eq!(chunker.count_tokens(&text[pair[1].start..pair[0].end]), 40);
+    }
+    assert!(chunks[..chunks.len() - 1]
+        .iter()
+        .all(|chunk| chunk.tokens == 100));
+
+    // an overlap as large as the chunk still advances
+    let chunks = chunker.clone().with_chunk_overlap_tokens(1000).split(&text);
+    assert!(chunks.len() > 1);
+    assert!(TextChunker::default().split("").is_empty());
+}
+
+#[test]
+fn recursive_prefers_paragraphs_and_sentences() {
+    let paragraphs: Vec<String> = (0..6)
+        .map(|i| format!("Paragraph {i} starts here. {}.", words(20)))
+        .collect();
+    let text = paragraphs.join("\n\n");
+    let chunker = TextChunker::default()
+        .with_mode(ChunkingMode::recursive())
+        .with_max_chunk_size_tokens(120)
+        .with_chunk_overlap_tokens(0);
+    let chunks = chunker.split(&text);
+    check_chunks(&text, &chunks, 120, 5);
+    assert!(chunks.len() > 1);
+    // without overlap, chunks are consecutive and end at paragraph breaks
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[0].end, pair[1].start);
+        assert!(pair[0].text.ends_with("\n\n"));
+        assert!(pair[1].text.starts_with("Paragraph"));
+    }
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|chunk| chunk.text.as_str())
+            .collect::<String>(),
+        text
+    );
+
+    // overlapping chunks repeat whole paragraphs
+    let chunks = chunker.with_chunk_overlap_tokens(60).split(&text);
+    check_chunks(&text, &chunks, 120, 5);
+    assert!(chunks.windows(2).all(|pair| pair[1].start < pair[0].end));
+    assert!(chunks
+        .iter()
+        .all(|chunk| chunk.text.starts_with("Paragraph")));
+
+    // a single long word falls back to token windows
+    let text = "x".repeat(1000);
+    let chunks = TextChunker::default()
+        .with_mode(ChunkingMode::Recursive(vec![" ".into()]))
+        .with_max_chunk_size_tokens(50)
+        .with_chunk_overlap_tokens(0)
+        .split(&text);
+    check_chunks(&text, &chunks, 50, 0);
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|chunk| chunk.text.as_str())
+            .collect::<String>(),
+        text
+    );
+}
+
+#[test]
+fn markdown_sections_and_code_blocks() {
+    let code = format!("```rust\n{}```\n", "let x = 1;\n".repeat(12));
+    let text = format!(
+        "# Guide\n\nIntro. {}\n\n## Install\n\n{}\n\n{code}\n## Usage\n\n# Not a heading inside code\n\n{}\n\n# Reference\n\n{}\n",
+        words(30),
+        words(30),
+        words(30),
+        words(30)
+    );
+    let text = text.replace(
+        "# Not a heading inside code\n\n",
+        "```\n# Not a heading inside code\n```\n\n",
+    );
+    let chunker = TextChunker::default()
+        .with_mode(ChunkingMode::Markdown)
+        .with_max_chunk_size_tokens(100)
+        .with_chunk_overlap_tokens(0);
+    let chunks = chunker.split(&text);
+    check_chunks(&text, &chunks, 100, 5);
+
+    // the code block fits in a chunk and is not split
+    let code_start = text.find("```rust").unwrap();
+    let code_end = code_start + code.len();
+    assert!(chunks
+        .iter()
+        .any(|chunk| chunk.start <= code_start && code_end <= chunk.end));
+
+    let reference = chunks
+        .iter()
+        .find(|chunk| chunk.text.starts_with("# Reference"))
+        .unwrap();
+    assert_eq!(reference.headings, ["Reference"]);
+    let usage = chunks
+        .iter()
+        .find(|chunk| {
+            chunk.start <= text.find("```\n# Not").unwrap()
+                && chunk.end > text.find("# Not").unwrap()
+        })
+        .unwrap();
+    assert_eq!(usage.headings.first().map(String::as_str), Some("Guide"));
+    assert!(!usage
+        .headings
+        .iter()
+        .any(|heading| heading.contains("Not a heading")));
+    assert!(chunks.iter().all(|chunk| chunk.metadata == Value::Null));
+}
+
+#[test]
+fn code_splits_at_top_level_blocks() {
+    let function = |name: &str| {
+        format!(
+            "fn {name}() {{\n    let value = {};\n    value.len()\n}}\n",
+            words(10)
+        )
+    };
+    let text = [
+        "use std::fmt;\n".to_string(),
+        function("a"),
+        function("b"),
+        function("c"),
+    ]
+    .join("\n");
+    let chunker = TextChunker::default()
+        .with_mode(ChunkingMode::Code)
+        .with_max_chunk_size_tokens(80)
+        .with_chunk_overlap_tokens(0);
+    let chunks = chunker.split(&text);
+    check_chunks(&text, &chunks, 80, 5);
+    assert!(chunks.len() > 1);
+    assert!(chunks[1..]
+        .iter()
+        .all(|chunk| chunk.text.starts_with("fn ")));
+    assert!(chunks.iter().all(|chunk| chunk.headings.is_empty()));
+}
+
+#[test]
+fn offsets_respect_characters() {
+    let text = "äöü🦀 ".repeat(200);
+    for mode in [
+        ChunkingMode::Fixed,
+        ChunkingMode::recursive(),
+        ChunkingMode::Markdown,
+        ChunkingMode::Code,
+    ] {
+        let chunks = TextChunker::default()
+            .with_mode(mode)
+            .with_max_chunk_size_tokens(30)
+            .with_chunk_overlap_tokens(10)
+            .split(&text);
+        check_chunks(&text, &chunks, 30, 5);
+    }
+}
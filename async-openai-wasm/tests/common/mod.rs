@@ -0,0 +1,136 @@
+//! A local HTTP/1.1 server for the integration tests that need an API to talk to.
+#![allow(dead_code)]
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// The body of a request to [serve].
+pub struct Request {
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// The values of a field of a multipart body.
+    pub fn fields(&self, name: &str) -> Vec<String> {
+        self.text()
+            .split(&format!("name=\"{name}\"\r\n\r\n"))
+            .skip(1)
+            .map(|value| value[..value.find("\r\n").unwrap()].to_string())
+            .collect()
+    }
+
+    /// The first value of a field of a multipart body.
+    pub fn field(&self, name: &str) -> Option<String> {
+        self.fields(name).into_iter().next()
+    }
+}
+
+/// The answer of the handler of [serve].
+pub struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    /// Written at once with a `content-length`, or as one chunk per part when chunked.
+    parts: Vec<Vec<u8>>,
+    chunked: bool,
+}
+
+impl Response {
+    pub fn new(status: &'static str, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type,
+            parts: vec![body.into()],
+            chunked: false,
+        }
+    }
+
+    /// A `200 OK` JSON response.
+    pub fn json(value: Value) -> Self {
+        Self::new("200 OK", "application/json", value.to_string())
+    }
+
+    /// A JSON error response of the API.
+    pub fn error(status: &'static str, value: Value) -> Self {
+        Self::new(status, "application/json", value.to_string())
+    }
+
+    /// A `200 OK` response sent with chunked encoding, pausing after every part.
+    pub fn chunked(content_type: &'static str, parts: Vec<Vec<u8>>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            parts,
+            chunked: true,
+        }
+    }
+}
+
+/// Answers every request to the returned base URL with `handler`, each on its own thread.
+pub fn serve<F>(handler: F) -> String
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/v1", listener.local_addr().unwrap());
+    let handler = std::sync::Arc::new(handler);
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let handler = handler.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let response = handler(Request { body });
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\ncontent-type: {}\r\nconnection: close\r\n",
+                    response.status, response.content_type
+                )
+                .unwrap();
+                if response.chunked {
+                    stream
+                        .write_all(b"transfer-encoding: chunked\r\n\r\n")
+                        .unwrap();
+                    for part in response.parts {
+                        write!(stream, "{:x}\r\n", part.len()).unwrap();
+                        stream.write_all(&part).unwrap();
+                        stream.write_all(b"\r\n").unwrap();
+                        stream.flush().unwrap();
+                        std::thread::sleep(Duration::from_millis(5));
+                    }
+                    stream.write_all(b"0\r\n\r\n").unwrap();
+                } else {
+                    let body = response.parts.concat();
+                    write!(stream, "content-length: {}\r\n\r\n", body.len()).unwrap();
+                    stream.write_all(&body).unwrap();
+                }
+            });
+        }
+    });
+    url
+}
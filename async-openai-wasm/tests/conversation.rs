@@ -0,0 +1,114 @@
+//! Appending to and truncating a conversation.
+use async_openai_wasm::conversation::Conversation;
+use async_openai_wasm::types::{ChatCompletionRequestMessage, CreateChatCompletionResponse};
+use serde_json::json;
+
+fn response(message: serde_json::Value) -> CreateChatCompletionResponse {
+    serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{"index": 0, "message": message, "finish_reason": "stop", "logprobs": null}]
+    }))
+    .unwrap()
+}
+
+fn tool_call_response(ids: &[&str]) -> CreateChatCompletionResponse {
+    let tool_calls: Vec<_> = ids
+        .iter()
+        .map(|id| json!({"id": id, "type": "function", "function": {"name": "lookup", "arguments": "{}"}}))
+        .collect();
+    response(json!({"role": "assistant", "content": null, "tool_calls": tool_calls}))
+}
+
+fn roles(conversation: &Conversation) -> Vec<&'static str> {
+    conversation
+        .messages()
+        .iter()
+        .map(|message| match message {
+            ChatCompletionRequestMessage::System(_) => "system",
+            ChatCompletionRequestMessage::Developer(_) => "developer",
+            ChatCompletionRequestMessage::User(_) => "user",
+            ChatCompletionRequestMessage::Assistant(_) => "assistant",
+            ChatCompletionRequestMessage::Tool(_) => "tool",
+            ChatCompletionRequestMessage::Function(_) => "function",
+        })
+        .collect()
+}
+
+#[test]
+fn tool_results_follow_their_calls() {
+    let mut conversation = Conversation::new();
+    conversation.push_user("Look up a and b");
+    conversation
+        .push_response(&tool_call_response(&["call_a", "call_b"]))
+        .unwrap();
+    assert_eq!(conversation.pending_tool_calls().len(), 2);
+
+    conversation.push_tool_result("call_a", "result a").unwrap();
+    assert_eq!(conversation.pending_tool_calls()[0].id, "call_b");
+    assert!(conversation.push_tool_result("call_a", "again").is_err());
+    assert!(conversation.push_tool_result("call_c", "unknown").is_err());
+    conversation.push_tool_result("call_b", "result b").unwrap();
+    assert!(conversation.pending_tool_calls().is_empty());
+
+    conversation
+        .push_response(&response(json!({"role": "assistant", "content": "Done"})))
+        .unwrap();
+    assert_eq!(
+        roles(&conversation),
+        ["user", "assistant", "tool", "tool", "assistant"]
+    );
+}
+
+#[test]
+fn truncation_keeps_system_messages_and_tool_pairs() {
+    let mut conversation = Conversation::new();
+    conversation.push_system("Be brief.");
+    conversation.push_user("first");
+    conversation
+        .push_response(&tool_call_response(&["call_1"]))
+        .unwrap();
+    conversation.push_tool_result("call_1", "result").unwrap();
+    conversation.push_user("second");
+    conversation
+        .push_response(&response(json!({"role": "assistant", "content": "ok"})))
+        .unwrap();
+    conversation.push_user("third");
+
+    // every message costs one token
+    let count = |messages: &[ChatCompletionRequestMessage]| messages.len();
+
+    let mut truncated = conversation.clone();
+    assert_eq!(truncated.truncate_with(4, count).unwrap(), 3);
+    assert_eq!(roles(&truncated), ["system", "user", "assistant", "user"]);
+
+    let mut truncated = conversation.clone();
+    assert_eq!(truncated.truncate_with(2, count).unwrap(), 5);
+    assert_eq!(roles(&truncated), ["system", "user"]);
+
+    // the latest turn is never dropped
+    let mut truncated = conversation.clone();
+    assert!(truncated.truncate_with(1, count).is_err());
+    assert_eq!(truncated, conversation);
+}
+
+#[test]
+fn serializes_to_json() {
+    let mut conversation = Conversation::new();
+    conversation.push_system("Be brief.");
+    conversation.push_user("hi");
+    let value = serde_json::to_value(&conversation).unwrap();
+    assert_eq!(
+        value,
+        json!({"messages": [
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "content": "hi"}
+        ]})
+    );
+    assert_eq!(
+        serde_json::from_value::<Conversation>(value).unwrap(),
+        conversation
+    );
+}
@@ -0,0 +1,151 @@
+//! Fine-tuning and batch JSONL datasets.
+use async_openai_wasm::dataset::{ChatFineTuningExample, DatasetWriter, PreferenceExample};
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::types::{
+    BatchRequestInput, ChatCompletionRequestAssistantMessage, ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessage, CreateChatCompletionRequest, CreateChatCompletionRequestArgs,
+    CreateChatCompletionResponse, FilePurpose, Validate,
+};
+use serde_json::json;
+
+fn messages(value: serde_json::Value) -> Vec<ChatCompletionRequestMessage> {
+    serde_json::from_value(value).unwrap()
+}
+
+fn params(error: OpenAIError) -> Vec<String> {
+    match error {
+        OpenAIError::InvalidArguments(details) => {
+            details.into_iter().map(|detail| detail.param).collect()
+        }
+        e => panic!("unexpected error: {e}"),
+    }
+}
+
+#[test]
+fn chat_examples_with_tools_and_weights() {
+    let request: CreateChatCompletionRequest = serde_json::from_value(json!({
+        "model": "gpt-4o-mini",
+        "messages": [
+            {"role": "system", "content": "You are a weather bot."},
+            {"role": "user", "content": "Weather in Paris?"},
+            {"role": "assistant", "tool_calls": [
+                {"id": "call_1", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"}}
+            ]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "sunny"}
+        ],
+        "tools": [{"type": "function", "function": {"name": "weather"}}],
+        "parallel_tool_calls": false
+    }))
+    .unwrap();
+    let response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-1", "object": "chat.completion", "created": 1, "model": "gpt-4o-mini",
+        "choices": [{"index": 0, "finish_reason": "stop", "logprobs": null,
+                     "message": {"role": "assistant", "content": "It is sunny in Paris."}}]
+    }))
+    .unwrap();
+
+    let example = ChatFineTuningExample::from_exchange(&request, &response)
+        .unwrap()
+        .with_weight(2, 0)
+        .unwrap();
+    assert!(example.clone().with_weight(1, 0).is_err());
+
+    let mut writer = DatasetWriter::new();
+    writer.push(&example).unwrap();
+    let line: serde_json::Value = serde_json::from_str(writer.as_str().trim_end()).unwrap();
+    assert_eq!(line["messages"][2]["weight"], 0);
+    assert_eq!(line["messages"][2]["role"], "assistant");
+    assert!(line["messages"][4].get("weight").is_none());
+    assert_eq!(line["messages"][4]["content"], "It is sunny in Paris.");
+    assert_eq!(line["tools"][0]["function"]["name"], "weather");
+    assert_eq!(line["parallel_tool_calls"], false);
+
+    let parsed: ChatFineTuningExample = serde_json::from_value(line).unwrap();
+    assert_eq!(parsed, example);
+
+    let request = writer.into_file_request("train.jsonl");
+    assert_eq!(request.purpose, FilePurpose::FineTune);
+}
+
+#[test]
+fn role_ordering_is_checked() {
+    let example = ChatFineTuningExample::new(messages(json!([
+        {"role": "assistant", "content": "Hi!"},
+        {"role": "system", "content": "Be brief."},
+        {"role": "user", "content": "Weather?"},
+        {"role": "assistant", "tool_calls": [
+            {"id": "call_1", "type": "function", "function": {"name": "weather", "arguments": "{}"}}
+        ]},
+        {"role": "tool", "tool_call_id": "call_2", "content": "sunny"},
+        {"role": "user", "content": "Well?"}
+    ])));
+
+    let mut writer = DatasetWriter::new();
+    assert_eq!(
+        params(writer.push(&example).unwrap_err()),
+        [
+            "lines[0].messages[0]",
+            "lines[0].messages[1]",
+            "lines[0].messages[4]",
+            "lines[0].messages[5]",
+            "lines[0].messages",
+        ]
+    );
+    assert!(writer.is_empty());
+}
+
+#[test]
+fn preference_pairs() {
+    let example = PreferenceExample::new(
+        vec![ChatCompletionRequestUserMessage::from("Hello").into()],
+        ChatCompletionRequestAssistantMessage::from("Hi! How can I help?"),
+        ChatCompletionRequestAssistantMessage::from("What?"),
+    );
+    assert!(example.validate().is_ok());
+    let line = serde_json::to_value(&example).unwrap();
+    assert_eq!(line["input"]["messages"][0]["role"], "user");
+    assert_eq!(
+        line["preferred_output"][0]["content"],
+        "Hi! How can I help?"
+    );
+    assert_eq!(line["non_preferred_output"][0]["role"], "assistant");
+
+    let mut invalid = example.clone();
+    invalid
+        .input
+        .messages
+        .push(ChatCompletionRequestAssistantMessage::from("Hi!").into());
+    invalid.preferred_output.clear();
+    assert_eq!(
+        params(invalid.validate().unwrap_err()),
+        ["input.messages", "preferred_output"]
+    );
+}
+
+#[test]
+fn batch_input_lines() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4o-mini")
+        .messages([ChatCompletionRequestUserMessage::from("Hello").into()])
+        .build()
+        .unwrap();
+
+    let mut writer = DatasetWriter::new();
+    writer
+        .push(&BatchRequestInput::chat_completion("request-1", &request).unwrap())
+        .unwrap();
+    let duplicate = BatchRequestInput::chat_completion("request-1", &request).unwrap();
+    assert_eq!(
+        params(writer.push(&duplicate).unwrap_err()),
+        ["lines[1].custom_id"]
+    );
+
+    let line: serde_json::Value = serde_json::from_str(writer.as_str().trim_end()).unwrap();
+    assert_eq!(line["url"], "/v1/chat/completions");
+    assert_eq!(line["method"], "POST");
+    assert_eq!(line["body"]["model"], "gpt-4o-mini");
+    assert_eq!(
+        writer.into_file_request("batch.jsonl").purpose,
+        FilePurpose::Batch
+    );
+}
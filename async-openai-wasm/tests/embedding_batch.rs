@@ -0,0 +1,193 @@
+//! Auto-batching of embedding requests, against a local server that embeds each numeric input as its value.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::embedding_batch::EmbeddingBatchOptions;
+use async_openai_wasm::types::{CreateEmbeddingRequestArgs, EmbeddingInput, EncodingFormat};
+use async_openai_wasm::{BatchRequestOptions, Client};
+use base64::engine::{general_purpose, Engine};
+use serde_json::{json, Value};
+
+mod common;
+use common::Response;
+
+/// Serves `/embeddings`, failing the first `rate_limited` requests with a 429. Returns the base url and the input
+/// counts of the successful requests.
+fn serve(rate_limited: usize) -> (String, Arc<Mutex<Vec<usize>>>) {
+    let batches = Arc::new(Mutex::new(vec![]));
+    let failures = AtomicUsize::new(rate_limited);
+
+    let served = batches.clone();
+    let url = common::serve(move |request| {
+        let request = request.json();
+        if failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            return Response::error(
+                "429 Too Many Requests",
+                json!({"error": {
+                    "message": "Rate limit reached", "type": "requests", "param": null, "code": "rate_limit_exceeded"
+                }}),
+            );
+        }
+        let inputs = request["input"].as_array().unwrap();
+        served.lock().unwrap().push(inputs.len());
+        let base64 = request["encoding_format"] == "base64";
+        let data: Vec<Value> = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let value: f32 = input.as_str().unwrap().parse().unwrap();
+                let embedding = if base64 {
+                    json!(general_purpose::STANDARD.encode(value.to_le_bytes()))
+                } else {
+                    json!([value])
+                };
+                json!({"index": index, "object": "embedding", "embedding": embedding})
+            })
+            .collect();
+        Response::json(json!({
+            "object": "list",
+            "model": request["model"],
+            "data": data,
+            "usage": {"prompt_tokens": inputs.len(), "total_tokens": inputs.len()}
+        }))
+    });
+
+    (url, batches)
+}
+
+fn inputs(n: usize) -> Vec<String> {
+    (0..n).map(|i| i.to_string()).collect()
+}
+
+#[test]
+fn split_respects_input_and_token_limits() {
+    let options = EmbeddingBatchOptions::default()
+        .with_max_inputs(3)
+        .with_max_tokens(2);
+    let batches = options.split(
+        "text-embedding-3-small",
+        EmbeddingInput::ArrayOfIntegerArray(vec![
+            vec![1],
+            vec![2],
+            vec![3, 4, 5],
+            vec![6],
+            vec![7],
+        ]),
+    );
+    assert_eq!(
+        batches,
+        [
+            EmbeddingInput::ArrayOfIntegerArray(vec![vec![1], vec![2]]),
+            EmbeddingInput::ArrayOfIntegerArray(vec![vec![3, 4, 5]]),
+            EmbeddingInput::ArrayOfIntegerArray(vec![vec![6], vec![7]]),
+        ]
+    );
+
+    let options = EmbeddingBatchOptions::default().with_max_inputs(2);
+    let batches = options.split("text-embedding-3-small", EmbeddingInput::from(inputs(5)));
+    assert_eq!(batches.len(), 3);
+    assert_eq!(
+        options.split("text-embedding-3-small", EmbeddingInput::from("one")),
+        [EmbeddingInput::from("one")]
+    );
+}
+
+#[tokio::test]
+async fn create_many_reassembles_in_order_with_retries() {
+    let (url, batches) = serve(2);
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let options = EmbeddingBatchOptions::default()
+        .with_max_inputs(4)
+        .with_requests(
+            BatchRequestOptions::default()
+                .with_concurrency(3)
+                .with_retry_delay(Duration::from_millis(10)),
+        );
+
+    let request = CreateEmbeddingRequestArgs::default()
+        .model("text-embedding-3-small")
+        .input(inputs(10))
+        .build()
+        .unwrap();
+    let mut reports = vec![];
+    let response = client
+        .embeddings()
+        .create_many(request, &options, |progress| {
+            reports.push((progress.completed_requests, progress.completed_inputs))
+        })
+        .await
+        .unwrap();
+
+    let embeddings: Vec<(u32, f32)> = response
+        .data
+        .iter()
+        .map(|embedding| (embedding.index, embedding.embedding[0]))
+        .collect();
+    assert_eq!(
+        embeddings,
+        (0..10).map(|i| (i, i as f32)).collect::<Vec<_>>()
+    );
+    assert_eq!(response.usage.prompt_tokens, 10);
+    assert_eq!(response.usage.total_tokens, 10);
+
+    let mut sizes = batches.lock().unwrap().clone();
+    sizes.sort();
+    assert_eq!(sizes, [2, 4, 4]);
+    assert_eq!(reports.len(), 3);
+    assert_eq!(reports.last(), Some(&(3, 10)));
+}
+
+#[tokio::test]
+async fn create_many_base64() {
+    let (url, _) = serve(0);
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let options = EmbeddingBatchOptions::default().with_max_inputs(2);
+
+    let request = CreateEmbeddingRequestArgs::default()
+        .model("text-embedding-3-small")
+        .input(inputs(5))
+        .encoding_format(EncodingFormat::Base64)
+        .build()
+        .unwrap();
+    assert!(client
+        .embeddings()
+        .create_many(request.clone(), &options, |_| {})
+        .await
+        .is_err());
+
+    let response = client
+        .embeddings()
+        .create_many_base64(request, &options, |_| {})
+        .await
+        .unwrap();
+    let values: Vec<f32> = response
+        .data
+        .into_iter()
+        .map(|embedding| Vec::<f32>::from(embedding.embedding)[0])
+        .collect();
+    assert_eq!(values, [0.0, 1.0, 2.0, 3.0, 4.0]);
+}
+
+#[tokio::test]
+async fn create_many_does_not_retry_invalid_requests() {
+    let client = Client::with_config(OpenAIConfig::new().with_api_base("http://127.0.0.1:9/v1"))
+        .with_request_validation(true);
+    let request = CreateEmbeddingRequestArgs::default()
+        .model("text-embedding-3-small")
+        .input(vec!["a".to_string(), String::new()])
+        .build()
+        .unwrap();
+    let error = client
+        .embeddings()
+        .create_many(request, &EmbeddingBatchOptions::default(), |_| {})
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, async_openai_wasm::error::OpenAIError::InvalidArguments(details) if details[0].param == "input[1]")
+    );
+}
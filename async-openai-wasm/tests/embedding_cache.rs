@@ -0,0 +1,275 @@
+//! Per-input caching of embeddings, against a local server that embeds each numeric input as its value.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::embedding_batch::EmbeddingBatchOptions;
+use async_openai_wasm::embedding_cache::{
+    CacheFuture, DiskEmbeddingCache, EmbeddingCache, EmbeddingCacheKey, KeyValueStore,
+    KvEmbeddingCache, MemoryEmbeddingCache,
+};
+use async_openai_wasm::types::{CreateEmbeddingRequestArgs, EmbeddingInput, EncodingFormat};
+use async_openai_wasm::Client;
+use base64::engine::{general_purpose, Engine};
+use serde_json::{json, Value};
+
+mod common;
+use common::Response;
+
+/// Serves `/embeddings`. Returns the base url and the inputs of every request.
+fn serve() -> (String, Arc<Mutex<Vec<Vec<String>>>>) {
+    let requests = Arc::new(Mutex::new(vec![]));
+
+    let served = requests.clone();
+    let url = common::serve(move |request| {
+        let request = request.json();
+        let inputs: Vec<String> = match &request["input"] {
+            Value::Array(inputs) => inputs
+                .iter()
+                .map(|input| input.as_str().unwrap().to_string())
+                .collect(),
+            input => vec![input.as_str().unwrap().to_string()],
+        };
+        served.lock().unwrap().push(inputs.clone());
+        let base64 = request["encoding_format"] == "base64";
+        let data: Vec<Value> = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let value: f32 = input.parse().unwrap();
+                let embedding = if base64 {
+                    json!(general_purpose::STANDARD.encode(value.to_le_bytes()))
+                } else {
+                    json!([value])
+                };
+                json!({"index": index, "object": "embedding", "embedding": embedding})
+            })
+            .collect();
+        Response::json(json!({
+            "object": "list",
+            "model": request["model"],
+            "data": data,
+            "usage": {"prompt_tokens": inputs.len(), "total_tokens": inputs.len()}
+        }))
+    });
+
+    (url, requests)
+}
+
+fn inputs(values: &[u32]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+/// Embeds `values` twice, the second time with some new inputs, and checks that only those are sent.
+async fn check_cache(cache: impl EmbeddingCache + 'static) {
+    let (url, requests) = serve();
+    let client =
+        Client::with_config(OpenAIConfig::new().with_api_base(url)).with_embedding_cache(cache);
+
+    let request = |values: &[u32]| {
+        CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-3-small")
+            .input(inputs(values))
+            .build()
+            .unwrap()
+    };
+    let response = client
+        .embeddings()
+        .create(request(&[1, 2, 3]))
+        .await
+        .unwrap();
+    assert_eq!(response.usage.prompt_tokens, 3);
+
+    let response = client
+        .embeddings()
+        .create(request(&[4, 2, 5, 1]))
+        .await
+        .unwrap();
+    let embeddings: Vec<(u32, f32)> = response
+        .data
+        .iter()
+        .map(|embedding| (embedding.index, embedding.embedding[0]))
+        .collect();
+    assert_eq!(embeddings, [(0, 4.0), (1, 2.0), (2, 5.0), (3, 1.0)]);
+    assert_eq!(response.model, "text-embedding-3-small");
+    assert_eq!(response.usage.prompt_tokens, 2);
+
+    let response = client.embeddings().create(request(&[5, 3])).await.unwrap();
+    assert_eq!(response.usage.total_tokens, 0);
+    assert_eq!(response.data[1].embedding, [3.0]);
+
+    assert_eq!(
+        *requests.lock().unwrap(),
+        [inputs(&[1, 2, 3]), inputs(&[4, 5])]
+    );
+}
+
+#[tokio::test]
+async fn memory_cache() {
+    let cache = MemoryEmbeddingCache::new();
+    check_cache(cache.clone()).await;
+    assert_eq!(cache.len(), 5);
+}
+
+#[tokio::test]
+async fn disk_cache() {
+    let directory = std::env::temp_dir().join(format!("embedding-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    check_cache(DiskEmbeddingCache::new(&directory).unwrap()).await;
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 5);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[tokio::test]
+async fn disk_cache_rejects_damaged_files() {
+    let directory =
+        std::env::temp_dir().join(format!("embedding-cache-damaged-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let cache = DiskEmbeddingCache::new(&directory).unwrap();
+    let key = |byte| EmbeddingCacheKey {
+        model: "text-embedding-3-small".into(),
+        dimensions: None,
+        encoding_format: EncodingFormat::Float,
+        hash: [byte; 32],
+    };
+    let keys = [key(1), key(2)];
+    let entries: Vec<_> = keys.iter().map(|key| (key.clone(), vec![1.0; 4])).collect();
+    cache.put(&entries).await.unwrap();
+    // only the renamed files are left
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 2);
+
+    let files: Vec<_> = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    let file = std::fs::read(&files[0]).unwrap();
+    let damaged = [
+        // cut short, as seen by a reader of a partial write
+        file[..file.len() - 4].to_vec(),
+        file[..8].to_vec(),
+        // a flipped bit in a value
+        {
+            let mut file = file.clone();
+            *file.last_mut().unwrap() ^= 1;
+            file
+        },
+    ];
+    for bytes in damaged {
+        std::fs::write(&files[0], bytes).unwrap();
+        let hits = cache.get(&keys).await.unwrap();
+        assert_eq!(
+            hits.iter().filter(|hit| hit.is_none()).count(),
+            1,
+            "{hits:?}"
+        );
+        assert!(hits.iter().flatten().all(|hit| hit == &[1.0; 4]));
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[derive(Default, Clone)]
+struct Store(Arc<Mutex<HashMap<String, Vec<u8>>>>);
+
+impl KeyValueStore for Store {
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<Vec<u8>>> {
+        let value = self.0.lock().unwrap().get(key).cloned();
+        Box::pin(async move { Ok(value) })
+    }
+
+    fn put<'a>(&'a self, key: &'a str, value: Vec<u8>) -> CacheFuture<'a, ()> {
+        self.0.lock().unwrap().insert(key.to_string(), value);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn key_value_cache() {
+    let store = Store::default();
+    check_cache(KvEmbeddingCache::new(store.clone()).with_prefix("test/")).await;
+    let store = store.0.lock().unwrap();
+    assert_eq!(store.len(), 5);
+    assert!(store
+        .keys()
+        .all(|key| key.starts_with("test/text-embedding-3-small/")));
+}
+
+#[tokio::test]
+async fn cache_with_batching_and_base64() {
+    let (url, requests) = serve();
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url))
+        .with_embedding_cache(MemoryEmbeddingCache::new());
+    let options = EmbeddingBatchOptions::default().with_max_inputs(2);
+
+    let request = |values: &[u32]| {
+        CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-3-small")
+            .input(inputs(values))
+            .encoding_format(EncodingFormat::Base64)
+            .build()
+            .unwrap()
+    };
+    client
+        .embeddings()
+        .create_base64(request(&[1, 2]))
+        .await
+        .unwrap();
+    let response = client
+        .embeddings()
+        .create_many_base64(request(&[3, 1, 4, 5, 2, 6]), &options, |_| {})
+        .await
+        .unwrap();
+    let values: Vec<f32> = response
+        .data
+        .into_iter()
+        .map(|embedding| Vec::<f32>::from(embedding.embedding)[0])
+        .collect();
+    assert_eq!(values, [3.0, 1.0, 4.0, 5.0, 2.0, 6.0]);
+    assert_eq!(response.usage.prompt_tokens, 4);
+
+    let mut sizes: Vec<usize> = requests.lock().unwrap().iter().map(Vec::len).collect();
+    sizes.sort();
+    assert_eq!(sizes, [2, 2, 2]);
+
+    // float and base64 embeddings are cached separately
+    let response = client
+        .embeddings()
+        .create(
+            CreateEmbeddingRequestArgs::default()
+                .model("text-embedding-3-small")
+                .input("1")
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.data[0].embedding, [1.0]);
+    assert_eq!(requests.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn keys_per_input() {
+    let request = CreateEmbeddingRequestArgs::default()
+        .model("text-embedding-3-small")
+        .input(EmbeddingInput::ArrayOfIntegerArray(vec![
+            vec![1, 2],
+            vec![3],
+            vec![1, 2],
+        ]))
+        .dimensions(256u32)
+        .build()
+        .unwrap();
+    let keys = EmbeddingCacheKey::for_request(&request);
+    assert_eq!(keys.len(), 3);
+    assert_eq!(keys[0], keys[2]);
+    assert_ne!(keys[0], keys[1]);
+    assert!(keys[0]
+        .to_string()
+        .starts_with("text-embedding-3-small/256/float/"));
+
+    let text = CreateEmbeddingRequestArgs::default()
+        .model("text-embedding-3-small")
+        .input("12")
+        .build()
+        .unwrap();
+    assert_ne!(EmbeddingCacheKey::for_request(&text)[0].hash, keys[0].hash);
+}
@@ -0,0 +1,104 @@
+//! Contiguous decoding, quantization and the binary format of embedding matrices.
+use async_openai_wasm::types::{CreateBase64EmbeddingResponse, CreateEmbeddingResponse};
+use async_openai_wasm::vector::{EmbeddingMatrix, Quantization, QuantizedMatrix};
+use base64::engine::{general_purpose, Engine};
+use serde_json::json;
+
+fn encode(values: &[f32]) -> String {
+    let bytes: Vec<u8> = values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect();
+    general_purpose::STANDARD.encode(bytes)
+}
+
+fn base64_response(rows: &[(u32, &[f32])]) -> CreateBase64EmbeddingResponse {
+    let data: Vec<_> = rows
+        .iter()
+        .map(|(index, values)| json!({"index": index, "object": "embedding", "embedding": encode(values)}))
+        .collect();
+    serde_json::from_value(json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": data,
+        "usage": {"prompt_tokens": 2, "total_tokens": 2}
+    }))
+    .unwrap()
+}
+
+#[test]
+fn decode_base64_response_in_index_order() {
+    let response = base64_response(&[(1, &[0.5, -0.25, 1.0]), (0, &[1.0, 2.0, 3.0])]);
+    let matrix = EmbeddingMatrix::from_base64_response(&response).unwrap();
+    assert_eq!(matrix.model(), "text-embedding-3-small");
+    assert_eq!(matrix.dimensions(), 3);
+    assert_eq!(matrix.len(), 2);
+    assert_eq!(matrix.row(0), Some(&[1.0, 2.0, 3.0][..]));
+    assert_eq!(matrix.row(1), Some(&[0.5, -0.25, 1.0][..]));
+    assert_eq!(matrix.row(2), None);
+    assert_eq!(matrix.as_slice(), [1.0, 2.0, 3.0, 0.5, -0.25, 1.0]);
+
+    let rows: Vec<Vec<f32>> = response
+        .data
+        .iter()
+        .map(|embedding| embedding.embedding.clone().into())
+        .collect();
+    assert_eq!(rows[0], matrix.row(1).unwrap());
+
+    let mismatched = base64_response(&[(0, &[1.0, 2.0]), (1, &[1.0])]);
+    assert!(EmbeddingMatrix::from_base64_response(&mismatched).is_err());
+}
+
+#[test]
+fn from_float_response() {
+    let response: CreateEmbeddingResponse = serde_json::from_value(json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": [
+            {"index": 1, "object": "embedding", "embedding": [3.0, 4.0]},
+            {"index": 0, "object": "embedding", "embedding": [1.0, 2.0]}
+        ],
+        "usage": {"prompt_tokens": 2, "total_tokens": 2}
+    }))
+    .unwrap();
+    let matrix = EmbeddingMatrix::try_from(&response).unwrap();
+    let rows: Vec<&[f32]> = matrix.rows().collect();
+    assert_eq!(rows, [&[1.0, 2.0][..], &[3.0, 4.0][..]]);
+    assert!(EmbeddingMatrix::new("m", 3, vec![1.0; 4]).is_err());
+}
+
+#[test]
+fn quantization_round_trips() {
+    let values: Vec<f32> = (0..64).map(|i| ((i as f32) * 0.37).sin() * 0.2).collect();
+    let matrix = EmbeddingMatrix::new("text-embedding-3-small", 16, values.clone()).unwrap();
+
+    for (quantization, tolerance) in [
+        (Quantization::F32, 0.0),
+        (Quantization::F16, 1e-3),
+        (Quantization::Int8, 0.2 / 127.0),
+    ] {
+        let quantized = matrix.quantize(quantization);
+        assert_eq!(quantized.quantization(), quantization);
+        assert_eq!(quantized.len(), 4);
+        assert_eq!(quantized.dimensions(), 16);
+
+        let restored = quantized.dequantize();
+        for (a, b) in restored.as_slice().iter().zip(&values) {
+            assert!((a - b).abs() <= tolerance, "{quantization:?}: {a} != {b}");
+        }
+        assert_eq!(quantized.row(3).unwrap(), restored.row(3).unwrap());
+        assert_eq!(quantized.row(4), None);
+
+        let bytes = quantized.to_bytes();
+        let decoded = QuantizedMatrix::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, quantized);
+        assert_eq!(decoded.model(), "text-embedding-3-small");
+        assert_eq!(EmbeddingMatrix::from_bytes(&bytes).unwrap(), restored);
+        assert!(QuantizedMatrix::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    let f32_size = matrix.to_bytes().len();
+    let int8_size = matrix.quantize(Quantization::Int8).to_bytes().len();
+    assert!(int8_size * 2 < f32_size);
+    assert!(EmbeddingMatrix::from_bytes(b"OAVI").is_err());
+}
@@ -0,0 +1,247 @@
+//! Rewriting chat requests and responses between legacy functions and tools.
+#![allow(deprecated)]
+use async_openai_wasm::function_calling::FunctionCallingStyle;
+use async_openai_wasm::types::{
+    ChatCompletionFunctionCall, ChatCompletionRequestMessage, ChatCompletionToolChoiceOption,
+    CreateChatCompletionRequest, CreateChatCompletionResponse, CreateChatCompletionStreamResponse,
+    FinishReason,
+};
+use serde_json::json;
+
+fn request(value: serde_json::Value) -> CreateChatCompletionRequest {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn functions_to_tools_and_back() {
+    let mut request = request(json!({
+        "model": "gpt-4o",
+        "messages": [
+            {"role": "user", "content": "Weather in Paris?"},
+            {"role": "assistant", "content": null,
+             "function_call": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+            {"role": "function", "name": "get_weather", "content": "sunny"}
+        ],
+        "functions": [{"name": "get_weather", "parameters": {"type": "object"}}],
+        "function_call": {"name": "get_weather"}
+    }));
+
+    assert!(!FunctionCallingStyle::Functions
+        .convert_request(&mut request)
+        .unwrap());
+    assert!(FunctionCallingStyle::Tools
+        .convert_request(&mut request)
+        .unwrap());
+
+    assert!(request.functions.is_none() && request.function_call.is_none());
+    assert_eq!(
+        request.tools.as_ref().unwrap()[0].function.name,
+        "get_weather"
+    );
+    assert_eq!(request.parallel_tool_calls, Some(false));
+    assert!(matches!(
+        &request.tool_choice,
+        Some(ChatCompletionToolChoiceOption::Named(choice)) if choice.function.name == "get_weather"
+    ));
+    let ChatCompletionRequestMessage::Assistant(assistant) = &request.messages[1] else {
+        panic!("expected an assistant message");
+    };
+    let call_id = assistant.tool_calls.as_ref().unwrap()[0].id.clone();
+    assert!(matches!(
+        &request.messages[2],
+        ChatCompletionRequestMessage::Tool(tool) if tool.tool_call_id == call_id
+    ));
+
+    assert!(FunctionCallingStyle::Functions
+        .convert_request(&mut request)
+        .unwrap());
+    assert!(request.tools.is_none() && request.parallel_tool_calls.is_none());
+    assert_eq!(
+        request.function_call,
+        Some(ChatCompletionFunctionCall::Function {
+            name: "get_weather".into()
+        })
+    );
+    assert!(matches!(
+        &request.messages[2],
+        ChatCompletionRequestMessage::Function(function) if function.name == "get_weather"
+            && function.content.as_deref() == Some("sunny")
+    ));
+}
+
+#[test]
+fn parallel_tool_calls_are_split() {
+    let mut request = request(json!({
+        "model": "gpt-4o",
+        "messages": [
+            {"role": "user", "content": "Weather in Paris and Rome?"},
+            {"role": "assistant", "tool_calls": [
+                {"id": "a", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"}},
+                {"id": "b", "type": "function", "function": {"name": "time", "arguments": "{\"city\":\"Rome\"}"}}
+            ]},
+            {"role": "tool", "tool_call_id": "a", "content": "sunny"},
+            {"role": "tool", "tool_call_id": "b", "content": [{"type": "text", "text": "noon"}]}
+        ],
+        "tools": [
+            {"type": "function", "function": {"name": "weather"}},
+            {"type": "function", "function": {"name": "time"}}
+        ]
+    }));
+    FunctionCallingStyle::Functions
+        .convert_request(&mut request)
+        .unwrap();
+
+    let roles: Vec<&str> = request
+        .messages
+        .iter()
+        .map(|message| match message {
+            ChatCompletionRequestMessage::User(_) => "user",
+            ChatCompletionRequestMessage::Assistant(_) => "assistant",
+            ChatCompletionRequestMessage::Function(_) => "function",
+            _ => "other",
+        })
+        .collect();
+    assert_eq!(
+        roles,
+        ["user", "assistant", "function", "assistant", "function"]
+    );
+    assert!(matches!(
+        &request.messages[4],
+        ChatCompletionRequestMessage::Function(function) if function.name == "time"
+            && function.content.as_deref() == Some("noon")
+    ));
+    assert_eq!(request.functions.as_ref().unwrap().len(), 2);
+
+    let mut required = self::request(json!({
+        "model": "gpt-4o",
+        "messages": [{"role": "user", "content": "hi"}],
+        "tools": [
+            {"type": "function", "function": {"name": "weather"}},
+            {"type": "function", "function": {"name": "time"}}
+        ],
+        "tool_choice": "required"
+    }));
+    assert!(FunctionCallingStyle::Functions
+        .convert_request(&mut required)
+        .is_err());
+}
+
+#[test]
+fn split_tool_calls_follow_their_results() {
+    let mut request = request(json!({
+        "model": "gpt-4o",
+        "messages": [
+            {"role": "user", "content": "Weather in Paris and Rome?"},
+            {"role": "assistant", "tool_calls": [
+                {"id": "a", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"}},
+                {"id": "b", "type": "function", "function": {"name": "weather", "arguments": "{\"city\":\"Rome\"}"}}
+            ]},
+            {"role": "tool", "tool_call_id": "b", "content": [
+                {"type": "text", "text": "Rome:"}, {"type": "text", "text": "rainy"}
+            ]},
+            {"role": "tool", "tool_call_id": "a", "content": "Paris: sunny"},
+            {"role": "user", "content": "Thanks"}
+        ],
+        "tools": [{"type": "function", "function": {"name": "weather"}}]
+    }));
+    let mut missing = request.clone();
+    FunctionCallingStyle::Functions
+        .convert_request(&mut request)
+        .unwrap();
+
+    let messages: Vec<String> = request
+        .messages
+        .iter()
+        .map(|message| match message {
+            ChatCompletionRequestMessage::User(_) => "user".into(),
+            ChatCompletionRequestMessage::Assistant(assistant) => {
+                assistant.function_call.as_ref().unwrap().arguments.clone()
+            }
+            ChatCompletionRequestMessage::Function(function) => function.content.clone().unwrap(),
+            _ => "other".into(),
+        })
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "user",
+            "{\"city\":\"Paris\"}",
+            "Paris: sunny",
+            "{\"city\":\"Rome\"}",
+            "Rome:\nrainy",
+            "user"
+        ]
+    );
+
+    // a call without a result cannot be split
+    missing.messages.remove(3);
+    assert!(FunctionCallingStyle::Functions
+        .convert_request(&mut missing)
+        .is_err());
+}
+
+#[test]
+fn responses_are_restored() {
+    let mut response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": null,
+                        "function_call": {"name": "weather", "arguments": "{}"}},
+            "finish_reason": "function_call",
+            "logprobs": null
+        }]
+    }))
+    .unwrap();
+    FunctionCallingStyle::Functions.restore_response(&mut response);
+    let choice = &response.choices[0];
+    assert_eq!(choice.finish_reason, Some(FinishReason::ToolCalls));
+    assert!(choice.message.function_call.is_none());
+    assert_eq!(
+        choice.message.tool_calls.as_ref().unwrap()[0].function.name,
+        "weather"
+    );
+
+    FunctionCallingStyle::Tools.restore_response(&mut response);
+    let choice = &response.choices[0];
+    assert_eq!(choice.finish_reason, Some(FinishReason::FunctionCall));
+    assert_eq!(
+        choice.message.function_call.as_ref().unwrap().name,
+        "weather"
+    );
+
+    let chunk = |delta: serde_json::Value,
+                 finish_reason: Option<&str>|
+     -> CreateChatCompletionStreamResponse {
+        serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "gpt-4o",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason, "logprobs": null}]
+        }))
+        .unwrap()
+    };
+    let mut first = chunk(
+        json!({"function_call": {"name": "weather", "arguments": ""}}),
+        None,
+    );
+    let mut next = chunk(json!({"function_call": {"arguments": "{}"}}), None);
+    let mut last = chunk(json!({}), Some("function_call"));
+    for chunk in [&mut first, &mut next, &mut last] {
+        FunctionCallingStyle::Functions.restore_chunk(chunk);
+    }
+    let first = &first.choices[0].delta.tool_calls.as_ref().unwrap()[0];
+    assert!(first.id.is_some());
+    assert_eq!(
+        first.function.as_ref().unwrap().name.as_deref(),
+        Some("weather")
+    );
+    assert!(next.choices[0].delta.tool_calls.as_ref().unwrap()[0]
+        .id
+        .is_none());
+    assert_eq!(last.choices[0].finish_reason, Some(FinishReason::ToolCalls));
+}
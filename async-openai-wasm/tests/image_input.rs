@@ -0,0 +1,98 @@
+//! Image inputs from raw bytes.
+use async_openai_wasm::types::{
+    CreateFileRequest, FilePurpose, ImageDetail, ImageMetadata, ImageMimeType, ImageUrl,
+    InputSource, MessageContentInput,
+};
+
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    bytes.extend(width.to_be_bytes());
+    bytes.extend(height.to_be_bytes());
+    bytes.extend([8, 6, 0, 0, 0]);
+    bytes
+}
+
+#[test]
+fn sniffs_format_and_size() {
+    let png = ImageMetadata::from_bytes(&png_header(640, 480)).unwrap();
+    assert_eq!(png.mime_type, ImageMimeType::Png);
+    assert_eq!((png.width, png.height), (640, 480));
+
+    let gif = ImageMetadata::from_bytes(b"GIF89a\x20\x03\x58\x02\0\0").unwrap();
+    assert_eq!(gif.mime_type, ImageMimeType::Gif);
+    assert_eq!((gif.width, gif.height), (800, 600));
+
+    // SOI, an APP0 segment, then a baseline SOF0 for a 300x200 image
+    let jpeg = [
+        0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0xC8,
+        0x01, 0x2C, 0x03,
+    ];
+    let jpeg = ImageMetadata::from_bytes(&jpeg).unwrap();
+    assert_eq!(jpeg.mime_type, ImageMimeType::Jpeg);
+    assert_eq!((jpeg.width, jpeg.height), (300, 200));
+
+    let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+    webp.extend([0xFF, 0x0F, 0x00, 0xFF, 0x07, 0x00]);
+    let webp = ImageMetadata::from_bytes(&webp).unwrap();
+    assert_eq!(webp.mime_type, ImageMimeType::Webp);
+    assert_eq!((webp.width, webp.height), (4096, 2048));
+
+    assert!(ImageMetadata::from_bytes(b"%PDF-1.7").is_err());
+    assert!(ImageMetadata::from_bytes(&png_header(1, 1)[..18]).is_err());
+}
+
+#[test]
+fn data_urls_and_token_estimates() {
+    let image_url = ImageUrl::from_bytes(png_header(2048, 4096), ImageDetail::High).unwrap();
+    assert!(image_url
+        .url
+        .starts_with("data:image/png;base64,iVBORw0KGgo"));
+    assert_eq!(image_url.detail, Some(ImageDetail::High));
+    // scaled to 1024x2048, then 768x1536: 2x3 tiles
+    assert_eq!(image_url.estimated_tokens(), Some(85 + 170 * 6));
+    assert_eq!(ImageDetail::High.scaled_size(2048, 4096), (768, 1536));
+    assert_eq!(ImageDetail::Low.scaled_size(2048, 4096), (256, 512));
+
+    let source = InputSource::VecU8 {
+        filename: "photo".into(),
+        vec: png_header(100, 100),
+    };
+    let low = ImageUrl::from_input_source(&source, ImageDetail::Low).unwrap();
+    assert_eq!(low.estimated_tokens(), Some(85));
+    assert_eq!(
+        ImageUrl::from("https://example.com/a.png").estimated_tokens(),
+        None
+    );
+
+    let content =
+        MessageContentInput::image_from_bytes(png_header(1, 1), ImageDetail::Auto).unwrap();
+    assert!(matches!(content, MessageContentInput::ImageUrl(_)));
+
+    let upload = CreateFileRequest::vision_image(png_header(1, 1)).unwrap();
+    assert_eq!(upload.purpose, FilePurpose::Vision);
+    assert!(matches!(
+        upload.file.source,
+        InputSource::Bytes { ref filename, .. } if filename == "image.png"
+    ));
+}
+
+#[cfg(feature = "image-resize")]
+#[test]
+fn downscales_to_detail_limits() {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(1024, 256)
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    let png = png.into_inner();
+
+    let image_url = ImageUrl::from_bytes_downscaled(&png, ImageDetail::Low).unwrap();
+    let metadata = image_url.metadata().unwrap();
+    assert_eq!((metadata.width, metadata.height), (512, 128));
+
+    // small enough images are passed through
+    let image_url = ImageUrl::from_bytes_downscaled(&png, ImageDetail::High).unwrap();
+    assert_eq!(
+        image_url,
+        ImageUrl::from_bytes(&png, ImageDetail::High).unwrap()
+    );
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "json-schema")]
+//! Strict-mode schema generation from `schemars::JsonSchema` types.
+use std::collections::HashMap;
+
+use async_openai_wasm::types::{FunctionObject, ResponseFormat, ResponseFormatJsonSchema};
+use schemars::JsonSchema;
+use serde_json::json;
+
+/// Get the current weather in a given location
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct GetWeather {
+    /// The city and state, e.g. San Francisco, CA
+    location: String,
+    unit: Option<Unit>,
+    days: Option<u8>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct WithMap {
+    counts: HashMap<String, u32>,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+struct Calc {
+    /// The expression to evaluate
+    expr: Expr,
+}
+
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+enum Expr {
+    Add {
+        /// The left operand
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Lit {
+        value: i32,
+    },
+}
+
+#[test]
+fn function_object_from_type() {
+    let function = FunctionObject::from_type::<GetWeather>().unwrap();
+    assert_eq!(function.name, "GetWeather");
+    assert_eq!(
+        function.description.as_deref(),
+        Some("Get the current weather in a given location")
+    );
+    assert_eq!(function.strict, Some(true));
+
+    let parameters = function.parameters.unwrap();
+    assert_eq!(parameters["additionalProperties"], json!(false));
+    assert_eq!(parameters["required"], json!(["location", "unit", "days"]));
+    assert_eq!(parameters["properties"]["days"]["type"], json!(["integer", "null"]));
+    assert!(parameters["properties"]["days"].get("format").is_none());
+    assert!(parameters.get("$schema").is_none());
+
+    let unit = &parameters["properties"]["unit"];
+    let variants = unit["anyOf"].as_array().unwrap();
+    assert!(variants.contains(&json!({ "type": "null" })));
+}
+
+#[test]
+fn response_format_from_type() {
+    let ResponseFormat::JsonSchema { json_schema } = ResponseFormat::json_schema::<GetWeather>().unwrap()
+    else {
+        panic!("expected json_schema response format");
+    };
+    assert_eq!(json_schema.name, "GetWeather");
+    assert_eq!(json_schema.strict, Some(true));
+}
+
+#[test]
+fn unsupported_constructs_are_rejected() {
+    assert!(FunctionObject::from_type::<WithMap>().is_err());
+    // root must be an object
+    assert!(ResponseFormat::json_schema::<Vec<String>>().is_err());
+    assert!(ResponseFormat::json_schema::<Unit>().is_err());
+}
+
+#[test]
+fn recursive_references_with_descriptions() {
+    let json_schema = ResponseFormatJsonSchema::from_type::<Calc>().unwrap();
+    let schema = json_schema.schema.unwrap();
+    assert_eq!(
+        schema["properties"]["expr"],
+        json!({ "$ref": "#/$defs/Expr" })
+    );
+    let add = &schema["$defs"]["Expr"]["anyOf"][0]["properties"]["Add"];
+    assert_eq!(
+        add["properties"]["left"],
+        json!({ "$ref": "#/$defs/Expr" })
+    );
+}
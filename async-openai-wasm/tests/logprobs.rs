@@ -0,0 +1,149 @@
+//! Scoring and classification helpers on chat completion logprobs.
+use async_openai_wasm::stream::ChatCompletionStreamAccumulator;
+use async_openai_wasm::types::{ChatChoiceLogprobs, CreateChatCompletionStreamResponse};
+use serde_json::{json, Value};
+
+fn token(token: &str, logprob: f32, top: &[(&str, f32)]) -> Value {
+    let top: Vec<Value> = top
+        .iter()
+        .map(|(token, logprob)| json!({"token": token, "logprob": logprob, "bytes": null}))
+        .collect();
+    json!({"token": token, "logprob": logprob, "bytes": null, "top_logprobs": top})
+}
+
+fn logprobs(tokens: Vec<Value>) -> ChatChoiceLogprobs {
+    serde_json::from_value(json!({"content": tokens, "refusal": null})).unwrap()
+}
+
+#[test]
+fn likelihood_perplexity_and_alternatives() {
+    let half = 0.5f32.ln();
+    let logprobs = logprobs(vec![
+        token("Hello", half, &[("Hi", 0.25f32.ln()), ("Hello", half)]),
+        token("!", 0.0, &[("!", 0.0)]),
+    ]);
+
+    assert!((logprobs.log_likelihood() - f64::from(half)).abs() < 1e-6);
+    assert!((logprobs.perplexity().unwrap() - 2f64.sqrt()).abs() < 1e-6);
+
+    let probabilities = logprobs.token_probabilities();
+    assert_eq!(probabilities[0].0, "Hello");
+    assert!((probabilities[0].1 - 0.5).abs() < 1e-6);
+
+    let top = logprobs.top_k(1);
+    assert_eq!(top[0][0].token, "Hello");
+    assert_eq!(top[1][0].token, "!");
+
+    assert_eq!(self::logprobs(vec![]).perplexity(), None);
+}
+
+#[test]
+fn classify_normalizes_over_labels() {
+    // "negative" is split into "neg" + "ative", and was generated
+    let logprobs = logprobs(vec![
+        token(
+            " neg",
+            0.4f32.ln(),
+            &[
+                (" Positive", 0.3f32.ln()),
+                (" neg", 0.4f32.ln()),
+                (" neutral", 0.1f32.ln()),
+                (" other", 0.2f32.ln()),
+            ],
+        ),
+        token(
+            "ative",
+            0.5f32.ln(),
+            &[("ative", 0.5f32.ln()), ("ated", 0.5f32.ln())],
+        ),
+    ]);
+
+    let probabilities = logprobs
+        .classify(&["positive", "negative", "neutral"])
+        .unwrap();
+    // 0.3, 0.4 * 0.5 and 0.1, normalized
+    let expected = [0.5, 1.0 / 3.0, 1.0 / 6.0];
+    for (probability, expected) in probabilities.iter().zip(expected) {
+        assert!((probability - expected).abs() < 1e-6, "{probabilities:?}");
+    }
+
+    assert_eq!(logprobs.classify(&["maybe"]), None);
+}
+
+#[test]
+fn classify_counts_shared_prefixes_once() {
+    // "no" is a label and the start of "none", and is followed by "ne" a quarter of the time
+    let logprobs = logprobs(vec![
+        token(
+            "no",
+            0.6f32.ln(),
+            &[
+                ("no", 0.6f32.ln()),
+                ("yes", 0.3f32.ln()),
+                ("non", 0.1f32.ln()),
+            ],
+        ),
+        token(
+            ".",
+            0.75f32.ln(),
+            &[(".", 0.75f32.ln()), ("ne", 0.25f32.ln())],
+        ),
+    ]);
+    let probabilities = logprobs.classify(&["no", "none", "yes"]).unwrap();
+    // 0.6 * 0.75, 0.6 * 0.25 + 0.1 and 0.3, which already sum to 1
+    let expected = [0.45, 0.25, 0.3];
+    for (probability, expected) in probabilities.iter().zip(expected) {
+        assert!((probability - expected).abs() < 1e-6, "{probabilities:?}");
+    }
+
+    // without a next token, "yes" is as likely to be the label as the start of "yesterday"
+    let logprobs = self::logprobs(vec![token(
+        "yes",
+        0.8f32.ln(),
+        &[("yes", 0.8f32.ln()), ("no", 0.2f32.ln())],
+    )]);
+    let probabilities = logprobs.classify(&["yes", "yesterday", "no"]).unwrap();
+    let expected = [0.4, 0.4, 0.2];
+    for (probability, expected) in probabilities.iter().zip(expected) {
+        assert!((probability - expected).abs() < 1e-6, "{probabilities:?}");
+    }
+}
+
+fn chunk(content: &str, logprobs: Value) -> CreateChatCompletionStreamResponse {
+    serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "delta": {"content": content},
+            "finish_reason": null,
+            "logprobs": logprobs
+        }]
+    }))
+    .unwrap()
+}
+
+#[test]
+fn accumulator_merges_logprobs() {
+    let mut accumulator = ChatCompletionStreamAccumulator::new();
+    accumulator.push(&chunk(
+        "Hello",
+        json!({"content": [token("Hello", -0.1, &[])], "refusal": null}),
+    ));
+    accumulator.push(&chunk(
+        " world",
+        json!({"content": [token(" world", -0.2, &[])], "refusal": null}),
+    ));
+
+    let response = accumulator.response();
+    let logprobs = response.choices[0].logprobs.as_ref().unwrap();
+    let tokens: Vec<&str> = logprobs
+        .tokens()
+        .iter()
+        .map(|token| token.token.as_str())
+        .collect();
+    assert_eq!(tokens, ["Hello", " world"]);
+    assert!((logprobs.log_likelihood() + 0.3).abs() < 1e-6);
+}
@@ -0,0 +1,113 @@
+//! Parsing of chat completion responses into Rust types, without calling the API.
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::types::{
+    CreateChatCompletionResponse, FinishReason, ParseOptions, ParsedChatCompletion,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Answer {
+    value: i32,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "name", content = "arguments", rename_all = "snake_case")]
+enum Tools {
+    GetWeather { location: String },
+}
+
+fn response(message: serde_json::Value, finish_reason: &str) -> CreateChatCompletionResponse {
+    serde_json::from_value(json!({
+        "id": "chatcmpl-123",
+        "object": "chat.completion",
+        "created": 1728933352,
+        "model": "gpt-4o-2024-08-06",
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": finish_reason,
+            "logprobs": null
+        }],
+        "usage": null
+    }))
+    .unwrap()
+}
+
+fn options() -> ParseOptions {
+    ParseOptions {
+        parse_content: true,
+        strict_functions: vec!["get_weather".into()],
+    }
+}
+
+#[test]
+fn parses_content_and_refusal() {
+    let parsed = ParsedChatCompletion::<Answer>::parse(
+        response(
+            json!({"role": "assistant", "content": "{\"value\": 2}", "refusal": null}),
+            "stop",
+        ),
+        &options(),
+    )
+    .unwrap();
+    assert_eq!(parsed.choices[0].message.parsed, Some(Answer { value: 2 }));
+
+    let refused = ParsedChatCompletion::<Answer>::parse(
+        response(
+            json!({"role": "assistant", "content": null, "refusal": "I can't help with that."}),
+            "stop",
+        ),
+        &options(),
+    )
+    .unwrap();
+    let message = &refused.choices[0].message;
+    assert_eq!(message.parsed, None);
+    assert_eq!(
+        message.refusal.as_ref().unwrap().to_string(),
+        "I can't help with that."
+    );
+}
+
+#[test]
+fn parses_tool_calls_into_registered_types() {
+    let parsed = ParsedChatCompletion::<Answer, Tools>::parse_with_tools(
+        response(
+            json!({
+                "role": "assistant",
+                "content": null,
+                "refusal": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"location\": \"Paris\"}"}
+                }]
+            }),
+            "tool_calls",
+        ),
+        &options(),
+    )
+    .unwrap();
+    let tool_call = &parsed.choices[0].message.tool_calls.as_ref().unwrap()[0];
+    assert_eq!(
+        tool_call.parsed_arguments,
+        Some(Tools::GetWeather {
+            location: "Paris".into()
+        })
+    );
+}
+
+#[test]
+fn truncated_responses_are_errors() {
+    let result = ParsedChatCompletion::<Answer>::parse(
+        response(
+            json!({"role": "assistant", "content": "{\"val", "refusal": null}),
+            "length",
+        ),
+        &options(),
+    );
+    assert!(matches!(
+        result,
+        Err(OpenAIError::IncompleteResponse(FinishReason::Length))
+    ));
+}
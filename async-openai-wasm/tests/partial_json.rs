@@ -0,0 +1,182 @@
+//! Incremental parsing of streamed JSON and the partial JSON stream adapter.
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::partial_json::{parse_partial_json, PartialJsonParser};
+use async_openai_wasm::stream::ChatCompletionStreamExt;
+use async_openai_wasm::types::CreateChatCompletionStreamResponse;
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+
+#[test]
+fn snapshots_close_open_containers() {
+    assert_eq!(parse_partial_json(""), None);
+    assert_eq!(parse_partial_json("```json\n{"), Some(json!({})));
+    assert_eq!(parse_partial_json(r#"{"a": 1, "b"#), Some(json!({"a": 1})));
+    assert_eq!(parse_partial_json(r#"{"a": 1, "b": "#), Some(json!({"a": 1})));
+    assert_eq!(parse_partial_json(r#"{"a": [1, 2.5, tr"#), Some(json!({"a": [1, 2.5]})));
+    assert_eq!(parse_partial_json(r#"{"a": [1, -"#), Some(json!({"a": [1]})));
+    // numbers appear once they end, not digit by digit
+    assert_eq!(parse_partial_json(r#"{"a": [1, 23"#), Some(json!({"a": [1]})));
+    assert_eq!(parse_partial_json(r#"{"a": 1, "b": 2.5"#), Some(json!({"a": 1})));
+    assert_eq!(parse_partial_json(r#"{"a": 1, "b": 2.5}"#), Some(json!({"a": 1, "b": 2.5})));
+    assert_eq!(
+        parse_partial_json(r#"{"a": {"b": [{"c": "x\"yé"#),
+        Some(json!({"a": {"b": [{"c": "x\"yé"}]}}))
+    );
+    assert_eq!(
+        parse_partial_json(r#"{"emoji": "🦀", "n": null} trailing"#),
+        Some(json!({"emoji": "🦀", "n": null}))
+    );
+}
+
+#[test]
+fn parses_across_arbitrary_fragment_boundaries() {
+    let document = r#"{"name": "café \ud83e\udd80", "scores": [10, 20], "ok": true, "nested": {"x": -1.5e2}}"#;
+    for (split, _) in document.char_indices() {
+        let mut parser = PartialJsonParser::new();
+        parser.push(&document[..split]).unwrap();
+        parser.push(&document[split..]).unwrap();
+        assert!(parser.is_complete());
+        assert_eq!(
+            parser.snapshot(),
+            Some(serde_json::from_str(document).unwrap())
+        );
+    }
+}
+
+#[test]
+fn invalid_input_keeps_last_snapshot() {
+    let mut parser = PartialJsonParser::new();
+    parser.push(r#"{"a": 1, "b": x"#).unwrap_err();
+    assert!(parser.is_invalid());
+    assert_eq!(parser.snapshot(), Some(json!({"a": 1})));
+}
+
+#[test]
+fn misplaced_separators_are_rejected() {
+    for document in [
+        r#"{"a" "b"}"#,
+        r#"{"a":1 "b":2}"#,
+        r#"[,1,,2]"#,
+        r#"[1,,2]"#,
+        r#"[1 2]"#,
+        r#"{"a"::1}"#,
+        r#"{"a",1}"#,
+        r#"{,"a":1}"#,
+        r#"{"a":1,}"#,
+        r#"[1,]"#,
+        r#"{"a":}"#,
+    ] {
+        let mut parser = PartialJsonParser::new();
+        assert!(parser.push(document).is_err(), "{document}");
+    }
+}
+
+#[test]
+fn incomplete_strings_can_be_excluded() {
+    let mut parser = PartialJsonParser::new().with_incomplete_strings(false);
+    parser.push(r#"{"a": "b", "c": "d"#).unwrap();
+    assert_eq!(parser.snapshot(), Some(json!({"a": "b"})));
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Recipe {
+    title: Option<String>,
+    steps: Option<Vec<String>>,
+}
+
+fn chunk(content: Option<&str>, arguments: Option<&str>) -> CreateChatCompletionStreamResponse {
+    let tool_calls = arguments.map(|arguments| {
+        json!([{"index": 0, "id": "call_1", "type": "function",
+                "function": {"name": "save", "arguments": arguments}}])
+    });
+    serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": 0,
+            "delta": {"content": content, "tool_calls": tool_calls},
+            "finish_reason": null,
+            "logprobs": null
+        }]
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn partial_json_stream() {
+    let chunks = vec![
+        Ok(chunk(Some(r#"{"title": "Pan"#), None)),
+        Ok(chunk(Some(r#"cakes", "steps": ["Mix"#), None)),
+        Ok(chunk(None, Some(r#"{"path": "/tm"#))),
+    ];
+    // a chunk which is pending gives the stream a chance to snapshot the ones before it
+    let mut stream = Box::pin(
+        futures::stream::iter(chunks)
+            .then(|chunk| async {
+                tokio::task::yield_now().await;
+                chunk
+            })
+            .partial_json(),
+    );
+
+    let first = stream.next().await.unwrap().unwrap();
+    assert_eq!(
+        first[0].content_as::<Recipe>(),
+        Some(Recipe {
+            title: Some("Pan".into()),
+            steps: None
+        })
+    );
+
+    let second = stream.next().await.unwrap().unwrap();
+    assert_eq!(
+        second[0].content_as::<Recipe>(),
+        Some(Recipe {
+            title: Some("Pancakes".into()),
+            steps: Some(vec!["Mix".into()])
+        })
+    );
+
+    let third = stream.next().await.unwrap().unwrap();
+    assert_eq!(third[0].tool_calls[0].arguments, Some(json!({"path": "/tm"})));
+    assert!(stream.next().await.is_none());
+
+    let response = stream.accumulator().response();
+    let message = &response.choices[0].message;
+    assert_eq!(
+        message.content.as_deref(),
+        Some(r#"{"title": "Pancakes", "steps": ["Mix"#)
+    );
+    assert_eq!(
+        message.tool_calls.as_ref().unwrap()[0].function.arguments,
+        r#"{"path": "/tm"#
+    );
+}
+
+#[tokio::test]
+async fn partial_json_stream_snapshots_ready_chunks_once() {
+    let chunks = vec![
+        Ok(chunk(Some(r#"{"title": "Pan"#), None)),
+        Ok(chunk(Some(r#"cakes", "steps": ["Mix"#), None)),
+        Err(OpenAIError::StreamError("connection reset".into())),
+    ];
+    let mut stream = futures::stream::iter(chunks).partial_json();
+
+    let choices = stream.next().await.unwrap().unwrap();
+    assert_eq!(choices.len(), 1);
+    assert_eq!(
+        choices[0].content_as::<Recipe>(),
+        Some(Recipe {
+            title: Some("Pancakes".into()),
+            steps: Some(vec!["Mix".into()])
+        })
+    );
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(OpenAIError::StreamError(_)))
+    ));
+    assert!(stream.next().await.is_none());
+}
@@ -0,0 +1,272 @@
+//! Speech of long inputs, against a local server whose audio lasts 10 ms per input character.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_openai_wasm::audio_format::{concat, duration, wav_header, Wav};
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::speech_batch::SpeechBatchOptions;
+use async_openai_wasm::types::{CreateSpeechRequestArgs, SpeechResponseFormat, Voice};
+use async_openai_wasm::{BatchRequestOptions, Client};
+
+mod common;
+use common::Response;
+
+fn pcm(chars: usize) -> Vec<u8> {
+    vec![1; chars * 240 * 2]
+}
+
+fn wav(chars: usize) -> Vec<u8> {
+    // streamed WAV files have a placeholder size
+    let mut wav = wav_header(1, 24_000, 16, u32::MAX);
+    wav.extend(pcm(chars));
+    wav
+}
+
+/// An ID3 tag, a Xing frame and one 1152 sample MPEG-1 Layer III frame per character.
+fn mp3(chars: usize) -> Vec<u8> {
+    let frame = |xing: bool| {
+        let mut frame = vec![0; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x64]);
+        if xing {
+            frame[36..40].copy_from_slice(b"Xing");
+        }
+        frame
+    };
+    let mut mp3 = b"ID3\x04\x00\x00\x00\x00\x00\x05tags!".to_vec();
+    mp3.extend(frame(true));
+    for _ in 0..chars {
+        mp3.extend(frame(false));
+    }
+    mp3
+}
+
+fn ogg_crc(bytes: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in bytes {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn ogg_page(header_type: u8, granule: u64, serial: u32, sequence: u32, body: &[u8]) -> Vec<u8> {
+    let mut page = b"OggS\x00".to_vec();
+    page.push(header_type);
+    page.extend(granule.to_le_bytes());
+    page.extend(serial.to_le_bytes());
+    page.extend(sequence.to_le_bytes());
+    page.extend([0; 4]);
+    let mut segments = vec![255; body.len() / 255];
+    segments.push((body.len() % 255) as u8);
+    page.push(segments.len() as u8);
+    page.extend(segments);
+    page.extend(body);
+    let crc = ogg_crc(&page);
+    page[22..26].copy_from_slice(&crc.to_le_bytes());
+    page
+}
+
+/// OpusHead and OpusTags pages and two audio pages, 480 samples at 48 kHz per character after a pre-skip of 312.
+fn opus(chars: usize, serial: u32) -> Vec<u8> {
+    let mut head = b"OpusHead\x01\x01".to_vec();
+    head.extend(312u16.to_le_bytes());
+    head.extend(24_000u32.to_le_bytes());
+    head.extend([0, 0, 0]);
+    let mut opus = ogg_page(0x02, 0, serial, 0, &head);
+    opus.extend(ogg_page(
+        0,
+        0,
+        serial,
+        1,
+        b"OpusTags\x00\x00\x00\x00\x00\x00\x00\x00",
+    ));
+    let half = 312 + 480 * (chars as u64 / 2);
+    opus.extend(ogg_page(0, half, serial, 2, &[7; 300]));
+    opus.extend(ogg_page(
+        0x04,
+        312 + 480 * chars as u64,
+        serial,
+        3,
+        &[7; 20],
+    ));
+    opus
+}
+
+/// Serves `/audio/speech`, returning the inputs of every request.
+fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+    let inputs = Arc::new(Mutex::new(vec![]));
+    let served = inputs.clone();
+    let url = common::serve(move |request| {
+        let request = request.json();
+        assert_eq!(request["voice"], "nova");
+
+        let input = request["input"].as_str().unwrap().to_string();
+        let chars = input.chars().count();
+        let body = match request["response_format"].as_str().unwrap() {
+            "pcm" => pcm(chars),
+            "wav" => wav(chars),
+            "mp3" => mp3(chars),
+            "opus" => opus(chars, chars as u32),
+            format => panic!("unexpected format {format}"),
+        };
+        served.lock().unwrap().push(input);
+        Response::new("200 OK", "application/octet-stream", body)
+    });
+    (url, inputs)
+}
+
+const TEXT: &str = "The first paragraph has two sentences. This is the second one!\n\n\
+                    A short paragraph.\n\nThe last paragraph is rather long and will be split between its words.";
+
+#[test]
+fn split_prefers_paragraphs_and_sentences() {
+    let split = |max: usize, text: &str| -> Vec<String> {
+        SpeechBatchOptions::default()
+            .with_max_chars(max)
+            .split(text)
+            .into_iter()
+            .map(|range| text[range].to_string())
+            .collect()
+    };
+    assert_eq!(
+        split(90, TEXT),
+        [
+            "The first paragraph has two sentences. This is the second one!\n\nA short paragraph.",
+            "The last paragraph is rather long and will be split between its words."
+        ]
+    );
+    assert_eq!(
+        split(40, TEXT),
+        [
+            "The first paragraph has two sentences.",
+            "This is the second one!",
+            "A short paragraph.",
+            "The last paragraph is rather long and",
+            "will be split between its words."
+        ]
+    );
+    assert_eq!(split(4, "abcdefghij"), ["abcd", "efgh", "ij"]);
+    assert_eq!(split(6, "第一句。第二句。"), ["第一句。", "第二句。"]);
+    assert!(split(10, " \n\n ").is_empty());
+}
+
+#[tokio::test]
+async fn speech_long_joins_segments() {
+    let (url, inputs) = serve();
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let options = SpeechBatchOptions::default()
+        .with_max_chars(40)
+        .with_requests(BatchRequestOptions::default().with_concurrency(3));
+
+    for format in [
+        SpeechResponseFormat::Pcm,
+        SpeechResponseFormat::Wav,
+        SpeechResponseFormat::Mp3,
+        SpeechResponseFormat::Opus,
+    ] {
+        let request = CreateSpeechRequestArgs::default()
+            .input(TEXT)
+            .voice(Voice::Nova)
+            .response_format(format)
+            .build()
+            .unwrap();
+        let response = client.audio().speech_long(request, &options).await.unwrap();
+
+        assert_eq!(response.response_format, format);
+        assert_eq!(response.segments.len(), 5);
+        let mut offset = Duration::ZERO;
+        for (index, segment) in response.segments.iter().enumerate() {
+            assert_eq!(segment.index, index);
+            assert_eq!(&TEXT[segment.range.clone()], segment.text);
+            assert_eq!(segment.offset, offset);
+            offset += segment.duration;
+        }
+        assert_eq!(response.duration(), offset);
+
+        let chars: usize = response
+            .segments
+            .iter()
+            .map(|segment| segment.text.chars().count())
+            .sum();
+        let joined = duration(format, &response.bytes).unwrap();
+        let expected = match format {
+            SpeechResponseFormat::Mp3 => Duration::from_secs_f64(chars as f64 * 1152.0 / 44_100.0),
+            // the pre-skip of 312 samples is only dropped at the start of the joined stream
+            SpeechResponseFormat::Opus => {
+                Duration::from_millis(chars as u64 * 10)
+                    + Duration::from_secs_f64(4.0 * 312.0 / 48_000.0)
+            }
+            _ => Duration::from_millis(chars as u64 * 10),
+        };
+        assert!(
+            joined.abs_diff(expected) < Duration::from_millis(1),
+            "{format:?}: {joined:?} != {expected:?}"
+        );
+        assert!(joined.abs_diff(response.duration()) < Duration::from_millis(1));
+    }
+
+    let mut inputs = inputs.lock().unwrap().clone();
+    assert_eq!(inputs.len(), 20);
+    inputs.dedup();
+    assert_eq!(inputs.len(), 20);
+}
+
+#[test]
+fn joined_containers() {
+    let joined = concat(SpeechResponseFormat::Wav, &[wav(2), wav(3)]).unwrap();
+    let parsed = Wav::parse(&joined).unwrap();
+    assert_eq!(parsed.data, 44..joined.len());
+    assert_eq!(
+        u32::from_le_bytes(joined[4..8].try_into().unwrap()) as usize,
+        joined.len() - 8
+    );
+
+    // the tags and Xing frames of all parts are dropped
+    let joined = concat(SpeechResponseFormat::Mp3, &[mp3(2), mp3(3)]).unwrap();
+    assert_eq!(joined.len(), 5 * 417);
+
+    let joined = concat(SpeechResponseFormat::Opus, &[opus(4, 1), opus(6, 2)]).unwrap();
+    let mut pages = vec![];
+    let mut position = 0;
+    while position < joined.len() {
+        let page = &joined[position..];
+        let segments = page[26] as usize;
+        let len = 27
+            + segments
+            + page[27..27 + segments]
+                .iter()
+                .map(|&s| s as usize)
+                .sum::<usize>();
+        let mut zeroed = page[..len].to_vec();
+        zeroed[22..26].fill(0);
+        assert_eq!(
+            ogg_crc(&zeroed),
+            u32::from_le_bytes(page[22..26].try_into().unwrap())
+        );
+        pages.push((
+            page[5],
+            u64::from_le_bytes(page[6..14].try_into().unwrap()),
+            u32::from_le_bytes(page[14..18].try_into().unwrap()),
+            u32::from_le_bytes(page[18..22].try_into().unwrap()),
+        ));
+        position += len;
+    }
+    let flags: Vec<u8> = pages.iter().map(|page| page.0).collect();
+    assert_eq!(flags, [0x02, 0, 0, 0, 0, 0x04]);
+    let granules: Vec<u64> = pages.iter().map(|page| page.1).collect();
+    assert_eq!(granules, [0, 0, 1272, 2232, 2232 + 1752, 2232 + 3192]);
+    assert!(pages.iter().all(|page| page.2 == 1));
+    assert!(pages
+        .iter()
+        .enumerate()
+        .all(|(index, page)| page.3 == index as u32));
+
+    assert!(concat(SpeechResponseFormat::Flac, &[[0u8; 4]]).is_err());
+    assert!(duration(SpeechResponseFormat::Wav, b"RIFF").is_err());
+}
@@ -0,0 +1,147 @@
+//! Streaming of speech, against a local server sending the audio in small writes.
+use std::time::Duration;
+
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::stream::{
+    pcm_duration, pcm_samples, pcm_to_f32, pcm_to_i16, OpenAIStreamExt,
+};
+use async_openai_wasm::types::{
+    CreateSpeechRequestArgs, SpeechModel, SpeechResponseFormat, SpeechStreamFormat,
+};
+use async_openai_wasm::Client;
+use base64::engine::{general_purpose, Engine};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::json;
+
+mod common;
+use common::Response;
+
+const AUDIO: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Serves `/audio/speech` with [AUDIO], as raw bytes or as events of 5 bytes depending on `stream_format`.
+/// Inputs starting with `fail` are answered with an API error.
+fn serve() -> String {
+    common::serve(|request| {
+        let request = request.json();
+        if request["input"].as_str().unwrap().starts_with("fail") {
+            return Response::error(
+                "400 Bad Request",
+                json!({"error": {
+                    "message": "Invalid voice", "type": "invalid_request_error", "param": "voice", "code": null
+                }}),
+            );
+        }
+
+        let sse = request["stream_format"] == "sse";
+        let mut parts: Vec<Vec<u8>> = AUDIO
+            .chunks(5)
+            .map(|chunk| {
+                if sse {
+                    let event = json!({"type": "speech.audio.delta", "audio": general_purpose::STANDARD.encode(chunk)});
+                    format!("data: {event}\n\n").into_bytes()
+                } else {
+                    chunk.to_vec()
+                }
+            })
+            .collect();
+        if sse {
+            let done = json!({"type": "speech.audio.done", "usage": {"input_tokens": 3, "output_tokens": 7, "total_tokens": 10}});
+            parts.push(format!("data: {done}\n\n").into_bytes());
+            Response::chunked("text/event-stream", parts)
+        } else {
+            Response::chunked("audio/pcm", parts)
+        }
+    })
+}
+
+#[tokio::test]
+async fn speech_stream_audio_and_sse() {
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(serve()));
+
+    let request = CreateSpeechRequestArgs::default()
+        .input("hello")
+        .response_format(SpeechResponseFormat::Pcm)
+        .build()
+        .unwrap();
+    let chunks: Vec<_> = client
+        .audio()
+        .speech_stream(request)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(chunks.concat(), AUDIO);
+
+    let request = CreateSpeechRequestArgs::default()
+        .input("hello")
+        .model(SpeechModel::Gpt4oMiniTts)
+        .instructions("Speak cheerfully.")
+        .stream_format(SpeechStreamFormat::Sse)
+        .build()
+        .unwrap();
+    let chunks: Vec<_> = client
+        .audio()
+        .speech_stream(request)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(chunks.len(), 8);
+    assert_eq!(chunks.concat(), AUDIO);
+
+    // frames of 4 samples, the last with the remaining 2 samples
+    let request = CreateSpeechRequestArgs::default()
+        .input("hello")
+        .build()
+        .unwrap();
+    let frames: Vec<_> = client
+        .audio()
+        .speech_stream(request)
+        .await
+        .unwrap()
+        .pcm_frames(4)
+        .try_collect()
+        .await
+        .unwrap();
+    let sizes: Vec<usize> = frames.iter().map(|frame| frame.len()).collect();
+    assert_eq!(sizes, [8, 8, 8, 8, 4]);
+    assert_eq!(frames.concat(), AUDIO);
+}
+
+#[tokio::test]
+async fn speech_stream_api_error() {
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(serve()));
+    let request = CreateSpeechRequestArgs::default()
+        .input("fail")
+        .build()
+        .unwrap();
+    let error = client.audio().speech_stream(request).await.err().unwrap();
+    assert!(
+        matches!(error, OpenAIError::ApiError(error) if error.param.as_deref() == Some("voice"))
+    );
+}
+
+#[tokio::test]
+async fn pcm_frames_and_samples() {
+    let chunks = vec![
+        Ok(vec![1u8, 0, 2]),
+        Ok(vec![0, 255, 127, 0]),
+        Ok(vec![128, 9]),
+    ];
+    let frames: Vec<_> = stream::iter(chunks).pcm_frames(2).collect().await;
+    assert_eq!(frames.len(), 3);
+    let samples = pcm_to_i16(frames[0].as_ref().unwrap());
+    assert_eq!(samples, [1, 2]);
+    assert_eq!(
+        pcm_to_i16(frames[1].as_ref().unwrap()),
+        [i16::MAX, i16::MIN]
+    );
+    assert!(matches!(frames[2], Err(OpenAIError::StreamError(_))));
+
+    assert_eq!(pcm_to_f32(&[0, 128, 0, 64]), [-1.0, 0.5]);
+    assert_eq!(pcm_samples(Duration::from_millis(20)), 480);
+    assert_eq!(pcm_duration(48_000), Duration::from_secs(1));
+}
@@ -0,0 +1,103 @@
+//! Query and response types of the stored chat completions endpoints.
+use std::collections::HashMap;
+
+use async_openai_wasm::types::{
+    ListChatCompletionMessagesQueryArgs, ListChatCompletionMessagesResponse,
+    ListChatCompletionsQueryArgs, ListChatCompletionsResponse, ListOrder, Role,
+};
+
+fn query_string<Q: serde::Serialize>(query: &Q) -> String {
+    let request = reqwest::Client::new()
+        .get("https://api.openai.com/v1/chat/completions")
+        .query(query)
+        .build()
+        .unwrap();
+    request.url().query().unwrap_or_default().to_string()
+}
+
+#[test]
+fn list_query_flattens_metadata_filters() {
+    let query = ListChatCompletionsQueryArgs::default()
+        .model("gpt-4o")
+        .metadata(HashMap::from([
+            ("user".to_string(), "42".to_string()),
+            ("env".to_string(), "prod".to_string()),
+        ]))
+        .limit(10u32)
+        .order(ListOrder::Desc)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        query_string(&query),
+        "model=gpt-4o&metadata%5Benv%5D=prod&metadata%5Buser%5D=42&limit=10&order=desc"
+    );
+
+    let query = ListChatCompletionMessagesQueryArgs::default()
+        .after("msg_1")
+        .build()
+        .unwrap();
+    assert_eq!(query_string(&query), "after=msg_1");
+}
+
+#[test]
+fn list_responses_deserialize() {
+    let completions: ListChatCompletionsResponse = serde_json::from_str(
+        r#"{
+            "object": "list",
+            "data": [{
+                "object": "chat.completion",
+                "id": "chatcmpl-1",
+                "model": "gpt-4o-2024-08-06",
+                "created": 1738960610,
+                "request_id": "req_1",
+                "tool_choice": null,
+                "usage": {"total_tokens": 31, "completion_tokens": 18, "prompt_tokens": 13},
+                "seed": 42,
+                "top_p": 1.0,
+                "temperature": 1.0,
+                "presence_penalty": 0.0,
+                "frequency_penalty": 0.0,
+                "system_fingerprint": "fp_1",
+                "metadata": {"user": "42"},
+                "choices": [{
+                    "index": 0,
+                    "message": {"content": "Mind of a bee", "role": "assistant", "tool_calls": null, "function_call": null, "refusal": null},
+                    "finish_reason": "stop",
+                    "logprobs": null
+                }]
+            }],
+            "first_id": "chatcmpl-1",
+            "last_id": "chatcmpl-1",
+            "has_more": false
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(completions.data[0].id, "chatcmpl-1");
+    assert_eq!(
+        completions.data[0].choices[0].message.content.as_deref(),
+        Some("Mind of a bee")
+    );
+
+    let messages: ListChatCompletionMessagesResponse = serde_json::from_str(
+        r#"{
+            "object": "list",
+            "data": [{
+                "id": "chatcmpl-1-0",
+                "role": "user",
+                "content": "write a haiku about ai",
+                "name": null,
+                "content_parts": null
+            }],
+            "first_id": "chatcmpl-1-0",
+            "last_id": "chatcmpl-1-0",
+            "has_more": false
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(messages.data[0].role, Role::User);
+    assert_eq!(
+        messages.data[0].content.as_deref(),
+        Some("write a haiku about ai")
+    );
+}
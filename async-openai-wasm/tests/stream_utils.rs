@@ -0,0 +1,136 @@
+//! Runtime-independent stream adapters: text deltas, coalescing, idle timeout and tee.
+use std::time::Duration;
+
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::stream::{ChatCompletionStreamExt, OpenAIStreamExt};
+use async_openai_wasm::types::CreateChatCompletionStreamResponse;
+use futures::{stream, StreamExt};
+use futures_timer::Delay;
+use serde_json::json;
+
+fn chunk(
+    index: u32,
+    content: Option<&str>,
+) -> Result<CreateChatCompletionStreamResponse, OpenAIError> {
+    Ok(serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion.chunk",
+        "created": 1,
+        "model": "gpt-4o",
+        "choices": [{
+            "index": index,
+            "delta": {"content": content},
+            "finish_reason": null,
+            "logprobs": null
+        }]
+    }))
+    .unwrap())
+}
+
+fn texts(texts: &[&str]) -> Vec<Result<String, OpenAIError>> {
+    texts.iter().map(|text| Ok(text.to_string())).collect()
+}
+
+#[tokio::test]
+async fn text_yields_first_choice_content() {
+    let text: Vec<String> = stream::iter(vec![
+        chunk(0, None),
+        chunk(0, Some("Hello")),
+        chunk(1, Some("ignored")),
+        chunk(0, Some("")),
+        chunk(0, Some(" world")),
+    ])
+    .text()
+    .map(Result::unwrap)
+    .collect()
+    .await;
+    assert_eq!(text, ["Hello", " world"]);
+}
+
+#[tokio::test]
+async fn coalesce_by_size_and_at_end() {
+    let batches: Vec<String> = stream::iter(texts(&["ab", "cd", "é", "f", "g"]))
+        .coalesce(Duration::from_secs(60), 4)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(batches, ["abcd", "éfg"]);
+}
+
+#[tokio::test]
+async fn coalesce_by_interval() {
+    let delayed = stream::iter(texts(&["a", "b"])).chain(stream::once(async {
+        Delay::new(Duration::from_millis(200)).await;
+        Ok("c".to_string())
+    }));
+    let batches: Vec<String> = delayed
+        .coalesce(Duration::from_millis(20), 100)
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(batches, ["ab", "c"]);
+}
+
+#[tokio::test]
+async fn coalesce_flushes_before_error() {
+    let mut items = texts(&["a", "b"]);
+    items.push(Err(OpenAIError::StreamError("boom".into())));
+    let batches: Vec<Result<String, OpenAIError>> = stream::iter(items)
+        .coalesce(Duration::from_secs(60), 100)
+        .collect()
+        .await;
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].as_deref().unwrap(), "ab");
+    assert!(matches!(&batches[1], Err(OpenAIError::StreamError(message)) if message == "boom"));
+}
+
+#[tokio::test]
+async fn idle_timeout_errors_and_ends() {
+    let items: Vec<Result<String, OpenAIError>> = stream::iter(texts(&["a"]))
+        .chain(stream::pending())
+        .idle_timeout(Duration::from_millis(20))
+        .collect()
+        .await;
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_deref().unwrap(), "a");
+    assert!(matches!(items[1], Err(OpenAIError::StreamError(_))));
+
+    let items: Vec<Result<String, OpenAIError>> = stream::iter(texts(&["a", "b"]))
+        .idle_timeout(Duration::from_millis(20))
+        .collect()
+        .await;
+    assert_eq!(items.len(), 2);
+}
+
+#[tokio::test]
+async fn tee_copies_items_and_errors() {
+    let mut items = texts(&["a", "b"]);
+    items.push(Err(OpenAIError::StreamError("boom".into())));
+    let mut copies = stream::iter(items).tee(2);
+    let second = copies.pop().unwrap();
+    let first = copies.pop().unwrap();
+
+    let first: Vec<Result<String, OpenAIError>> = first.collect().await;
+    let second: Vec<Result<String, OpenAIError>> = second.collect().await;
+    for copy in [first, second] {
+        assert_eq!(copy.len(), 3);
+        assert_eq!(copy[0].as_deref().unwrap(), "a");
+        assert_eq!(copy[1].as_deref().unwrap(), "b");
+        assert!(matches!(&copy[2], Err(OpenAIError::StreamError(message)) if message == "boom"));
+    }
+}
+
+#[tokio::test]
+async fn tee_consumers_run_concurrently() {
+    let copies = stream::iter(texts(&["a", "b", "c"])).tee(3);
+    let joined: Vec<String> = futures::future::join_all(
+        copies
+            .into_iter()
+            .map(|copy| copy.map(Result::unwrap).collect::<Vec<String>>()),
+    )
+    .await
+    .into_iter()
+    .map(|copy| copy.concat())
+    .collect();
+    assert_eq!(joined, ["abc", "abc", "abc"]);
+}
@@ -0,0 +1,225 @@
+//! Parsing, writing and generation of SubRip and WebVTT subtitles.
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::subtitle::{Cue, Subtitle, SubtitleOptions};
+use async_openai_wasm::types::{
+    AudioInput, CreateTranscriptionRequestArgs, CreateTranscriptionResponseVerboseJson,
+};
+use async_openai_wasm::Client;
+use serde_json::json;
+
+mod common;
+use common::{Request, Response};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn texts(subtitle: &Subtitle) -> Vec<&str> {
+    subtitle.cues.iter().map(|cue| cue.text.as_str()).collect()
+}
+
+fn verbose_json(value: serde_json::Value) -> CreateTranscriptionResponseVerboseJson {
+    serde_json::from_value(value).unwrap()
+}
+
+fn segment(id: i32, start: f32, end: f32, text: &str) -> serde_json::Value {
+    json!({
+        "id": id, "seek": 0, "start": start, "end": end, "text": text, "tokens": [],
+        "temperature": 0.0, "avg_logprob": -0.2, "compression_ratio": 1.2, "no_speech_prob": 0.01
+    })
+}
+
+#[test]
+fn parse_and_convert() {
+    let srt = "\u{feff}1\r\n00:00:01,200 --> 00:00:03,050\r\nFirst line\r\nsecond line\r\n\r\n\
+               2\r\n01:02:03,004 --> 01:02:05,000\r\nLater.\r\n";
+    let subtitle = Subtitle::parse_srt(srt).unwrap();
+    assert_eq!(
+        subtitle.cues,
+        [
+            Cue {
+                id: Some("1".into()),
+                ..Cue::new(ms(1200), ms(3050), "First line\nsecond line")
+            },
+            Cue {
+                id: Some("2".into()),
+                ..Cue::new(ms(3_723_004), ms(3_725_000), "Later.")
+            },
+        ]
+    );
+    assert_eq!(subtitle.cues[0].duration(), ms(1850));
+    assert_eq!(
+        subtitle.to_srt(),
+        "1\n00:00:01,200 --> 00:00:03,050\nFirst line\nsecond line\n\n\
+         2\n01:02:03,004 --> 01:02:05,000\nLater.\n\n"
+    );
+    assert_eq!(Subtitle::parse_srt(&subtitle.to_srt()).unwrap(), subtitle);
+
+    let vtt = "WEBVTT - transcript\n\nNOTE a comment\nover two lines\n\nSTYLE\n::cue { color: white }\n\n\
+               00:01.200 --> 00:03.050 align:start position:10%\nFirst line\n\n\
+               intro\n00:00:03.050 --> 00:00:04.000\nSecond\n";
+    let subtitle = Subtitle::parse_vtt(vtt).unwrap();
+    assert_eq!(texts(&subtitle), ["First line", "Second"]);
+    assert_eq!(subtitle.cues[0].id, None);
+    assert_eq!(subtitle.cues[0].start, ms(1200));
+    assert_eq!(subtitle.cues[1].id.as_deref(), Some("intro"));
+    assert_eq!(
+        subtitle.to_vtt(),
+        "WEBVTT\n\n00:00:01.200 --> 00:00:03.050\nFirst line\n\nintro\n00:00:03.050 --> 00:00:04.000\nSecond\n\n"
+    );
+    // SubRip numbers the cues
+    assert!(subtitle
+        .to_srt()
+        .starts_with("1\n00:00:01,200 --> 00:00:03,050\nFirst line\n\n2\n"));
+
+    assert!(Subtitle::parse_srt("").unwrap().cues.is_empty());
+    assert!(Subtitle::parse_vtt("WEBVTT\n").unwrap().cues.is_empty());
+}
+
+#[test]
+fn parse_errors_name_the_line() {
+    let error = |result: Result<Subtitle, OpenAIError>| match result {
+        Err(OpenAIError::InvalidArgument(message)) => message,
+        other => panic!("unexpected {other:?}"),
+    };
+    assert_eq!(
+        error(Subtitle::parse_srt(
+            "1\n00:00:00,000 --> 00:00:01,000\nok\n\n2\n00:00:01 --> 00:00:02,000\nno millis\n"
+        )),
+        "invalid srt subtitles at line 6: invalid timestamp"
+    );
+    assert_eq!(
+        error(Subtitle::parse_srt(
+            "1\n99999999999999999:00:00,000 --> 99999999999999999:00:01,000\noverflow\n"
+        )),
+        "invalid srt subtitles at line 2: invalid timestamp"
+    );
+    assert_eq!(
+        error(Subtitle::parse_srt("1\n\n")),
+        "invalid srt subtitles at line 1: missing timing line"
+    );
+    assert_eq!(
+        error(Subtitle::parse_vtt("00:00.000 --> 00:01.000\nno header\n")),
+        "invalid vtt subtitles at line 1: missing WEBVTT header"
+    );
+    assert!(Subtitle::parse_vtt("WEBVTT\n\n00:00.000 -> 00:01.000\nx\n").is_err());
+}
+
+#[test]
+fn generate_from_segments() {
+    let response = verbose_json(json!({
+        "language": "english", "duration": 12.0, "text": "",
+        "segments": [
+            segment(0, 0.0, 0.4, " Hi."),
+            segment(1, 0.4, 3.0, " How are you doing today?"),
+            segment(2, 3.0, 9.0, " This segment is far too long to be shown in a single cue of two short lines."),
+            segment(3, 9.0, 12.0, "   "),
+        ]
+    }));
+    let options = SubtitleOptions::default().with_max_chars_per_line(20);
+    let subtitle = Subtitle::from_verbose_json(&response, &options);
+    assert_eq!(
+        texts(&subtitle),
+        [
+            // the short first segment is merged into the next
+            "Hi. How are you\ndoing today?",
+            "This segment is far\ntoo long to be shown",
+            "in a single cue of\ntwo short lines.",
+        ]
+    );
+    assert_eq!(subtitle.cues[0].start, ms(0));
+    assert_eq!(subtitle.cues[0].end, ms(3000));
+    // the long segment is shared by characters, and the cues follow each other
+    assert_eq!(subtitle.cues[1].start, ms(3000));
+    assert_eq!(subtitle.cues[1].end, subtitle.cues[2].start);
+    assert!(subtitle.cues[1].end > ms(5900) && subtitle.cues[1].end < ms(6300));
+    assert_eq!(subtitle.cues[2].end, ms(9000));
+
+    // nothing is merged without a minimum duration, and every line is a cue
+    let options = options.with_min_duration(Duration::ZERO).with_max_lines(1);
+    let subtitle = Subtitle::from_verbose_json(&response, &options);
+    assert_eq!(subtitle.cues.len(), 7);
+    assert_eq!(subtitle.cues[0].text, "Hi.");
+    assert_eq!(subtitle.cues[0].end, ms(400));
+
+    let response =
+        verbose_json(json!({"language": "english", "duration": 2.5, "text": " Only text."}));
+    let subtitle = Subtitle::from_verbose_json(&response, &SubtitleOptions::default());
+    assert_eq!(subtitle.cues, [Cue::new(ms(0), ms(2500), "Only text.")]);
+}
+
+#[test]
+fn generate_from_words() {
+    let words: Vec<_> = "the quick brown fox jumps over the lazy dog"
+        .split(' ')
+        .enumerate()
+        .map(
+            |(i, word)| json!({"word": word, "start": i as f32 * 0.5, "end": i as f32 * 0.5 + 0.4}),
+        )
+        .collect();
+    let response = verbose_json(json!({
+        "language": "english", "duration": 5.0, "text": "",
+        "words": words, "segments": [segment(0, 0.0, 5.0, "ignored")]
+    }));
+    let options = SubtitleOptions::default()
+        .with_max_chars_per_line(10)
+        .with_max_lines(2)
+        .with_min_duration(Duration::ZERO);
+    let subtitle = Subtitle::from_verbose_json(&response, &options);
+    assert_eq!(
+        texts(&subtitle),
+        ["the quick\nbrown fox", "jumps over\nthe lazy", "dog"]
+    );
+    assert_eq!(subtitle.cues[0].start, ms(0));
+    assert_eq!(subtitle.cues[0].end, ms(1900));
+    assert_eq!(subtitle.cues[1].start, ms(2000));
+    assert_eq!(subtitle.cues[2].start, ms(4000));
+    assert_eq!(subtitle.cues[2].end, ms(4400));
+    assert!(subtitle
+        .to_vtt()
+        .contains("00:00:04.000 --> 00:00:04.400\ndog\n"));
+}
+
+/// Serves a subtitle file and returns the requests.
+fn serve(body: &'static str) -> (String, Receiver<Request>) {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let url = common::serve(move |request| {
+        sender.lock().unwrap().send(request).unwrap();
+        Response::new("200 OK", "text/plain", body)
+    });
+    (url, receiver)
+}
+
+#[tokio::test]
+async fn transcribe_subtitles() {
+    let request = CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from_vec_u8("audio.mp3".into(), vec![0; 16]))
+        .model("whisper-1")
+        .build()
+        .unwrap();
+
+    let (url, received) = serve("1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n");
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let subtitle = client
+        .audio()
+        .transcribe_srt(request.clone())
+        .await
+        .unwrap();
+    assert_eq!(subtitle.cues[0].end, ms(1500));
+    assert_eq!(subtitle.cues[0].text, "Hello.");
+    let sent = received.recv().unwrap();
+    assert_eq!(sent.field("response_format").as_deref(), Some("srt"));
+
+    let (url, received) = serve("WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello.\n\n");
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let subtitle = client.audio().transcribe_vtt(request).await.unwrap();
+    assert_eq!(subtitle.cues, [Cue::new(ms(0), ms(1500), "Hello.")]);
+    let sent = received.recv().unwrap();
+    assert_eq!(sent.field("response_format").as_deref(), Some("vtt"));
+}
@@ -0,0 +1,159 @@
+#![cfg(feature = "tokenizer")]
+//! Client-side token counting against the counts reported by the API.
+use async_openai_wasm::tokenizer::{image_tokens, Encoding, Tokenizer};
+use async_openai_wasm::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPartImage,
+    ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs, EmbeddingInput,
+    ImageDetail, ImageUrl,
+};
+use serde_json::json;
+
+#[test]
+fn model_encodings() {
+    assert_eq!(
+        Encoding::for_model("gpt-4o-mini"),
+        Some(Encoding::O200kBase)
+    );
+    assert_eq!(Encoding::for_model("o3-mini"), Some(Encoding::O200kBase));
+    assert_eq!(
+        Encoding::for_model("ft:gpt-4o-2024-08-06:org::abc"),
+        Some(Encoding::O200kBase)
+    );
+    assert_eq!(
+        Encoding::for_model("gpt-4-turbo"),
+        Some(Encoding::Cl100kBase)
+    );
+    assert_eq!(
+        Encoding::for_model("text-embedding-3-small"),
+        Some(Encoding::Cl100kBase)
+    );
+    assert_eq!(Encoding::for_model("whisper-1"), None);
+    assert!(Tokenizer::for_model("whisper-1").is_err());
+}
+
+#[test]
+fn encode_and_decode() {
+    let tokenizer = Tokenizer::new(Encoding::Cl100kBase);
+    assert_eq!(tokenizer.encode("hello world"), vec![15339, 1917]);
+    assert_eq!(tokenizer.count("<|endoftext|>"), 7);
+
+    let input = tokenizer.encode_embedding_input(&["hello", "world"]);
+    assert!(matches!(&input, EmbeddingInput::ArrayOfIntegerArray(inputs) if inputs.len() == 2));
+    assert_eq!(
+        tokenizer.decode_embedding_input(&input).unwrap(),
+        vec!["hello", "world"]
+    );
+
+    let text = "hello wörld 🦀";
+    let ranges = tokenizer.token_ranges(text);
+    assert_eq!(&text[ranges[0].clone()], "hello");
+    assert_eq!(ranges.last().unwrap().end, text.len());
+    assert!(ranges.windows(2).all(|pair| pair[0].end == pair[1].start));
+    // the crab is split into several tokens, which end within the character
+    assert_eq!(&text[ranges.last().unwrap().clone()], " 🦀");
+    assert!(ranges.len() < tokenizer.count(text));
+}
+
+#[test]
+fn count_chat_messages() {
+    // matches the prompt_tokens reported for this conversation in the OpenAI cookbook
+    let messages: Vec<ChatCompletionRequestMessage> = serde_json::from_value(json!([
+        {"role": "system", "content": "You are a helpful, pattern-following assistant that translates corporate jargon into plain English."},
+        {"role": "system", "name": "example_user", "content": "New synergies will help drive top-line growth."},
+        {"role": "system", "name": "example_assistant", "content": "Things working well together will increase revenue."},
+        {"role": "system", "name": "example_user", "content": "Let's circle back when we have more bandwidth to touch base on opportunities for increased leverage."},
+        {"role": "system", "name": "example_assistant", "content": "Let's talk later when we're less busy about how to do better."},
+        {"role": "user", "content": "This late pivot means we don't have time to boil the ocean for the client deliverable."}
+    ]))
+    .unwrap();
+    let tokenizer = Tokenizer::for_model("gpt-4").unwrap();
+    assert_eq!(tokenizer.count_message_tokens(&messages), 129);
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4")
+        .messages(messages)
+        .build()
+        .unwrap();
+    assert_eq!(tokenizer.count_request_tokens(&request), 129);
+}
+
+#[test]
+fn count_tool_messages() {
+    let tokenizer = Tokenizer::new(Encoding::O200kBase);
+    let tool_message = |id: &str| -> Vec<ChatCompletionRequestMessage> {
+        serde_json::from_value(json!([{"role": "tool", "tool_call_id": id, "content": "42"}]))
+            .unwrap()
+    };
+    let id = "call_abc123def456";
+    assert_eq!(
+        tokenizer.count_message_tokens(&tool_message(id)),
+        tokenizer.count_message_tokens(&tool_message("")) + tokenizer.count(id)
+    );
+}
+
+#[test]
+fn count_tool_definitions() {
+    let request: async_openai_wasm::types::CreateChatCompletionRequest =
+        serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "What's the weather like in San Francisco?"}],
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_current_weather",
+                    "description": "Get the current weather in a given location",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "location": {"type": "string", "description": "The city and state, e.g. San Francisco, CA"},
+                            "unit": {"type": "string", "description": "The unit of temperature to return", "enum": ["celsius", "fahrenheit"]}
+                        },
+                        "required": ["location"]
+                    }
+                }
+            }]
+        }))
+        .unwrap();
+    let tokenizer = Tokenizer::for_model("gpt-4o").unwrap();
+    let without_tools = tokenizer.count_message_tokens(&request.messages);
+    assert!(tokenizer.count_request_tokens(&request) > without_tools + 40);
+}
+
+#[test]
+fn image_costs() {
+    assert_eq!(image_tokens(4096, 8192, &ImageDetail::Low), 85);
+    // 1024x1024 is scaled to 768x768, 4 tiles
+    assert_eq!(image_tokens(1024, 1024, &ImageDetail::High), 765);
+    // 2048x4096 is scaled to 1024x2048, then 768x1536, 6 tiles
+    assert_eq!(image_tokens(2048, 4096, &ImageDetail::Auto), 1105);
+    // small images are not scaled up
+    assert_eq!(image_tokens(300, 300, &ImageDetail::High), 255);
+}
+
+#[test]
+fn image_messages_use_the_size_of_data_urls() {
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    png.extend(300u32.to_be_bytes());
+    png.extend(300u32.to_be_bytes());
+    png.extend([8, 6, 0, 0, 0]);
+    let message = |image_url: ImageUrl| -> ChatCompletionRequestMessage {
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(vec![ChatCompletionRequestMessageContentPartImage {
+                image_url,
+            }
+            .into()])
+            .build()
+            .unwrap()
+            .into()
+    };
+    let tokenizer = Tokenizer::for_model("gpt-4o").unwrap();
+    let count = |image_url: ImageUrl| tokenizer.count_message_tokens(&[message(image_url)]);
+
+    let data_url = ImageUrl::from_bytes(&png, ImageDetail::High).unwrap();
+    let remote = ImageUrl {
+        url: "https://example.com/image.png".into(),
+        detail: Some(ImageDetail::High),
+    };
+    // the 300x300 image is one tile, the remote one is counted as 1024x1024
+    assert_eq!(count(remote) - count(data_url), 765 - 255);
+}
@@ -0,0 +1,339 @@
+//! Chunked transcription of long audio, against a local server transcribing a word `wN` at every second N.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_openai_wasm::audio_format::{wav_header, Wav};
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::transcription_batch::{TranscriptionBatchOptions, MAX_UPLOAD_BYTES};
+use async_openai_wasm::types::{AudioInput, CreateTranscriptionRequestArgs, TimestampGranularity};
+use async_openai_wasm::Client;
+use serde_json::json;
+
+mod common;
+use common::Response;
+
+const RATE: u32 = 8_000;
+
+/// 16-bit mono noise, silent in the given ranges of seconds.
+fn noise(seconds: f64, silences: &[(f64, f64)]) -> Vec<u8> {
+    let frames = (seconds * RATE as f64) as usize;
+    let mut pcm = Vec::with_capacity(frames * 2);
+    for frame in 0..frames {
+        let time = frame as f64 / RATE as f64;
+        let silent = silences
+            .iter()
+            .any(|(start, end)| *start <= time && time < *end);
+        let sample: i16 = if silent {
+            0
+        } else if frame % 2 == 0 {
+            9000
+        } else {
+            -9000
+        };
+        pcm.extend(sample.to_le_bytes());
+    }
+    pcm
+}
+
+fn wav(pcm: &[u8]) -> Vec<u8> {
+    let mut wav = wav_header(1, RATE, 16, pcm.len() as u32);
+    wav.extend(pcm);
+    wav
+}
+
+#[test]
+fn split_fixed_and_on_silence() {
+    let audio = wav(&noise(20.0, &[(7.2, 7.6)]));
+    let options = TranscriptionBatchOptions::default()
+        .with_chunk_duration(Duration::from_secs(8))
+        .with_overlap(Duration::from_secs(2));
+
+    let chunks = options
+        .clone()
+        .with_split_on_silence(false)
+        .split(&audio)
+        .unwrap();
+    let bounds: Vec<(f64, f64)> = chunks
+        .iter()
+        .map(|chunk| (chunk.start.as_secs_f64(), chunk.end.as_secs_f64()))
+        .collect();
+    assert_eq!(bounds, [(0.0, 8.0), (6.0, 14.0), (12.0, 20.0)]);
+    for chunk in &chunks {
+        let parsed = Wav::parse(&chunk.wav).unwrap();
+        assert_eq!(parsed.sample_rate, RATE);
+        assert_eq!(parsed.duration(), chunk.end - chunk.start);
+    }
+
+    // the first chunk ends in the silence, the others have no silence and end at the chunk duration
+    let chunks = options.split(&audio).unwrap();
+    let end = chunks[0].end.as_secs_f64();
+    assert!((7.2..7.6).contains(&end), "{end}");
+    assert!((chunks[1].start.as_secs_f64() - (end - 2.0)).abs() < 1e-9);
+    assert_eq!(chunks.last().unwrap().end, Duration::from_secs(20));
+    assert!(chunks.windows(2).all(|pair| pair[1].start < pair[0].end));
+
+    // raw PCM in the format of the options
+    let pcm = noise(3.0, &[]);
+    let chunks = TranscriptionBatchOptions::default()
+        .with_pcm_format(2, RATE / 2)
+        .with_chunk_duration(Duration::from_secs(1))
+        .with_overlap(Duration::ZERO)
+        .with_split_on_silence(false)
+        .split(&pcm)
+        .unwrap();
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(Wav::parse(&chunks[0].wav).unwrap().channels, 2);
+    assert!(TranscriptionBatchOptions::default()
+        .split(&[])
+        .unwrap()
+        .is_empty());
+    assert!(TranscriptionBatchOptions::default()
+        .split(b"RIFF....WAVEjunk")
+        .is_err());
+}
+
+/// A WAV file with an extensible `fmt ` chunk whose subformat GUID starts with `subformat`.
+fn extensible_wav(subformat: u16, bits_per_sample: u16, data: &[u8]) -> Vec<u8> {
+    let block_align = bits_per_sample / 8;
+    let mut wav = b"RIFF".to_vec();
+    wav.extend((60 + data.len() as u32).to_le_bytes());
+    wav.extend(b"WAVEfmt ");
+    wav.extend(40u32.to_le_bytes());
+    wav.extend(0xFFFEu16.to_le_bytes());
+    wav.extend(1u16.to_le_bytes());
+    wav.extend(RATE.to_le_bytes());
+    wav.extend((RATE * block_align as u32).to_le_bytes());
+    wav.extend(block_align.to_le_bytes());
+    wav.extend(bits_per_sample.to_le_bytes());
+    // cbSize, valid bits and channel mask
+    wav.extend(22u16.to_le_bytes());
+    wav.extend(bits_per_sample.to_le_bytes());
+    wav.extend(4u32.to_le_bytes());
+    wav.extend(subformat.to_le_bytes());
+    wav.extend(b"\x00\x00\x00\x00\x10\x00\x80\x00\x00\xAA\x00\x38\x9B\x71");
+    wav.extend(b"data");
+    wav.extend((data.len() as u32).to_le_bytes());
+    wav.extend(data);
+    wav
+}
+
+#[test]
+fn split_rejects_samples_other_than_integer_pcm() {
+    let options = TranscriptionBatchOptions::default();
+    let samples: Vec<u8> = [0.5f32, -0.5, 0.0, 0.25]
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .collect();
+
+    // IEEE float samples, format 3
+    let mut float = wav_header(1, RATE, 32, samples.len() as u32);
+    float[20..22].copy_from_slice(&3u16.to_le_bytes());
+    float.extend(&samples);
+    assert!(matches!(
+        options.split(&float),
+        Err(OpenAIError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        options.split(&extensible_wav(3, 32, &samples)),
+        Err(OpenAIError::InvalidArgument(_))
+    ));
+
+    let chunks = options
+        .split(&extensible_wav(1, 16, &noise(1.0, &[])))
+        .unwrap();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].end, Duration::from_secs(1));
+}
+
+#[test]
+fn chunks_stay_under_the_upload_limit() {
+    // 5 minutes of 16-bit stereo at 48 kHz are over 55 MB
+    let pcm = vec![0; 300 * 48_000 * 4];
+    let chunks = TranscriptionBatchOptions::default()
+        .with_pcm_format(2, 48_000)
+        .split(&pcm)
+        .unwrap();
+    assert_eq!(chunks.len(), 3);
+    assert!(chunks
+        .iter()
+        .all(|chunk| chunk.wav.len() <= MAX_UPLOAD_BYTES));
+    assert_eq!(chunks.last().unwrap().end, Duration::from_secs(300));
+}
+
+/// The prompts of the requests with the index of their chunk.
+type Prompts = Arc<Mutex<Vec<(usize, Option<String>)>>>;
+
+/// Transcribes chunks of [noise], whose chunk starts are given by their file names, and returns the prompts of the
+/// requests by chunk.
+fn serve(starts: &'static [f32], end: f32) -> (String, Prompts) {
+    let prompts = Arc::new(Mutex::new(vec![]));
+    let received = prompts.clone();
+    let url = common::serve(move |request| {
+        assert_eq!(
+            request.field("response_format").as_deref(),
+            Some("verbose_json")
+        );
+
+        let body = request.text();
+        let filename = &body[body.find("filename=\"speech-").unwrap() + 17..];
+        let index: usize = filename[..filename.find(".wav").unwrap()].parse().unwrap();
+        received
+            .lock()
+            .unwrap()
+            .push((index, request.field("prompt")));
+
+        let start = starts[index];
+        let chunk_end = (start + 8.0).min(end);
+        let words: Vec<_> = (0..end as usize)
+            .map(|second| second as f32)
+            .filter(|&second| start <= second && second + 0.5 <= chunk_end)
+            .map(|second| {
+                json!({
+                    "word": format!("w{second}"),
+                    "start": second - start,
+                    "end": second - start + 0.5,
+                })
+            })
+            .collect();
+        let text = words
+            .iter()
+            .map(|word| word["word"].as_str().unwrap())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if request.field("model").as_deref() == Some("text-only") {
+            return Response::json(
+                json!({"language": "english", "duration": chunk_end - start, "text": text}),
+            );
+        }
+        let segments: Vec<_> = words
+            .iter()
+            .enumerate()
+            .map(|(id, word)| {
+                json!({
+                    "id": id, "seek": 0, "start": word["start"], "end": word["end"],
+                    "text": format!(" {}", word["word"].as_str().unwrap()), "tokens": [],
+                    "temperature": 0.0, "avg_logprob": -0.2, "compression_ratio": 1.2,
+                    "no_speech_prob": 0.01
+                })
+            })
+            .collect();
+        Response::json(json!({
+            "language": "english", "duration": chunk_end - start, "text": text,
+            "words": words, "segments": segments
+        }))
+    });
+    (url, prompts)
+}
+
+fn words(range: std::ops::Range<usize>) -> String {
+    range.map(|n| format!("w{n}")).collect::<Vec<_>>().join(" ")
+}
+
+#[tokio::test]
+async fn transcribe_long_merges_chunks() {
+    let (url, prompts) = serve(&[0.0, 6.0, 12.0], 20.0);
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let options = TranscriptionBatchOptions::default()
+        .with_chunk_duration(Duration::from_secs(8))
+        .with_overlap(Duration::from_secs(2))
+        .with_split_on_silence(false);
+    let request = CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from_vec_u8(
+            "speech.wav".into(),
+            wav(&noise(20.0, &[])),
+        ))
+        .model("whisper-1")
+        .prompt("Glossary: w")
+        .timestamp_granularities(vec![
+            TimestampGranularity::Word,
+            TimestampGranularity::Segment,
+        ])
+        .build()
+        .unwrap();
+
+    let response = client
+        .audio()
+        .transcribe_long(request.clone(), &options)
+        .await
+        .unwrap();
+    assert_eq!(response.text, words(0..20));
+    assert_eq!(response.duration, 20.0);
+    assert_eq!(response.language, "english");
+    let merged = response.words.unwrap();
+    assert_eq!(merged.len(), 20);
+    for (second, word) in merged.iter().enumerate() {
+        assert_eq!(word.word, format!("w{second}"));
+        assert_eq!(word.start, second as f32);
+        assert_eq!(word.end, second as f32 + 0.5);
+    }
+    let segments = response.segments.unwrap();
+    assert_eq!(segments.len(), 20);
+    assert!(segments
+        .iter()
+        .enumerate()
+        .all(|(id, segment)| segment.id == id as i32 && segment.start == id as f32));
+    assert_eq!(segments[13].seek, 1200);
+
+    // concurrent chunks all get the prompt of the request
+    let mut received = prompts.lock().unwrap().split_off(0);
+    received.sort();
+    assert!(received
+        .iter()
+        .all(|(_, prompt)| prompt.as_deref() == Some("Glossary: w")));
+
+    // the text of every chunk follows the prompt of the request for the next
+    let options = options.with_previous_text_prompt(true);
+    client
+        .audio()
+        .transcribe_long(request.clone(), &options)
+        .await
+        .unwrap();
+    let received = prompts.lock().unwrap().split_off(0);
+    assert_eq!(
+        received,
+        [
+            (0, Some("Glossary: w".to_string())),
+            (1, Some(format!("Glossary: w {}", words(0..8)))),
+            (2, Some(format!("Glossary: w {}", words(6..14)))),
+        ]
+    );
+
+    // only the end of the text fits after a long prompt
+    let glossary = vec!["g"; 146].join(" ");
+    let mut long_prompt = request.clone();
+    long_prompt.prompt = Some(glossary.clone());
+    client
+        .audio()
+        .transcribe_long(long_prompt, &options)
+        .await
+        .unwrap();
+    let received = prompts.lock().unwrap().split_off(0);
+    assert_eq!(received[1].1, Some(format!("{glossary} {}", words(4..8))));
+
+    // without timestamps, the words repeated at the start of a chunk are dropped
+    let mut request = request;
+    request.model = "text-only".into();
+    request.timestamp_granularities = None;
+    let response = client
+        .audio()
+        .transcribe_long(request.clone(), &options)
+        .await
+        .unwrap();
+    assert_eq!(response.text, words(0..20));
+    assert!(response.words.is_none() && response.segments.is_none());
+
+    // a single repeated word may well have been said twice, and is kept
+    let (url, _) = serve(&[0.0, 7.0, 14.0], 20.0);
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    let options = options.with_overlap(Duration::from_secs(1));
+    let response = client
+        .audio()
+        .transcribe_long(request, &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        response.text,
+        format!("{} {} {}", words(0..8), words(7..15), words(14..20))
+    );
+}
@@ -0,0 +1,141 @@
+//! Streaming transcription and the multipart encoding of the newer transcription options.
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+
+use async_openai_wasm::config::OpenAIConfig;
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::types::{
+    AudioInput, CreateTranscriptionRequest, CreateTranscriptionRequestArgs,
+    TranscriptionChunkingStrategy, TranscriptionInclude, TranscriptionServerVad,
+    TranscriptionStreamEvent, TranscriptionUsage,
+};
+use async_openai_wasm::Client;
+use futures::StreamExt;
+
+mod common;
+use common::{Request, Response};
+
+/// Answers every request with `status`, `content_type` and `body`, and returns the requests.
+fn serve(
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+) -> (Client<OpenAIConfig>, Receiver<Request>) {
+    let (sender, receiver) = channel();
+    let sender = Mutex::new(sender);
+    let url = common::serve(move |request| {
+        sender.lock().unwrap().send(request).unwrap();
+        Response::new(status, content_type, body.clone())
+    });
+    let client = Client::with_config(OpenAIConfig::new().with_api_base(url));
+    (client, receiver)
+}
+
+fn request() -> CreateTranscriptionRequest {
+    CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from_vec_u8("audio.mp3".into(), vec![0; 16]))
+        .model("gpt-4o-mini-transcribe")
+        .include(vec![TranscriptionInclude::Logprobs])
+        .chunking_strategy(TranscriptionChunkingStrategy::ServerVad(
+            TranscriptionServerVad {
+                silence_duration_ms: Some(500),
+                threshold: Some(0.6),
+                ..Default::default()
+            },
+        ))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn transcribe_stream_events() {
+    let events = [
+        r#"{"type":"transcript.text.delta","delta":"Hello","logprobs":[{"token":"Hello","logprob":-0.1,"bytes":[72,101,108,108,111]}]}"#,
+        r#"{"type":"transcript.text.delta","delta":" world."}"#,
+        r#"{"type":"transcript.text.done","text":"Hello world.","usage":{"type":"tokens","input_tokens":14,"input_token_details":{"text_tokens":0,"audio_tokens":14},"output_tokens":4,"total_tokens":18}}"#,
+    ];
+    let body: String = events
+        .iter()
+        .map(|event| format!("data: {event}\n\n"))
+        .collect();
+    let (client, received) = serve("200 OK", "text/event-stream", body);
+
+    let mut stream = client.audio().transcribe_stream(request()).await.unwrap();
+    let mut text = String::new();
+    let mut done = None;
+    while let Some(event) = stream.next().await {
+        match event.unwrap() {
+            TranscriptionStreamEvent::TextDelta { delta, logprobs } => {
+                if text.is_empty() {
+                    let logprobs = logprobs.unwrap();
+                    assert_eq!(logprobs[0].token, "Hello");
+                    assert_eq!(logprobs[0].bytes, b"Hello");
+                }
+                text.push_str(&delta);
+            }
+            TranscriptionStreamEvent::TextDone { text, usage, .. } => done = Some((text, usage)),
+        }
+    }
+    let (done_text, usage) = done.unwrap();
+    assert_eq!(text, done_text);
+    assert!(matches!(
+        usage,
+        Some(TranscriptionUsage::Tokens {
+            total_tokens: 18,
+            ..
+        })
+    ));
+
+    let request = received.recv().unwrap();
+    assert_eq!(request.fields("stream"), ["true"]);
+    assert_eq!(request.fields("include[]"), ["logprobs"]);
+    assert_eq!(request.fields("chunking_strategy[type]"), ["server_vad"]);
+    assert_eq!(
+        request.fields("chunking_strategy[silence_duration_ms]"),
+        ["500"]
+    );
+    assert_eq!(request.fields("chunking_strategy[threshold]"), ["0.6"]);
+    assert!(request
+        .fields("chunking_strategy[prefix_padding_ms]")
+        .is_empty());
+}
+
+#[tokio::test]
+async fn transcribe_with_logprobs() {
+    let response = r#"{"text":"Hi.","logprobs":[{"token":"Hi","logprob":-0.02,"bytes":[72,105]},{"token":".","logprob":-0.5,"bytes":[46]}],"usage":{"type":"duration","seconds":2.5}}"#;
+    let (client, received) = serve("200 OK", "application/json", response.into());
+    let mut request = request();
+    request.chunking_strategy = Some(TranscriptionChunkingStrategy::Auto);
+    let response = client.audio().transcribe(request).await.unwrap();
+    assert_eq!(response.text, "Hi.");
+    assert_eq!(response.logprobs.unwrap().len(), 2);
+    assert_eq!(
+        response.usage,
+        Some(TranscriptionUsage::Duration { seconds: 2.5 })
+    );
+
+    let request = received.recv().unwrap();
+    assert!(request.fields("stream").is_empty());
+    assert_eq!(request.fields("chunking_strategy"), ["auto"]);
+}
+
+#[tokio::test]
+async fn transcribe_stream_errors() {
+    let error = r#"{"error":{"message":"stream is not supported for whisper-1","type":"invalid_request_error","param":"stream","code":null}}"#;
+    let (client, _received) = serve("400 Bad Request", "application/json", error.into());
+    match client.audio().transcribe_stream(request()).await {
+        Err(OpenAIError::ApiError(error)) => assert_eq!(error.param.as_deref(), Some("stream")),
+        other => panic!("unexpected {:?}", other.map(|_| ())),
+    }
+
+    let (client, _received) = serve(
+        "200 OK",
+        "text/event-stream",
+        "data: {\"type\":\"transcript.unknown\"}\n\n".into(),
+    );
+    let mut stream = client.audio().transcribe_stream(request()).await.unwrap();
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(OpenAIError::JSONDeserialize(_)))
+    ));
+}
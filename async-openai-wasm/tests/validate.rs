@@ -0,0 +1,133 @@
+//! Client side validation of request parameters.
+use async_openai_wasm::error::OpenAIError;
+use async_openai_wasm::types::{
+    AudioResponseFormat, ChatCompletionRequestUserMessage, CreateChatCompletionRequestArgs,
+    CreateEmbeddingRequestArgs, CreateImageRequestArgs, CreateSpeechRequestArgs,
+    CreateTranscriptionRequestArgs, ImageModel, ImageSize, SpeechModel, SpeechStreamFormat,
+    TranscriptionInclude, Validate,
+};
+use async_openai_wasm::Client;
+
+fn params<R: Validate>(request: &R) -> Vec<String> {
+    request
+        .violations()
+        .into_iter()
+        .map(|detail| detail.param)
+        .collect()
+}
+
+#[test]
+fn chat_ranges_and_cross_field_rules() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4o")
+        .messages([])
+        .temperature(2.5)
+        .frequency_penalty(-3.0)
+        .n(0)
+        .top_logprobs(5)
+        .build()
+        .unwrap();
+    assert_eq!(
+        params(&request),
+        [
+            "messages",
+            "frequency_penalty",
+            "temperature",
+            "n",
+            "top_logprobs"
+        ]
+    );
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4o")
+        .messages([ChatCompletionRequestUserMessage::from("hello").into()])
+        .logprobs(true)
+        .top_logprobs(5)
+        .build()
+        .unwrap();
+    assert!(request.validate().is_ok());
+}
+
+#[test]
+fn image_sizes_depend_on_model() {
+    let request = CreateImageRequestArgs::default()
+        .prompt("a crab")
+        .model(ImageModel::DallE3)
+        .size(ImageSize::S256x256)
+        .n(2)
+        .build()
+        .unwrap();
+    assert_eq!(params(&request), ["n", "size"]);
+
+    let request = CreateImageRequestArgs::default()
+        .prompt("a crab")
+        .size(ImageSize::S1792x1024)
+        .build()
+        .unwrap();
+    assert_eq!(params(&request), ["size"]);
+}
+
+#[test]
+fn speech_speed() {
+    let request = CreateSpeechRequestArgs::default()
+        .input("hello")
+        .speed(4.5)
+        .build()
+        .unwrap();
+    let error = request.validate().unwrap_err();
+    assert!(matches!(&error, OpenAIError::InvalidArguments(details) if details.len() == 1));
+    assert_eq!(
+        error.to_string(),
+        "invalid args: speed: must be between 0.25 and 4, got 4.5"
+    );
+}
+
+#[test]
+fn speech_instructions_and_sse_need_newer_models() {
+    let mut request = CreateSpeechRequestArgs::default()
+        .input("hello")
+        .instructions("whisper")
+        .stream_format(SpeechStreamFormat::Sse)
+        .build()
+        .unwrap();
+    assert_eq!(params(&request), ["instructions", "stream_format"]);
+
+    request.model = SpeechModel::Gpt4oMiniTts;
+    assert!(params(&request).is_empty());
+}
+
+#[test]
+fn transcription_stream_and_logprobs_need_newer_models() {
+    let mut request = CreateTranscriptionRequestArgs::default()
+        .model("whisper-1")
+        .stream(true)
+        .include(vec![TranscriptionInclude::Logprobs])
+        .build()
+        .unwrap();
+    assert_eq!(params(&request), ["stream", "include"]);
+
+    request.model = "gpt-4o-transcribe".into();
+    assert!(params(&request).is_empty());
+    request.response_format = Some(AudioResponseFormat::VerboseJson);
+    assert_eq!(params(&request), ["include"]);
+}
+
+#[tokio::test]
+async fn client_validates_before_sending() {
+    let client = Client::new().with_request_validation(true);
+    let request = CreateEmbeddingRequestArgs::default()
+        .model("text-embedding-ada-002")
+        .input("")
+        .dimensions(256u32)
+        .build()
+        .unwrap();
+    let error = client.embeddings().create(request).await.unwrap_err();
+    match error {
+        OpenAIError::InvalidArguments(details) => {
+            assert_eq!(details.len(), 2);
+            assert_eq!(details[0].param, "input");
+            assert_eq!(details[1].param, "dimensions");
+        }
+        error => panic!("unexpected error {error}"),
+    }
+}
@@ -0,0 +1,126 @@
+//! Vector math on embeddings and the in-memory vector index.
+use async_openai_wasm::types::{CreateEmbeddingResponse, Embedding};
+use async_openai_wasm::vector::{VectorExt, VectorIndex};
+use serde_json::{json, Value};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn vector_math() {
+    let a = Vec::from([3.0, 4.0]);
+    let b = vec![4.0, 3.0];
+    assert!(close(a.dot(&b), 24.0));
+    assert!(close(a.norm(), 5.0));
+    assert!(close(a.cosine_similarity(&b), 24.0 / 25.0));
+    assert!(close(a.cosine_similarity(&[0.0, 0.0]), 0.0));
+    assert!(close(a.dot(&[1.0]), 0.0));
+
+    let normalized = a.normalized();
+    assert!(close(normalized[0], 0.6) && close(normalized[1], 0.8));
+    let mut zero = vec![0.0, 0.0];
+    zero.normalize();
+    assert_eq!(zero, [0.0, 0.0]);
+
+    let truncated = [0.5, 0.5, 0.5, 0.5].truncated(2);
+    assert_eq!(truncated.len(), 2);
+    assert!(close(truncated.norm(), 1.0));
+    assert_eq!([1.0f32, 2.0].truncated(8).len(), 2);
+}
+
+#[test]
+fn embedding_helpers() {
+    let mut a = Embedding {
+        index: 0,
+        object: "embedding".into(),
+        embedding: vec![1.0, 1.0, 1.0, 1.0],
+    };
+    let b = Embedding {
+        embedding: vec![1.0, 1.0, 0.0, 0.0],
+        ..a.clone()
+    };
+    assert!(close(a.cosine_similarity(&b), 2.0 / (2.0 * 2f32.sqrt())));
+    assert!(close(a.dot(&b), 2.0));
+
+    a.truncate(2);
+    assert_eq!(a.embedding.len(), 2);
+    assert!(close(a.embedding.norm(), 1.0));
+}
+
+fn response() -> CreateEmbeddingResponse {
+    serde_json::from_value(json!({
+        "object": "list",
+        "model": "text-embedding-3-small",
+        "data": [
+            {"index": 1, "object": "embedding", "embedding": [0.0, 1.0, 0.0]},
+            {"index": 0, "object": "embedding", "embedding": [1.0, 0.0, 0.0]},
+            {"index": 2, "object": "embedding", "embedding": [0.7, 0.7, 0.0]}
+        ],
+        "usage": {"prompt_tokens": 3, "total_tokens": 3}
+    }))
+    .unwrap()
+}
+
+#[test]
+fn index_search_and_filters() {
+    let mut index = VectorIndex::new();
+    index
+        .add_response(
+            response(),
+            [
+                ("x", json!({"axis": true})),
+                ("y", json!({"axis": true})),
+                ("xy", json!({"axis": false})),
+            ],
+        )
+        .unwrap();
+    assert_eq!(index.len(), 3);
+    assert_eq!(index.dimensions(), 3);
+
+    let hits = index.search(&[1.0, 0.1, 0.0], 2);
+    let ids: Vec<&str> = hits.iter().map(|hit| hit.id).collect();
+    assert_eq!(ids, ["x", "xy"]);
+    assert!(hits[0].score >= hits[1].score);
+
+    let hits = index.search_filtered(&[1.0, 0.1, 0.0], 5, |metadata| metadata["axis"] == true);
+    let ids: Vec<&str> = hits.iter().map(|hit| hit.id).collect();
+    assert_eq!(ids, ["x", "y"]);
+
+    assert!(index.search(&[1.0, 0.0], 1).is_empty());
+    assert!(index.add("bad", [1.0, 0.0], Value::Null).is_err());
+
+    // replacing keeps a single entry
+    index
+        .add("x", [0.0, 0.0, 1.0], json!({"axis": "z"}))
+        .unwrap();
+    assert_eq!(index.len(), 3);
+    assert_eq!(index.search(&[0.0, 0.0, 1.0], 1)[0].id, "x");
+
+    assert_eq!(index.remove("y"), Some(json!({"axis": true})));
+    assert_eq!(index.remove("y"), None);
+    let mut ids: Vec<&str> = index.ids().collect();
+    ids.sort();
+    assert_eq!(ids, ["x", "xy"]);
+    assert_eq!(index.get("xy").unwrap().1, &json!({"axis": false}));
+    assert!(close(index.get("xy").unwrap().0.norm(), 1.0));
+}
+
+#[test]
+fn index_from_response_and_bytes() {
+    let mut index = VectorIndex::try_from(response()).unwrap();
+    assert_eq!(index.search(&[0.0, 1.0, 0.0], 1)[0].id, "1");
+    index.remove("0");
+
+    let bytes = index.to_bytes();
+    let restored = VectorIndex::from_bytes(&bytes).unwrap();
+    assert_eq!(restored, index);
+    assert_eq!(restored.search(&[0.0, 1.0, 0.0], 1)[0].id, "1");
+
+    assert!(VectorIndex::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(VectorIndex::from_bytes(b"nope").is_err());
+    assert_eq!(
+        VectorIndex::from_bytes(&VectorIndex::new().to_bytes()).unwrap(),
+        VectorIndex::new()
+    );
+}
@@ -0,0 +1,164 @@
+//! Web search options and URL citation annotations of the search models.
+use async_openai_wasm::stream::ChatCompletionStreamAccumulator;
+use async_openai_wasm::types::{
+    ChatCompletionResponseMessage, ChatCompletionResponseMessageAnnotation, CitationStyle,
+    CreateChatCompletionRequestArgs, CreateChatCompletionResponse,
+    CreateChatCompletionStreamResponse, WebSearchContextSize, WebSearchLocationArgs,
+    WebSearchOptionsArgs,
+};
+use serde_json::{json, Value};
+
+fn citation(start_index: u32, end_index: u32, url: &str, title: &str) -> Value {
+    json!({
+        "type": "url_citation",
+        "url_citation": {"start_index": start_index, "end_index": end_index, "url": url, "title": title}
+    })
+}
+
+fn message(content: &str, annotations: Vec<Value>) -> ChatCompletionResponseMessage {
+    serde_json::from_value(json!({
+        "role": "assistant",
+        "content": content,
+        "refusal": null,
+        "annotations": annotations
+    }))
+    .unwrap()
+}
+
+#[test]
+fn web_search_options_serialize() {
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4o-search-preview")
+        .messages([])
+        .web_search_options(
+            WebSearchOptionsArgs::default()
+                .search_context_size(WebSearchContextSize::Low)
+                .user_location(
+                    WebSearchLocationArgs::default()
+                        .country("GB")
+                        .city("London")
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap();
+
+    let value = serde_json::to_value(&request).unwrap();
+    assert_eq!(
+        value["web_search_options"],
+        json!({
+            "search_context_size": "low",
+            "user_location": {"type": "approximate", "approximate": {"country": "GB", "city": "London"}}
+        })
+    );
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4o")
+        .messages([])
+        .build()
+        .unwrap();
+    assert!(serde_json::to_value(&request)
+        .unwrap()
+        .get("web_search_options")
+        .is_none());
+}
+
+#[test]
+fn response_annotations_deserialize() {
+    let response: CreateChatCompletionResponse = serde_json::from_value(json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "created": 1,
+        "model": "gpt-4o-search-preview",
+        "choices": [{
+            "index": 0,
+            "message": message("See example.", vec![citation(4, 11, "https://example.com", "Example")]),
+            "finish_reason": "stop",
+            "logprobs": null
+        }]
+    }))
+    .unwrap();
+
+    let message = &response.choices[0].message;
+    let Some(ChatCompletionResponseMessageAnnotation::UrlCitation { url_citation }) =
+        message.annotations.as_ref().and_then(|a| a.first())
+    else {
+        panic!("missing annotation");
+    };
+    assert_eq!(url_citation.url, "https://example.com");
+    assert_eq!((url_citation.start_index, url_citation.end_index), (4, 11));
+}
+
+#[test]
+fn render_appended_citations() {
+    let message = message(
+        "Rust is fast. Rust is safe. Rust is fun.",
+        vec![
+            citation(14, 26, "https://b.example", "B"),
+            citation(0, 12, "https://a.example", "A"),
+            citation(28, 39, "https://a.example", "A"),
+        ],
+    );
+
+    let rendered = message.render_citations(CitationStyle::Append);
+    assert_eq!(
+        rendered.text,
+        "Rust is fast[1]. Rust is safe[2]. Rust is fun[1]."
+    );
+    let urls: Vec<&str> = rendered.sources.iter().map(|s| s.url.as_str()).collect();
+    assert_eq!(urls, ["https://a.example", "https://b.example"]);
+    assert_eq!(
+        rendered.references(),
+        "[1] [A](https://a.example)\n[2] [B](https://b.example)\n"
+    );
+}
+
+#[test]
+fn render_replaced_citations_with_char_indices() {
+    // "é" is two bytes, indices count characters
+    let content = "Café news ([example.com](https://example.com)).";
+    let message = message(
+        content,
+        vec![
+            citation(11, 45, "https://example.com", "Example"),
+            citation(12, 20, "https://overlap.example", "Overlap"),
+            citation(40, 99, "https://out-of-range.example", "Out"),
+        ],
+    );
+
+    let rendered = message.render_citations(CitationStyle::Replace);
+    assert_eq!(rendered.text, "Café news ([1]).");
+    assert_eq!(rendered.sources.len(), 1);
+}
+
+#[test]
+fn accumulator_collects_delta_annotations() {
+    let chunk = |delta: Value| -> CreateChatCompletionStreamResponse {
+        serde_json::from_value(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "gpt-4o-search-preview",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": null, "logprobs": null}]
+        }))
+        .unwrap()
+    };
+
+    let mut accumulator = ChatCompletionStreamAccumulator::new();
+    accumulator.push(&chunk(json!({"role": "assistant", "content": "See "})));
+    accumulator.push(&chunk(json!({
+        "content": "example.",
+        "annotations": [citation(4, 11, "https://example.com", "Example")]
+    })));
+
+    let response = accumulator.response();
+    let message = &response.choices[0].message;
+    assert_eq!(message.url_citations().count(), 1);
+    assert_eq!(
+        message.render_citations(CitationStyle::Append).text,
+        "See example[1]."
+    );
+}